@@ -2082,3 +2082,130 @@ function http_make_request(scheme, url, body, headers) {
     return cid;
 }
 }());
+(function () {
+"use strict";
+
+// Storage for save files and settings. Bytes are kept base64 encoded in localStorage under a miner_ prefix.
+
+function storage_key(key_ptr, key_len) {
+    return "miner_" + UTF8ToString(key_ptr, key_len);
+}
+
+function storage_get(key_ptr, key_len) {
+    try {
+        var encoded = window.localStorage.getItem(storage_key(key_ptr, key_len));
+        if (encoded == null) {
+            return null;
+        }
+        var binary = atob(encoded);
+        var bytes = new Uint8Array(binary.length);
+        for (var i = 0; i < binary.length; i++) {
+            bytes[i] = binary.charCodeAt(i);
+        }
+        return bytes;
+    } catch (e) {
+        console.warn(e);
+        return null;
+    }
+}
+
+function register_plugin(importObject) {
+    importObject.env.miner_storage_len = function (key_ptr, key_len) {
+        var bytes = storage_get(key_ptr, key_len);
+        return bytes == null ? -1 : bytes.length;
+    }
+
+    importObject.env.miner_storage_read = function (key_ptr, key_len, buf_ptr, buf_len) {
+        var bytes = storage_get(key_ptr, key_len);
+        if (bytes == null) {
+            return;
+        }
+        var dest = new Uint8Array(wasm_memory.buffer, buf_ptr, buf_len);
+        dest.set(bytes.subarray(0, buf_len));
+    }
+
+    importObject.env.miner_storage_write = function (key_ptr, key_len, buf_ptr, buf_len) {
+        var src = new Uint8Array(wasm_memory.buffer, buf_ptr, buf_len);
+        var binary = "";
+        for (var i = 0; i < src.length; i++) {
+            binary += String.fromCharCode(src[i]);
+        }
+        try {
+            window.localStorage.setItem(storage_key(key_ptr, key_len), btoa(binary));
+            return 1;
+        } catch (e) {
+            console.warn(e);
+            return 0;
+        }
+    }
+
+    importObject.env.miner_storage_remove = function (key_ptr, key_len) {
+        try {
+            window.localStorage.removeItem(storage_key(key_ptr, key_len));
+            return 1;
+        } catch (e) {
+            console.warn(e);
+            return 0;
+        }
+    }
+}
+
+miniquad_add_plugin({ register_plugin, version: 2, name: "miner_storage" });
+}());
+(function () {
+"use strict";
+
+// Gamepads through the Gamepad API. Only pads with the "standard" mapping are read, their layout is known, so
+// buttons can be handed over in the same order as PadButton in src/input.rs.
+
+var STANDARD_BUTTONS = [
+    0,  // South
+    1,  // East
+    2,  // West
+    3,  // North
+    12, // DPadUp
+    13, // DPadDown
+    14, // DPadLeft
+    15, // DPadRight
+    9,  // Start
+    8,  // Select
+];
+
+function register_plugin(importObject) {
+    // Writes every button as 1 or 0, then left stick x and y. Connected pads are merged, returns 0 if there are none.
+    importObject.env.miner_gamepad_read = function (out_ptr, out_len) {
+        var out = new Float32Array(wasm_memory.buffer, out_ptr, out_len);
+        out.fill(0);
+
+        var pads = navigator.getGamepads ? navigator.getGamepads() : [];
+        var stick = STANDARD_BUTTONS.length;
+        var connected = 0;
+
+        for (var i = 0; i < pads.length; i++) {
+            var pad = pads[i];
+            if (pad == null || !pad.connected || pad.mapping != "standard") {
+                continue;
+            }
+            connected = 1;
+
+            for (var b = 0; b < STANDARD_BUTTONS.length; b++) {
+                var button = pad.buttons[STANDARD_BUTTONS[b]];
+                if (button && button.pressed) {
+                    out[b] = 1;
+                }
+            }
+
+            var x = pad.axes[0] || 0;
+            var y = pad.axes[1] || 0;
+            if (x*x + y*y > out[stick]*out[stick] + out[stick+1]*out[stick+1]) {
+                out[stick] = x;
+                out[stick+1] = y;
+            }
+        }
+
+        return connected;
+    }
+}
+
+miniquad_add_plugin({ register_plugin, version: 1, name: "miner_gamepad" });
+}());
@@ -0,0 +1,575 @@
+// INFO: Reads .aseprite files as they are saved by the editor, so sheets don't have to be exported to PNG and
+// described by hand. Visible layers are flattened into one image per frame and tags become the animations of
+// a Sheet (see manifest), with each frame keeping its own duration. Only what sprites need is read: normal
+// blending (other blend modes are drawn as normal), no tilemaps, no user data, no slices.
+// Format: https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md
+
+use crate::prelude::*;
+
+use crate::asset::{ AssetState };
+use crate::manifest::{ Sheet, SpriteManifest, ManifestAnim };
+use crate::sprite::{ LoopMode };
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_REFERENCE: u16 = 64;
+const HEADER_LAYER_OPACITY_VALID: u32 = 1;
+
+#[derive(Debug)]
+pub enum AsepriteError {
+    BadMagic,
+    Truncated,
+    Unsupported(&'static str),
+    Corrupt(&'static str),
+}
+
+impl std::fmt::Display for AsepriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+        | AsepriteError::BadMagic => write!(f, "not an aseprite file"),
+        | AsepriteError::Truncated => write!(f, "file is truncated"),
+        | AsepriteError::Unsupported(what) => write!(f, "unsupported {}", what),
+        | AsepriteError::Corrupt(reason) => write!(f, "file is corrupt: {}", reason),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TagDirection {
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+#[derive(Debug)]
+pub struct AsepriteTag {
+    pub name: String,
+    // INFO: Frame indices, both inclusive.
+    pub from: usize,
+    pub to: usize,
+    pub direction: TagDirection,
+    // INFO: 0 repeats forever.
+    pub repeat: u16,
+}
+
+#[derive(Debug)]
+pub struct AsepriteFile {
+    pub width: usize,
+    pub height: usize,
+    // INFO: Flattened, row major RGBA, one per frame.
+    pub frames: Vec<Vec<[u8; 4]>>,
+    // INFO: In seconds, one per frame.
+    pub durations: Vec<f32>,
+    pub tags: Vec<AsepriteTag>,
+}
+
+struct Layer {
+    flags: u16,
+    kind: u16,
+    child_level: u16,
+    opacity: u8,
+}
+
+enum CelPixels {
+    Image { width: usize, height: usize, pixels: Vec<u8> },
+    Linked(usize),
+}
+
+struct Cel {
+    layer: usize,
+    pos: IVec2,
+    opacity: u8,
+    pixels: CelPixels,
+}
+
+struct AsepriteReader<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> AsepriteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], AsepriteError> {
+        let bytes = self.bytes.get(self.cursor..self.cursor+count).ok_or(AsepriteError::Truncated)?;
+        self.cursor += count;
+        Ok(bytes)
+    }
+    fn u8(&mut self) -> Result<u8, AsepriteError> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, AsepriteError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    fn i16(&mut self) -> Result<i16, AsepriteError> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    fn u32(&mut self) -> Result<u32, AsepriteError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn str(&mut self) -> Result<String, AsepriteError> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| AsepriteError::Corrupt("invalid string"))
+    }
+}
+
+// INFO: Every pixel as RGBA, whatever the file's color depth is.
+fn to_rgba(pixels: &[u8], depth: u16, palette: &[[u8; 4]], transparent_index: u8) -> Vec<[u8; 4]> {
+    match depth {
+    | 32 => pixels.chunks_exact(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect(),
+    | 16 => pixels.chunks_exact(2).map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]]).collect(),
+    | _  => pixels.iter().map(|index| match *index == transparent_index {
+        | true  => [0, 0, 0, 0],
+        | false => palette.get(*index as usize).copied().unwrap_or([0, 0, 0, 0]),
+        }).collect(),
+    }
+}
+
+// INFO: Straight alpha "over", opacity is the cel's and its layer's together.
+fn blend(dst: &mut [u8; 4], src: [u8; 4], opacity: f32) {
+    let src_a = src[3] as f32 / 255.0 * opacity;
+    if src_a <= 0.0 { return; }
+    let dst_a = dst[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+
+    for i in 0..3 {
+        let color = (src[i] as f32 * src_a + dst[i] as f32 * dst_a * (1.0 - src_a)) / out_a;
+        dst[i] = color.round() as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+pub fn parse_aseprite(bytes: &[u8]) -> Result<AsepriteFile, AsepriteError> {
+    let mut r = AsepriteReader { bytes, cursor: 0 };
+
+    // header :::
+    r.u32()?;
+    if r.u16()? != HEADER_MAGIC { return Err(AsepriteError::BadMagic); }
+    let frame_count = r.u16()? as usize;
+    if frame_count == 0 { return Err(AsepriteError::Corrupt("no frames")); }
+    let width = r.u16()? as usize;
+    let height = r.u16()? as usize;
+    let depth = r.u16()?;
+    if !matches!(depth, 8 | 16 | 32) { return Err(AsepriteError::Unsupported("color depth")); }
+    let header_flags = r.u32()?;
+    r.take(10)?;
+    let transparent_index = r.u8()?;
+    r.take(128 - r.cursor)?;
+
+    let mut layers: Vec<Layer> = Vec::new();
+    let mut palette = vec![[0u8; 4]; 256];
+    let mut tags = Vec::new();
+    let mut durations = Vec::with_capacity(frame_count);
+    let mut cels: Vec<Vec<Cel>> = Vec::with_capacity(frame_count);
+
+    for _ in 0..frame_count {
+        // frame :::
+        let frame_start = r.cursor;
+        let frame_size = r.u32()? as usize;
+        if r.u16()? != FRAME_MAGIC { return Err(AsepriteError::Corrupt("bad frame magic")); }
+        let old_chunk_count = r.u16()? as usize;
+        durations.push(r.u16()? as f32 / 1000.0);
+        r.take(2)?;
+        let chunk_count = match r.u32()? as usize {
+        | 0 => old_chunk_count,
+        | count => count,
+        };
+
+        let mut frame_cels = Vec::new();
+        for _ in 0..chunk_count {
+            let chunk_start = r.cursor;
+            let chunk_size = r.u32()? as usize;
+            let chunk_type = r.u16()?;
+            if chunk_size < 6 { return Err(AsepriteError::Corrupt("chunk too small")); }
+
+            match chunk_type {
+            | CHUNK_LAYER => {
+                let flags = r.u16()?;
+                let kind = r.u16()?;
+                let child_level = r.u16()?;
+                r.take(6)?;
+                let opacity = r.u8()?;
+                layers.push(Layer {
+                    flags,
+                    kind,
+                    child_level,
+                    opacity: if header_flags & HEADER_LAYER_OPACITY_VALID != 0 { opacity } else { 255 },
+                });
+            },
+            | CHUNK_CEL => {
+                let layer = r.u16()? as usize;
+                let pos = ivec2(r.i16()? as i32, r.i16()? as i32);
+                let opacity = r.u8()?;
+                let cel_type = r.u16()?;
+                r.take(7)?;
+
+                let pixels = match cel_type {
+                | 0 | 2 => {
+                    let width = r.u16()? as usize;
+                    let height = r.u16()? as usize;
+                    let data_len = (chunk_start + chunk_size).checked_sub(r.cursor)
+                        .ok_or(AsepriteError::Corrupt("cel chunk too small"))?;
+                    let data = r.take(data_len)?;
+                    let pixels = match cel_type {
+                    | 0 => data.to_vec(),
+                    | _ => miniz_oxide::inflate::decompress_to_vec_zlib(data)
+                        .map_err(|_| AsepriteError::Corrupt("bad compressed cel"))?,
+                    };
+                    if pixels.len() < width * height * depth as usize / 8 {
+                        return Err(AsepriteError::Corrupt("cel is missing pixels"));
+                    }
+                    CelPixels::Image { width, height, pixels }
+                },
+                | 1 => CelPixels::Linked(r.u16()? as usize),
+                | _ => return Err(AsepriteError::Unsupported("tilemap cel")),
+                };
+                frame_cels.push(Cel { layer, pos, opacity, pixels });
+            },
+            | CHUNK_TAGS => {
+                let count = r.u16()?;
+                r.take(8)?;
+                for _ in 0..count {
+                    let from = r.u16()? as usize;
+                    let to = r.u16()? as usize;
+                    let direction = match r.u8()? {
+                    | 0 => TagDirection::Forward,
+                    | 1 => TagDirection::Reverse,
+                    | 2 => TagDirection::PingPong,
+                    | 3 => TagDirection::PingPongReverse,
+                    | _ => return Err(AsepriteError::Unsupported("tag direction")),
+                    };
+                    let repeat = r.u16()?;
+                    r.take(10)?;
+                    let name = r.str()?;
+                    if from > to || to >= frame_count { return Err(AsepriteError::Corrupt("tag out of range")); }
+                    tags.push(AsepriteTag { name, from, to, direction, repeat });
+                }
+            },
+            | CHUNK_PALETTE => {
+                let size = r.u32()? as usize;
+                let first = r.u32()? as usize;
+                let last = r.u32()? as usize;
+                r.take(8)?;
+                if last < first || last >= size { return Err(AsepriteError::Corrupt("bad palette range")); }
+                palette.resize(usize::max(palette.len(), size), [0, 0, 0, 0]);
+                for color in &mut palette[first..=last] {
+                    let flags = r.u16()?;
+                    *color = [r.u8()?, r.u8()?, r.u8()?, r.u8()?];
+                    if flags & 1 != 0 { r.str()?; }
+                }
+            },
+            // INFO: Files from before the new palette chunk only have this one, later files have both.
+            | CHUNK_OLD_PALETTE => {
+                let mut index = 0;
+                for _ in 0..r.u16()? {
+                    index += r.u8()? as usize;
+                    let count = match r.u8()? { 0 => 256, count => count as usize };
+                    for _ in 0..count {
+                        if let Some(color) = palette.get_mut(index) {
+                            *color = [r.u8()?, r.u8()?, r.u8()?, 255];
+                        }
+                        index += 1;
+                    }
+                }
+            },
+            | _ => {},
+            }
+
+            r.cursor = chunk_start;
+            r.take(chunk_size)?;
+        }
+
+        cels.push(frame_cels);
+        r.cursor = frame_start;
+        r.take(frame_size)?;
+    }
+
+    // INFO: A layer shows only if it and every group it is in are visible.
+    let mut visible = Vec::with_capacity(layers.len());
+    let mut parents_visible: Vec<bool> = Vec::new();
+    for layer in &layers {
+        parents_visible.truncate(layer.child_level as usize);
+        let shown = parents_visible.iter().all(|visible| *visible) &&
+            layer.flags & LAYER_VISIBLE != 0 && layer.flags & LAYER_REFERENCE == 0;
+        visible.push(shown);
+        parents_visible.push(shown);
+    }
+
+    let mut frames = Vec::with_capacity(frame_count);
+    for frame_cels in &cels {
+        let mut canvas = vec![[0u8; 4]; width * height];
+
+        for (layer_i, layer) in layers.iter().enumerate() {
+            if !visible[layer_i] || layer.kind != 0 { continue; }
+            let Some(cel) = frame_cels.iter().find(|cel| cel.layer == layer_i) else { continue };
+
+            let image_cel = match cel.pixels {
+            | CelPixels::Linked(frame) => cels.get(frame)
+                .and_then(|linked| linked.iter().find(|other| other.layer == layer_i))
+                .ok_or(AsepriteError::Corrupt("linked cel is missing"))?,
+            | CelPixels::Image { .. } => cel,
+            };
+            let CelPixels::Image { width: cel_width, height: cel_height, ref pixels } = image_cel.pixels else {
+                return Err(AsepriteError::Corrupt("linked cel links to a linked cel"));
+            };
+
+            let opacity = cel.opacity as f32 / 255.0 * layer.opacity as f32 / 255.0;
+            let cel_pixels = to_rgba(pixels, depth, &palette, transparent_index);
+            for y in 0..cel_height {
+                for x in 0..cel_width {
+                    let pos = image_cel.pos + ivec2(x as i32, y as i32);
+                    if pos.x < 0 || pos.y < 0 || pos.x >= width as i32 || pos.y >= height as i32 { continue; }
+                    blend(&mut canvas[pos.y as usize * width + pos.x as usize], cel_pixels[y * cel_width + x], opacity);
+                }
+            }
+        }
+        frames.push(canvas);
+    }
+
+    Ok(AsepriteFile { width, height, frames, durations, tags })
+}
+
+// INFO: Frames side by side, each with the same 1px border the hand made sheets have.
+pub fn sheet_image(file: &AsepriteFile) -> Image {
+    let stride = file.width + 2;
+    let mut image = Image::gen_image_color((stride * file.frames.len()) as u16, (file.height + 2) as u16, BLANK);
+
+    let image_width = image.width as usize;
+    let data = image.get_image_data_mut();
+    for (i, frame) in file.frames.iter().enumerate() {
+        for y in 0..file.height {
+            let row = (y + 1) * image_width + i * stride + 1;
+            data[row..row+file.width].copy_from_slice(&frame[y*file.width..(y+1)*file.width]);
+        }
+    }
+    image
+}
+
+// INFO: One animation per tag. Files without tags get a single animation named "default" with every frame.
+pub fn sheet_manifest(file: &AsepriteFile) -> SpriteManifest {
+    let frame_rect = |i: usize| Rect::new((i * (file.width + 2) + 1) as f32, 1.0, file.width as f32, file.height as f32);
+
+    let default_tag = AsepriteTag {
+        name: "default".to_string(),
+        from: 0,
+        to: file.frames.len().saturating_sub(1),
+        direction: TagDirection::Forward,
+        repeat: 0,
+    };
+    let tags = match file.tags.is_empty() {
+    | true  => std::slice::from_ref(&default_tag),
+    | false => &file.tags[..],
+    };
+
+    let anims = tags.iter().map(|tag| {
+        let mut frames: Vec<usize> = (tag.from..=tag.to).collect();
+        if matches!(tag.direction, TagDirection::Reverse | TagDirection::PingPongReverse) {
+            frames.reverse();
+        }
+
+        let mode = match tag.direction {
+        | TagDirection::PingPong | TagDirection::PingPongReverse => LoopMode::PingPong,
+        | _ if tag.repeat == 1 => LoopMode::Once,
+        | _ => LoopMode::Loop,
+        };
+
+        ManifestAnim {
+            name: tag.name.clone(),
+            line: 0,
+            mode,
+            frames: frames.iter().map(|i| frame_rect(*i)).collect(),
+            durations: frames.iter().map(|i| file.durations[*i]).collect(),
+        }
+    }).collect();
+
+    SpriteManifest { anims }
+}
+
+// INFO: None for a broken file, the caller carries on with the png and its manifest instead.
+pub fn load_aseprite_sheet(state: &mut AssetState, path: &'static str, bytes: &[u8]) -> Option<Sheet> {
+    let file = match parse_aseprite(bytes) {
+    | Ok(file) => file,
+    | Err(err) => {
+        warn!("Couldn't load {}, using the png instead: {}", path, err);
+        return None;
+    },
+    };
+
+    let image = state.add_atlas_image(path, Some(sheet_image(&file)));
+    Some(Sheet { path, image, manifest: sheet_manifest(&file) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // INFO: Writes just enough of the format for the tests, RGBA only.
+    struct AsepriteWriter {
+        frames: Vec<(u16, Vec<Vec<u8>>)>,
+    }
+
+    fn chunk(chunk_type: u16, body: &[u8]) -> Vec<u8> {
+        let mut bytes = ((body.len() + 6) as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&chunk_type.to_le_bytes());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    fn layer(name: &str, visible: bool) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&(visible as u16).to_le_bytes());
+        body.extend_from_slice(&[0; 10]);
+        body.push(255);
+        body.extend_from_slice(&[0; 3]);
+        body.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        body.extend_from_slice(name.as_bytes());
+        chunk(CHUNK_LAYER, &body)
+    }
+
+    fn cel(layer: u16, pos: IVec2, cel_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&layer.to_le_bytes());
+        body.extend_from_slice(&(pos.x as i16).to_le_bytes());
+        body.extend_from_slice(&(pos.y as i16).to_le_bytes());
+        body.push(255);
+        body.extend_from_slice(&cel_type.to_le_bytes());
+        body.extend_from_slice(&[0; 7]);
+        body.extend_from_slice(data);
+        chunk(CHUNK_CEL, &body)
+    }
+
+    fn image_cel(layer: u16, pos: IVec2, size: IVec2, color: [u8; 4], compressed: bool) -> Vec<u8> {
+        let pixels: Vec<u8> = (0..size.x*size.y).flat_map(|_| color).collect();
+        let mut data = Vec::new();
+        data.extend_from_slice(&(size.x as u16).to_le_bytes());
+        data.extend_from_slice(&(size.y as u16).to_le_bytes());
+        match compressed {
+        | true  => data.extend_from_slice(&miniz_oxide::deflate::compress_to_vec_zlib(&pixels, 6)),
+        | false => data.extend_from_slice(&pixels),
+        }
+        cel(layer, pos, if compressed { 2 } else { 0 }, &data)
+    }
+
+    fn tags(tags: &[(&str, u16, u16, u8, u16)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+        body.extend_from_slice(&[0; 8]);
+        for (name, from, to, direction, repeat) in tags {
+            body.extend_from_slice(&from.to_le_bytes());
+            body.extend_from_slice(&to.to_le_bytes());
+            body.push(*direction);
+            body.extend_from_slice(&repeat.to_le_bytes());
+            body.extend_from_slice(&[0; 10]);
+            body.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            body.extend_from_slice(name.as_bytes());
+        }
+        chunk(CHUNK_TAGS, &body)
+    }
+
+    impl AsepriteWriter {
+        fn encode(&self, size: IVec2) -> Vec<u8> {
+            let mut bytes = vec![0u8; 128];
+            bytes[4..6].copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+            bytes[6..8].copy_from_slice(&(self.frames.len() as u16).to_le_bytes());
+            bytes[8..10].copy_from_slice(&(size.x as u16).to_le_bytes());
+            bytes[10..12].copy_from_slice(&(size.y as u16).to_le_bytes());
+            bytes[12..14].copy_from_slice(&32u16.to_le_bytes());
+            bytes[14..18].copy_from_slice(&HEADER_LAYER_OPACITY_VALID.to_le_bytes());
+
+            for (duration_ms, chunks) in &self.frames {
+                let body: Vec<u8> = chunks.concat();
+                bytes.extend_from_slice(&((body.len() + 16) as u32).to_le_bytes());
+                bytes.extend_from_slice(&FRAME_MAGIC.to_le_bytes());
+                bytes.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+                bytes.extend_from_slice(&duration_ms.to_le_bytes());
+                bytes.extend_from_slice(&[0; 2]);
+                bytes.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&body);
+            }
+
+            let len = bytes.len() as u32;
+            bytes[0..4].copy_from_slice(&len.to_le_bytes());
+            bytes
+        }
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+
+    fn test_file() -> Vec<u8> {
+        AsepriteWriter { frames: vec![
+            (100, vec![
+                layer("back", true),
+                layer("front", true),
+                layer("hidden", false),
+                tags(&[("idle", 0, 1, 0, 0), ("hit", 1, 2, 2, 0), ("die", 2, 2, 1, 1)]),
+                image_cel(0, ivec2(0, 0), ivec2(4, 4), RED, false),
+                image_cel(1, ivec2(1, 1), ivec2(2, 2), BLUE, true),
+                image_cel(2, ivec2(0, 0), ivec2(4, 4), GREEN, false),
+            ]),
+            (200, vec![
+                image_cel(0, ivec2(0, 0), ivec2(4, 4), BLUE, true),
+                cel(1, ivec2(0, 0), 1, &0u16.to_le_bytes()),
+            ]),
+            (300, vec![]),
+        ]}.encode(ivec2(4, 4))
+    }
+
+    #[test]
+    fn visible_layers_are_flattened_per_frame() {
+        let file = parse_aseprite(&test_file()).unwrap();
+        assert_eq!((file.width, file.height, file.frames.len()), (4, 4, 3));
+        assert_eq!(file.durations, [0.1, 0.2, 0.3]);
+
+        // INFO: Front covers the middle of back, the hidden layer never shows.
+        assert_eq!(file.frames[0][0], RED);
+        assert_eq!(file.frames[0][5], BLUE);
+        // INFO: The linked cel repeats front from the first frame over the new back.
+        assert_eq!(file.frames[1][0], BLUE);
+        assert_eq!(file.frames[1][6], BLUE);
+        assert_eq!(file.frames[2][0], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn tags_become_animations() {
+        let file = parse_aseprite(&test_file()).unwrap();
+        let manifest = sheet_manifest(&file);
+        let anim = |name: &str| manifest.anims.iter().find(|anim| anim.name == name).unwrap();
+
+        assert_eq!(anim("idle").mode, LoopMode::Loop);
+        assert_eq!(anim("idle").frames, [Rect::new(1.0, 1.0, 4.0, 4.0), Rect::new(7.0, 1.0, 4.0, 4.0)]);
+        assert_eq!(anim("idle").durations, [0.1, 0.2]);
+        assert_eq!(anim("hit").mode, LoopMode::PingPong);
+        assert_eq!(anim("die").mode, LoopMode::Once);
+
+        let image = sheet_image(&file);
+        assert_eq!((image.width, image.height), (18, 6));
+        assert_eq!(image.get_image_data()[18 + 1], RED);
+        assert_eq!(image.get_image_data()[0], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn broken_files_are_rejected() {
+        let bytes = test_file();
+        assert!(matches!(parse_aseprite(&bytes[..100]), Err(AsepriteError::Truncated)));
+        assert!(matches!(parse_aseprite(&bytes[..bytes.len()-8]), Err(AsepriteError::Truncated)));
+        assert!(matches!(parse_aseprite(&[0; 128]), Err(AsepriteError::BadMagic)));
+
+        let no_frames = AsepriteWriter { frames: vec![] }.encode(ivec2(4, 4));
+        assert!(matches!(parse_aseprite(&no_frames), Err(AsepriteError::Corrupt("no frames"))));
+
+        // INFO: A cel chunk that claims to end inside its own header.
+        let mut short_cel = image_cel(0, ivec2(0, 0), ivec2(1, 1), RED, false);
+        short_cel[0..4].copy_from_slice(&20u32.to_le_bytes());
+        let bytes = AsepriteWriter { frames: vec![(100, vec![layer("back", true), short_cel])] }.encode(ivec2(4, 4));
+        assert!(matches!(parse_aseprite(&bytes), Err(AsepriteError::Corrupt("cel chunk too small"))));
+    }
+}
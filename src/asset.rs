@@ -1,24 +1,40 @@
 use macroquad::{audio, prelude::*};
 
-use crate::sprite::{ load_anim, load_sheet_cell, load_sheet_cells, load_sprite, load_three_patch };
-use crate::sprite::{ Offset, Size, RowCol };
+use crate::sprite::{ load_three_patch };
 use crate::sprite::{ SpriteAsset };
 
+use crate::manifest::{ load_sheet };
+
+use crate::aseprite::{ load_aseprite_sheet };
+
+use crate::update::{ DEV_MODE };
+
+use crate::atlas::{ Atlas, AtlasImage, pack_atlas };
+
 use crate::tile::{ load_tile_set };
 use crate::tile::{ TileSetAsset };
 
+use crate::music::{ MusicLayer };
+
+use crate::stereo::{ LoadedSound, load_sound_from_bytes };
+
 pub struct Assets {
-    pub sfx_pickaxe: audio::Sound,
-    pub sfx_coin: audio::Sound,
-    pub sfx_minecart_transfer: audio::Sound,
-    pub sfx_minecart_moving: audio::Sound,
-    pub sfx_minecart_throw: audio::Sound,
-    pub sfx_elevator: audio::Sound,
-    pub sfx_jetpack: audio::Sound,
-    pub sfx_demolisher: audio::Sound,
-    pub sfx_ui_positive: audio::Sound,
-    pub sfx_ui_negative: audio::Sound,
-    pub sfx_soundtrack: audio::Sound,
+    // INFO: Loaded sounds live here, everything else refers to them through SoundAsset handles so the
+    // simulation never has to touch the audio backend.
+    pub sounds: Vec<LoadedSound>,
+
+    pub sfx_pickaxe: SoundAsset,
+    pub sfx_coin: SoundAsset,
+    pub sfx_minecart_transfer: SoundAsset,
+    pub sfx_minecart_moving: SoundAsset,
+    pub sfx_minecart_throw: SoundAsset,
+    pub sfx_elevator: SoundAsset,
+    pub sfx_jetpack: SoundAsset,
+    pub sfx_demolisher: SoundAsset,
+    pub sfx_ui_positive: SoundAsset,
+    pub sfx_ui_negative: SoundAsset,
+    pub sfx_soundtrack: SoundAsset,
+    pub music_layers: [SoundAsset; MusicLayer::LENGTH],
 
     pub ui_title: Box<[SpriteAsset]>,
     pub ui_bg: SpriteAsset,
@@ -84,15 +100,98 @@ pub struct Assets {
 
     pub items: Box<[SpriteAsset]>,
     pub tile_set: TileSetAsset,
+
+    // INFO: Which file every texture and sound came from, for hot_reload. Sprites share the atlas page their
+    // sheet is on, so patching the sheet's region of the page patches all of them.
+    pub texture_sources: Vec<TextureSource>,
+    pub sound_sources: Vec<(&'static str, SoundAsset)>,
+}
+
+// INFO: Region is in pixels of the texture, the whole texture for ones that aren't in the atlas.
+#[derive(Debug, Clone)]
+pub struct TextureSource {
+    pub path: &'static str,
+    pub texture: Texture2D,
+    pub region: Rect,
+}
+
+// INFO: Level is the sound's own loudness relative to the others, players only ever change bus volumes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SoundAsset {
+    pub id: usize,
+    pub level: f32,
+    pub ducks_music: bool,
 }
 
 pub struct AssetState {
     pub asset_id: u64,
+    pub sound_id: usize,
+    // INFO: Headless assets have no window or audio device behind them. Textures are placeholders with a
+    // fixed size and sounds are only handles, which is enough for the simulation.
+    pub headless: bool,
+    pub texture_sources: Vec<TextureSource>,
+    pub sound_sources: Vec<(&'static str, SoundAsset)>,
+    // INFO: See manifest, loading panics with all of them at the end.
+    pub manifest_errors: Vec<String>,
+    pub atlas: Atlas,
+}
+
+// HACK: Big enough for every sheet to fit its frames in the first row, so frame counts and timings match
+// the real textures even though the rects don't.
+const HEADLESS_TEXTURE_SIZE: Vec2 = vec2(4096.0, 4096.0);
+
+impl AssetState {
+    pub fn new(headless: bool) -> Self {
+        AssetState {
+            asset_id: 0,
+            sound_id: 0,
+            headless,
+            texture_sources: Vec::with_capacity(32),
+            sound_sources: Vec::with_capacity(16),
+            manifest_errors: Vec::new(),
+            atlas: Atlas::default(),
+        }
+    }
+    pub fn texture_size(&self, texture: &Texture2D) -> Vec2 {
+        match self.headless {
+        | true  => HEADLESS_TEXTURE_SIZE,
+        | false => texture.size(),
+        }
+    }
+    pub fn set_filter(&self, texture: &Texture2D, filter_mode: FilterMode) {
+        if !self.headless {
+            texture.set_filter(filter_mode);
+        }
+    }
+    pub fn add_atlas_image(&mut self, path: &'static str, image: Option<Image>) -> AtlasImage {
+        let size = match &image {
+        | Some(image) => vec2(image.width as f32, image.height as f32),
+        | None => HEADLESS_TEXTURE_SIZE,
+        };
+        self.atlas.add(path, image, size)
+    }
+}
+
+pub fn headless_texture() -> Texture2D {
+    Texture2D::from_miniquad_texture(miniquad::TextureId::from_raw_id(miniquad::RawId::OpenGl(0)))
+}
+
+fn push_sound(state: &mut AssetState, sounds: &mut Vec<LoadedSound>, sound: Option<LoadedSound>, level: f32, path: &'static str) -> SoundAsset {
+    let handle = SoundAsset { id: state.sound_id, level, ducks_music: false };
+    state.sound_id += 1;
+    if let Some(sound) = sound {
+        debug_assert!(sounds.len() == handle.id);
+        sounds.push(sound);
+    }
+    state.sound_sources.push((path, handle));
+    handle
 }
 
 macro_rules! load_asset_texture {
-    ($path:literal) => {
-        if cfg!(not(target_family = "wasm")) {
+    ($state:expr, $path:literal) => {{
+        let texture = if $state.headless {
+            headless_texture()
+        } else if cfg!(not(target_family = "wasm")) {
             let path = ["asset/", $path, ".png"].join("");
             load_texture(&path).await.expect("Texture should exist")
         } else if cfg!(target_family = "wasm") {
@@ -100,89 +199,184 @@ macro_rules! load_asset_texture {
             Texture2D::from_file_with_format(&bytes[..], None)
         } else {
             unimplemented!();
+        };
+        let size = $state.texture_size(&texture);
+        let region = Rect::new(0.0, 0.0, size.x, size.y);
+        $state.texture_sources.push(TextureSource { path: concat!("asset/", $path, ".png"), texture: texture.clone(), region });
+        texture
+    }}
+}
+
+// INFO: Same as load_asset_texture, but the image waits for pack_atlas to put it on a page.
+macro_rules! load_asset_image {
+    ($state:expr, $path:literal) => {{
+        let image = if $state.headless {
+            None
+        } else if cfg!(not(target_family = "wasm")) {
+            let path = ["asset/", $path, ".png"].join("");
+            Some(load_image(&path).await.expect("Texture should exist"))
+        } else if cfg!(target_family = "wasm") {
+            let bytes = include_bytes!(concat!("../asset/", $path, ".png"));
+            Some(Image::from_file_with_format(&bytes[..], None).expect("Texture should exist"))
+        } else {
+            unimplemented!();
+        };
+        $state.add_atlas_image(concat!("asset/", $path, ".png"), image)
+    }}
+}
+
+// INFO: A texture together with its manifest in data/sprites/. In native dev builds an asset/$path.aseprite
+// next to the png takes its place, tags and all (see aseprite). Other builds go straight to the png, on the
+// web every missing .aseprite would be a failed fetch holding up loading.
+macro_rules! load_asset_sheet {
+    ($state:expr, $path:literal) => {{
+        let aseprite = match !$state.headless && DEV_MODE && cfg!(not(target_family = "wasm")) {
+        | true  => load_file(concat!("asset/", $path, ".aseprite")).await.ok(),
+        | false => None,
+        };
+        match aseprite.and_then(|bytes| load_aseprite_sheet(&mut $state, concat!("asset/", $path, ".aseprite"), &bytes)) {
+        | Some(sheet) => sheet,
+        | None => {
+            let image = load_asset_image!($state, $path);
+            let manifest = include_str!(concat!("../data/sprites/", $path, ".txt"));
+            load_sheet(&mut $state, concat!("data/sprites/", $path, ".txt"), manifest, image)
+        },
         }
-    }
+    }}
 }
 
+fn report_manifest_errors(errors: &[String]) {
+    if errors.is_empty() { return; }
+    panic!("Sprite manifests are broken:\n{}", errors.join("\n"));
+}
+
+// INFO: $panned sounds can be played from somewhere in the world, see stereo.
 macro_rules! load_asset_sound {
-    ($path:literal) => {
-        if cfg!(not(target_family = "wasm")) {
+    ($state:expr, $sounds:expr, $path:literal, $level:expr, $panned:expr) => {{
+        let sound = if $state.headless {
+            None
+        } else if cfg!(not(target_family = "wasm")) {
             let path = ["asset/", $path, ".flac"].join("");
-            audio::load_sound(&path).await.expect("Sound should exist")
+            let bytes = load_file(&path).await.expect("Sound should exist");
+            Some(load_sound_from_bytes(&bytes, $panned).await.expect("Sound should exist"))
         } else if cfg!(target_family = "wasm") {
             let bytes = include_bytes!(concat!("../asset/", $path, ".flac"));
-            audio::load_sound_from_bytes(&bytes[..]).await.expect("Sound should exist")
+            Some(load_sound_from_bytes(&bytes[..], $panned).await.expect("Sound should exist"))
         } else {
             unimplemented!();
-        }
-    }
+        };
+        push_sound(&mut $state, &mut $sounds, sound, $level, concat!("asset/", $path, ".flac"))
+    }}
 }
 
 macro_rules! load_asset_sound_flac {
-    ($path:literal) => {
-        if cfg!(not(target_family = "wasm")) {
+    ($state:expr, $sounds:expr, $path:literal, $level:expr) => {{
+        let sound = if $state.headless {
+            None
+        } else if cfg!(not(target_family = "wasm")) {
             let path = ["asset/", $path, ".flac"].join("");
-            audio::load_sound(&path).await.expect("Sound should exist")
+            Some(LoadedSound::Centered(audio::load_sound(&path).await.expect("Sound should exist")))
         } else if cfg!(target_family = "wasm") {
             let bytes = include_bytes!(concat!("../asset/", $path, ".flac"));
-            audio::load_sound_from_bytes(&bytes[..]).await.expect("Sound should exist")
+            Some(LoadedSound::Centered(audio::load_sound_from_bytes(&bytes[..]).await.expect("Sound should exist")))
         } else {
             unimplemented!();
+        };
+        push_sound(&mut $state, &mut $sounds, sound, $level, concat!("asset/", $path, ".flac"))
+    }}
+}
+
+// INFO: For sounds that don't have to ship, a missing one becomes $fallback at $level. These are fetched at
+// runtime on the web too (from asset/ next to the page), include_bytes! can't be optional.
+macro_rules! load_optional_asset_sound {
+    ($state:expr, $sounds:expr, $path:literal, $level:expr, $fallback:expr) => {{
+        let sound = match $state.headless {
+        | true  => None,
+        | false => audio::load_sound(&["asset/", $path, ".flac"].join("")).await.ok(),
+        };
+        match sound {
+        | Some(sound) => push_sound(&mut $state, &mut $sounds, Some(LoadedSound::Centered(sound)), $level, concat!("asset/", $path, ".flac")),
+        | None => SoundAsset { level: $level, ..$fallback },
         }
-    }
+    }}
 }
 
 pub async fn get_loading_screen_asset() -> SpriteAsset {
-    let tex = load_asset_texture!("loading_screen");
-    let mut state = AssetState { asset_id: 0 };
-    load_sprite(&mut state, &tex, Offset(0, 0), Size(160, 120))
+    let mut state = AssetState::new(false);
+    let loading_screen = load_asset_sheet!(state, "loading_screen");
+    pack_atlas(&mut state);
+    let screen = loading_screen.anim(&mut state, "screen");
+    report_manifest_errors(&state.manifest_errors);
+    screen
 }
 
 pub async fn init_assets() -> Assets {
-    let ui_title_tex = load_asset_texture!("ui_title");
-    let ui_bg_tex = load_asset_texture!("ui_bg");
-    let ui_keys_tex = load_asset_texture!("ui_keys");
-    let ui_button_tex = load_asset_texture!("ui_button");
-    let ui_inventory_bar_tex = load_asset_texture!("ui_inventory_bar");
-    let ui_fuel_bar_tex = load_asset_texture!("ui_fuel_bar");
-    let ui_elevator_arrow = load_asset_texture!("ui_elevator_arrow");
-    let ui_demolisher_arrow = load_asset_texture!("ui_demolisher_arrow");
-    let ui_sound_controls_tex = load_asset_texture!("ui_sound_controls");
-
-    let cracking_tex = load_asset_texture!("cracking");
-    let cant_dig_tex = load_asset_texture!("cant_dig");
-    let coin_tex = load_asset_texture!("coin");
-    let coins_tex = load_asset_texture!("coins");
-
-    let elevator_cage_tex = load_asset_texture!("elevator_cage");
-    let elevator_platform_tex = load_asset_texture!("elevator_platform");
-
-    let player_tex = load_asset_texture!("player");
-    let statue_tex = load_asset_texture!("statue");
-    let signs_tex = load_asset_texture!("signs");
+    load_assets(AssetState::new(false)).await
+}
+
+#[cfg(test)]
+pub async fn init_headless_assets() -> Assets {
+    load_assets(AssetState::new(true)).await
+}
+
+async fn load_assets(mut state: AssetState) -> Assets {
+    let mut sounds = Vec::with_capacity(16);
+
+    let ui_title = load_asset_sheet!(state, "ui_title");
+    let ui_bg = load_asset_sheet!(state, "ui_bg");
+    let ui_keys = load_asset_sheet!(state, "ui_keys");
+    let ui_button = load_asset_image!(state, "ui_button");
+    let ui_inventory_bar = load_asset_sheet!(state, "ui_inventory_bar");
+    let ui_fuel_bar = load_asset_sheet!(state, "ui_fuel_bar");
+    let ui_elevator_arrow = load_asset_sheet!(state, "ui_elevator_arrow");
+    let ui_demolisher_arrow = load_asset_sheet!(state, "ui_demolisher_arrow");
+    let ui_sound_controls = load_asset_sheet!(state, "ui_sound_controls");
+
+    let cracking = load_asset_sheet!(state, "cracking");
+    let cant_dig = load_asset_sheet!(state, "cant_dig");
+    let coin = load_asset_sheet!(state, "coin");
+    let coins = load_asset_sheet!(state, "coins");
+
+    let elevator_cage = load_asset_sheet!(state, "elevator_cage");
+    let elevator_platform = load_asset_sheet!(state, "elevator_platform");
+
+    let player = load_asset_sheet!(state, "player");
+    let statue = load_asset_sheet!(state, "statue");
+    let signs = load_asset_sheet!(state, "signs");
     
-    let minecart_tex = load_asset_texture!("minecart");
-    let rail_tex = load_asset_texture!("rail");
-    let demolisher_tex = load_asset_texture!("demolisher");
+    let minecart = load_asset_sheet!(state, "minecart");
+    let rail = load_asset_sheet!(state, "rail");
+    let demolisher = load_asset_sheet!(state, "demolisher");
     
-    let items_tex = load_asset_texture!("items");
-    let tile_set_tex = load_asset_texture!("tile_set");
-
-    let sfx_pickaxe = load_asset_sound!("pickaxe");
-    let sfx_coin = load_asset_sound!("coin");
-    let sfx_minecart_transfer = load_asset_sound!("minecart_transfer");
-    let sfx_minecart_moving = load_asset_sound!("minecart_moving");
-    let sfx_minecart_throw = load_asset_sound!("minecart_throw");
-    let sfx_elevator = load_asset_sound!("elevator");
-    let sfx_jetpack = load_asset_sound!("jetpack");
-    let sfx_demolisher = load_asset_sound!("demolisher");
-    let sfx_ui_positive = load_asset_sound!("ui_positive");
-    let sfx_ui_negative = load_asset_sound!("ui_negative");
-    let sfx_soundtrack = load_asset_sound_flac!("soundtrack");
-
-    let mut state = AssetState { asset_id: 0 };
-
-    Assets {
+    let items = load_asset_sheet!(state, "items");
+    let tile_set_tex = load_asset_texture!(state, "tile_set");
+
+    pack_atlas(&mut state);
+
+    let sfx_pickaxe = load_asset_sound!(state, sounds, "pickaxe", 0.2, true);
+    let sfx_coin = load_asset_sound!(state, sounds, "coin", 0.15, false);
+    let sfx_minecart_transfer = load_asset_sound!(state, sounds, "minecart_transfer", 0.2, true);
+    let sfx_minecart_moving = load_asset_sound!(state, sounds, "minecart_moving", 0.2, true);
+    let sfx_minecart_throw = load_asset_sound!(state, sounds, "minecart_throw", 0.2, true);
+    let sfx_elevator = load_asset_sound!(state, sounds, "elevator", 0.2, true);
+    let sfx_jetpack = load_asset_sound!(state, sounds, "jetpack", 0.1, false);
+    let sfx_demolisher = SoundAsset { ducks_music: true, ..load_asset_sound!(state, sounds, "demolisher", 0.2, true) };
+    let sfx_ui_positive = load_asset_sound!(state, sounds, "ui_positive", 0.1, false);
+    let sfx_ui_negative = load_asset_sound!(state, sounds, "ui_negative", 0.1, false);
+    let sfx_soundtrack = load_asset_sound_flac!(state, sounds, "soundtrack", 0.4);
+
+    // INFO: Same order as MusicLayer. Without stems the soundtrack just gets a little quieter down in the mine.
+    let music_layers = [
+        load_optional_asset_sound!(state, sounds, "music_overworld", 0.4 , sfx_soundtrack),
+        load_optional_asset_sound!(state, sounds, "music_shallow",   0.35, sfx_soundtrack),
+        load_optional_asset_sound!(state, sounds, "music_deep",      0.3 , sfx_soundtrack),
+        load_optional_asset_sound!(state, sounds, "music_jetpack",   0.4 , sfx_soundtrack),
+        load_optional_asset_sound!(state, sounds, "music_finale",    0.45, sfx_soundtrack),
+    ];
+
+    let assets = Assets {
+        sounds,
+
         sfx_pickaxe,
         sfx_coin,
         sfx_minecart_transfer,
@@ -194,76 +388,77 @@ pub async fn init_assets() -> Assets {
         sfx_ui_positive,
         sfx_ui_negative,
         sfx_soundtrack,
+        music_layers,
 
-        ui_title: load_sheet_cells(&mut state, &ui_title_tex, RowCol(0, 0), 4, Size(132, 32)),
-        ui_bg: load_sprite(&mut state, &ui_bg_tex, Offset(0, 0), Size(270, 190)),
-        ui_keys: load_sheet_cell(&mut state, &ui_keys_tex, RowCol(0, 0), Size(26, 23)),
-        ui_button: load_three_patch(&mut state, &ui_button_tex),
+        ui_title: ui_title.cells(&mut state, "title"),
+        ui_bg: ui_bg.anim(&mut state, "bg"),
+        ui_keys: ui_keys.anim(&mut state, "keys"),
+        ui_button: load_three_patch(&mut state, ui_button),
 
-        ui_inventory_bar_background: load_sheet_cell(&mut state, &ui_inventory_bar_tex, RowCol(0, 0), Size(16, 58)),
-        ui_inventory_bar_frame: load_sheet_cell(&mut state, &ui_inventory_bar_tex, RowCol(1, 0), Size(16, 58)),
-        ui_inventory_bar_frame_full: load_anim(&mut state, &ui_inventory_bar_tex, RowCol(2, 0), 3, Size(16, 58), 150.0),
-        ui_inventory_bar_fill: load_sheet_cell(&mut state, &ui_inventory_bar_tex, RowCol(5, 0), Size(16, 58)),
+        ui_inventory_bar_background: ui_inventory_bar.anim(&mut state, "background"),
+        ui_inventory_bar_frame: ui_inventory_bar.anim(&mut state, "frame"),
+        ui_inventory_bar_frame_full: ui_inventory_bar.anim(&mut state, "frame_full"),
+        ui_inventory_bar_fill: ui_inventory_bar.anim(&mut state, "fill"),
         
-        ui_fuel_bar_background: load_sheet_cell(&mut state, &ui_fuel_bar_tex, RowCol(0, 0), Size(16, 58)),
-        ui_fuel_bar_frame: load_sheet_cell(&mut state, &ui_fuel_bar_tex, RowCol(1, 0), Size(16, 58)),
-        ui_fuel_bar_frame_empty: load_anim(&mut state, &ui_fuel_bar_tex, RowCol(2, 0), 3, Size(16, 58), 150.0),
-        ui_fuel_bar_fill: load_sheet_cell(&mut state, &ui_fuel_bar_tex, RowCol(5, 0), Size(16, 58)),
-
-        ui_elevator_arrow: load_sheet_cells(&mut state, &ui_elevator_arrow, RowCol(0, 0), 2, Size(39, 9)),
-        ui_demolisher_arrow: load_sheet_cell(&mut state, &ui_demolisher_arrow, RowCol(0, 0), Size(47, 9)),
-
-        ui_music_playing: load_sheet_cell(&mut state, &ui_sound_controls_tex, RowCol(0, 0), Size(16, 16)),
-        ui_music_muted: load_sheet_cell(&mut state, &ui_sound_controls_tex, RowCol(1, 0), Size(16, 16)),
-        ui_sound_playing: load_sheet_cell(&mut state, &ui_sound_controls_tex, RowCol(2, 0), Size(16, 16)),
-        ui_sound_muted: load_sheet_cell(&mut state, &ui_sound_controls_tex, RowCol(3, 0), Size(16, 16)),
+        ui_fuel_bar_background: ui_fuel_bar.anim(&mut state, "background"),
+        ui_fuel_bar_frame: ui_fuel_bar.anim(&mut state, "frame"),
+        ui_fuel_bar_frame_empty: ui_fuel_bar.anim(&mut state, "frame_empty"),
+        ui_fuel_bar_fill: ui_fuel_bar.anim(&mut state, "fill"),
+
+        ui_elevator_arrow: ui_elevator_arrow.cells(&mut state, "arrow"),
+        ui_demolisher_arrow: ui_demolisher_arrow.anim(&mut state, "arrow"),
+
+        ui_music_playing: ui_sound_controls.anim(&mut state, "music_playing"),
+        ui_music_muted: ui_sound_controls.anim(&mut state, "music_muted"),
+        ui_sound_playing: ui_sound_controls.anim(&mut state, "sound_playing"),
+        ui_sound_muted: ui_sound_controls.anim(&mut state, "sound_muted"),
         
-        cracking: load_sheet_cells(&mut state, &cracking_tex, RowCol(0, 0), 4, Size(16, 16)),
-        cant_dig: load_sheet_cells(&mut state, &cant_dig_tex, RowCol(0, 0), 3, Size(16, 16)),
-        coin: load_sprite(&mut state, &coin_tex, Offset(0, 0), Size(16, 16)),
-        coins: load_sheet_cells(&mut state, &coins_tex, RowCol(0, 0), 5, Size(32, 32)),
-
-        sign_sell: load_sheet_cell(&mut state, &signs_tex, RowCol(0, 0), Size(36, 15)),
-        sign_sell_here: load_sheet_cell(&mut state, &signs_tex, RowCol(1, 0), Size(36, 15)),
-        sign_descend: load_sheet_cell(&mut state, &signs_tex, RowCol(2, 0), Size(36, 15)),
-        sign_jetpack_hint: load_sheet_cell(&mut state, &signs_tex, RowCol(3, 0), Size(36, 15)),
-
-        player_idle: load_anim(&mut state, &player_tex, RowCol(0, 0), 2, Size(16, 16), 400.0),
-        player_walk: load_anim(&mut state, &player_tex, RowCol(2, 0), 2, Size(16, 16), 150.0),
-        player_hit:  load_anim(&mut state, &player_tex, RowCol(4, 0), 2, Size(16, 16), 150.0),
-        player_jetpack_idle: load_anim(&mut state, &player_tex, RowCol(6, 0), 1, Size(16, 16), 150.0),
-        player_jetpack_move: load_anim(&mut state, &player_tex, RowCol(7, 0), 2, Size(16, 16), 150.0),
-        player_jetpack_hit:  load_anim(&mut state, &player_tex, RowCol(9, 0), 2, Size(16, 16), 150.0),
-
-        statue: load_sheet_cell(&mut state, &statue_tex, RowCol(0, 0), Size(32, 48)),
+        cracking: cracking.cells(&mut state, "cracking"),
+        cant_dig: cant_dig.cells(&mut state, "cant_dig"),
+        coin: coin.anim(&mut state, "coin"),
+        coins: coins.cells(&mut state, "coins"),
+
+        sign_sell: signs.anim(&mut state, "sell"),
+        sign_sell_here: signs.anim(&mut state, "sell_here"),
+        sign_descend: signs.anim(&mut state, "descend"),
+        sign_jetpack_hint: signs.anim(&mut state, "jetpack_hint"),
+
+        player_idle: player.anim(&mut state, "idle"),
+        player_walk: player.anim(&mut state, "walk"),
+        player_hit:  player.anim(&mut state, "hit"),
+        player_jetpack_idle: player.anim(&mut state, "jetpack_idle"),
+        player_jetpack_move: player.anim(&mut state, "jetpack_move"),
+        player_jetpack_hit:  player.anim(&mut state, "jetpack_hit"),
+
+        statue: statue.anim(&mut state, "statue"),
         
-        elevator_cage: load_anim(&mut state, &elevator_cage_tex , RowCol(0, 0), 12, Size(48, 41), 100.0),
-        elevator_platform_idle: load_anim(
-            &mut state, &elevator_platform_tex , RowCol(0, 0), 1, Size(48, 41), 400.0
-        ),
-        elevator_platform_countdown: load_sheet_cells(
-            &mut state, &elevator_platform_tex, RowCol(1, 0), 3, Size(48 , 41)
-        ),
-        elevator_platform_moving: load_anim(
-            &mut state, &elevator_platform_tex , RowCol(4, 0), 4, Size(48, 41), 100.0
-        ),
+        elevator_cage: elevator_cage.anim(&mut state, "cage"),
+        elevator_platform_idle: elevator_platform.anim(&mut state, "idle"),
+        elevator_platform_countdown: elevator_platform.cells(&mut state, "countdown"),
+        elevator_platform_moving: elevator_platform.anim(&mut state, "moving"),
         
-        minecart_idle:   load_anim(&mut state, &minecart_tex, RowCol(0, 0), 1, Size(15 , 16)  , 400.0),
-        minecart_moving: load_anim(&mut state, &minecart_tex, RowCol(1, 0), 4, Size(15 , 16)  , 150.0),
-
-        demolisher_idle: load_anim(&mut state, &demolisher_tex, RowCol(0, 0), 1, Size(52, 10), 400.0),
-        demolisher_working_0: load_anim(&mut state, &demolisher_tex, RowCol(1 , 0), 5, Size(52, 10), 150.0),
-        demolisher_working_1: load_anim(&mut state, &demolisher_tex, RowCol(6 , 0), 5, Size(52, 10), 150.0),
-        demolisher_working_2: load_anim(&mut state, &demolisher_tex, RowCol(11, 0), 5, Size(52, 10), 150.0),
-        demolisher_working_3: load_anim(&mut state, &demolisher_tex, RowCol(16, 0), 5, Size(52, 10), 150.0),
-        demolisher_working_4: load_anim(&mut state, &demolisher_tex, RowCol(21, 0), 5, Size(52, 10), 150.0),
-        demolisher_working_5: load_anim(&mut state, &demolisher_tex, RowCol(26, 0), 5, Size(52, 10), 150.0),
+        minecart_idle:   minecart.anim(&mut state, "idle"),
+        minecart_moving: minecart.anim(&mut state, "moving"),
+
+        demolisher_idle: demolisher.anim(&mut state, "idle"),
+        demolisher_working_0: demolisher.anim(&mut state, "working_0"),
+        demolisher_working_1: demolisher.anim(&mut state, "working_1"),
+        demolisher_working_2: demolisher.anim(&mut state, "working_2"),
+        demolisher_working_3: demolisher.anim(&mut state, "working_3"),
+        demolisher_working_4: demolisher.anim(&mut state, "working_4"),
+        demolisher_working_5: demolisher.anim(&mut state, "working_5"),
         
-        rail_start:      load_sheet_cell(&mut state, &rail_tex, RowCol(0, 0), Size(16 , 9)),
-        rail:            load_sheet_cell(&mut state, &rail_tex, RowCol(1, 0), Size(16 , 9)),
-        rail_diagonal:   load_sheet_cell(&mut state, &rail_tex, RowCol(2, 0), Size(16 , 9)),
+        rail_start:      rail.anim(&mut state, "start"),
+        rail:            rail.anim(&mut state, "rail"),
+        rail_diagonal:   rail.anim(&mut state, "diagonal"),
 
-        items:    load_sheet_cells(&mut state, &items_tex, RowCol(0, 0), 7, Size(16, 16)),
+        items:    items.cells(&mut state, "items"),
         tile_set: load_tile_set(&mut state, &tile_set_tex, ivec2(16, 16)),
-    }
+
+        texture_sources: state.texture_sources,
+        sound_sources: state.sound_sources,
+    };
+
+    report_manifest_errors(&state.manifest_errors);
+    assets
 }
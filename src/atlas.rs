@@ -0,0 +1,178 @@
+// INFO: Sprite sheets are packed into a few big textures (pages) at startup instead of each being a texture of
+// its own. macroquad batches consecutive draws that use the same texture into one draw call, so with every
+// sprite on one page a frame costs a handful of draw calls instead of one per texture switch, which WebGL
+// builds felt the most. Manifests keep describing frames in their sheet's pixels, Sheet::anim moves them to
+// wherever the sheet ended up. The tile set stays a texture of its own, its UVs are baked into chunk meshes.
+
+use crate::prelude::*;
+
+use crate::asset::{ AssetState, TextureSource, headless_texture };
+
+// INFO: In pixels. Anything that runs WebGL handles textures this big, sheets that don't fit get a page of
+// their own.
+pub const PAGE_SIDE: i32 = 2048;
+
+// INFO: Empty pixels between sheets, so filtering at a sheet's edge never picks up its neighbour.
+const PADDING: i32 = 1;
+
+// INFO: Index into Atlas::entries.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AtlasImage(usize);
+
+struct AtlasEntry {
+    path: &'static str,
+    size: Vec2,
+    // INFO: None for headless assets, dropped once it is on its page.
+    image: Option<Image>,
+}
+
+#[derive(Default)]
+pub struct Atlas {
+    entries: Vec<AtlasEntry>,
+    // INFO: Page texture and offset in it for every entry, filled in by pack_atlas.
+    placements: Vec<(Texture2D, Vec2)>,
+}
+
+impl Atlas {
+    pub fn add(&mut self, path: &'static str, image: Option<Image>, size: Vec2) -> AtlasImage {
+        debug_assert!(self.placements.is_empty(), "Images have to be added before the atlas is packed");
+        self.entries.push(AtlasEntry { path, size, image });
+        AtlasImage(self.entries.len() - 1)
+    }
+    pub fn size(&self, image: AtlasImage) -> Vec2 {
+        self.entries[image.0].size
+    }
+    // INFO: Frames in the image's own pixels, returned in pixels of the page it is on.
+    pub fn place(&self, image: AtlasImage, frames: &[Rect]) -> (Texture2D, Vec<Rect>) {
+        let (texture, offset) = self.placements.get(image.0).expect("Atlas should be packed before sprites are made");
+        let frames = frames.iter().map(|frame| frame.offset(*offset)).collect();
+        (texture.clone(), frames)
+    }
+}
+
+// INFO: Shelf packing, tallest first. There are a couple dozen sheets and most are strips of frames, shelves
+// waste little on them. Returns the page and position of every size and how big each page has to be.
+pub fn pack(sizes: &[IVec2], side: i32) -> (Vec<(usize, IVec2)>, Vec<IVec2>) {
+    struct Shelf {
+        page: usize,
+        pos: IVec2,
+        height: i32,
+    }
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|i| (-sizes[*i].y, -sizes[*i].x));
+
+    let mut placements = vec![(0, IVec2::ZERO); sizes.len()];
+    let mut pages: Vec<IVec2> = Vec::new();
+    let mut shelves: Vec<Shelf> = Vec::new();
+    // INFO: Page new shelves go on and where the next one starts.
+    let mut open_page: Option<(usize, i32)> = None;
+
+    for i in order {
+        let size = sizes[i] + IVec2::splat(PADDING);
+        if size.x > side || size.y > side {
+            placements[i] = (pages.len(), IVec2::ZERO);
+            pages.push(sizes[i]);
+            continue;
+        }
+
+        // INFO: Shelves only get lower, so the first one with room across is tall enough.
+        let shelf = match shelves.iter().position(|shelf| shelf.pos.x + size.x <= side && size.y <= shelf.height) {
+        | Some(shelf) => shelf,
+        | None => {
+            let (page, y) = match open_page {
+            | Some((page, y)) if y + size.y <= side => (page, y),
+            | _ => {
+                pages.push(IVec2::ZERO);
+                (pages.len() - 1, 0)
+            },
+            };
+            open_page = Some((page, y + size.y));
+            shelves.push(Shelf { page, pos: ivec2(0, y), height: size.y });
+            shelves.len() - 1
+        },
+        };
+
+        let shelf = &mut shelves[shelf];
+        placements[i] = (shelf.page, shelf.pos);
+        shelf.pos.x += size.x;
+        pages[shelf.page] = pages[shelf.page].max(shelf.pos + ivec2(0, size.y));
+    }
+
+    (placements, pages)
+}
+
+// INFO: Called once every sheet is loaded, before any sprite is made from one.
+pub fn pack_atlas(state: &mut AssetState) {
+    let atlas = &mut state.atlas;
+
+    let placements: Vec<(Texture2D, Vec2)> = match state.headless {
+    | true  => atlas.entries.iter().map(|_| (headless_texture(), Vec2::ZERO)).collect(),
+    | false => {
+        let sizes: Vec<IVec2> = atlas.entries.iter().map(|entry| entry.size.as_ivec2()).collect();
+        let (placements, page_sizes) = pack(&sizes, PAGE_SIDE);
+
+        let mut pages: Vec<Image> = page_sizes.iter()
+            .map(|size| Image::gen_image_color(size.x as u16, size.y as u16, BLANK))
+            .collect();
+
+        for (entry, (page, pos)) in atlas.entries.iter_mut().zip(&placements) {
+            let Some(image) = entry.image.take() else { continue };
+            let page = &mut pages[*page];
+            let page_width = page.width as usize;
+            let width = image.width as usize;
+
+            let data = page.get_image_data_mut();
+            for (y, row) in image.get_image_data().chunks_exact(width).enumerate() {
+                let start = (pos.y as usize + y) * page_width + pos.x as usize;
+                data[start..start+width].copy_from_slice(row);
+            }
+        }
+
+        let textures: Vec<Texture2D> = pages.iter().map(Texture2D::from_image).collect();
+        for texture in &textures {
+            texture.set_filter(FilterMode::Nearest);
+        }
+        info!("Packed {} sheets into {} atlas pages", atlas.entries.len(), textures.len());
+
+        placements.iter().map(|(page, pos)| (textures[*page].clone(), pos.as_vec2())).collect()
+    },
+    };
+
+    for (entry, (texture, offset)) in atlas.entries.iter().zip(&placements) {
+        let region = Rect::new(offset.x, offset.y, entry.size.x, entry.size.y);
+        state.texture_sources.push(TextureSource { path: entry.path, texture: texture.clone(), region });
+    }
+    atlas.placements = placements;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: (usize, IVec2, IVec2), b: (usize, IVec2, IVec2)) -> bool {
+        a.0 == b.0 && a.1.x < b.1.x + b.2.x && b.1.x < a.1.x + a.2.x && a.1.y < b.1.y + b.2.y && b.1.y < a.1.y + a.2.y
+    }
+
+    #[test]
+    fn sheets_are_packed_without_overlapping() {
+        let sizes = [
+            ivec2(30, 10), ivec2(64, 64), ivec2(12, 40), ivec2(40, 12), ivec2(100, 20),
+            ivec2(50, 50), ivec2(8, 8), ivec2(300, 8), ivec2(20, 20), ivec2(64, 30),
+        ];
+        let (placements, pages) = pack(&sizes, 128);
+
+        let rects: Vec<_> = placements.iter().zip(&sizes).map(|((page, pos), size)| (*page, *pos, *size)).collect();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.1.x + a.2.x <= pages[a.0].x && a.1.y + a.2.y <= pages[a.0].y);
+            for b in &rects[i+1..] {
+                assert!(!overlaps(*a, *b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+
+        // INFO: The strip wider than a page gets one of its own, the rest spill onto a second page.
+        assert_eq!(pages.len(), 3);
+        assert!(pages.iter().all(|page| page.y <= 128));
+        assert_eq!(pages[placements[7].0], ivec2(300, 8));
+    }
+}
@@ -0,0 +1,303 @@
+// INFO: Which keys and gamepad buttons drive which InputActions field. Every action has two key slots and one
+// gamepad slot. Bindings are stored as a small text table (same format as data/, see DEFAULT_BINDINGS) under
+// the "bindings" storage key, so they survive restarts on native and web builds alike.
+// Menu navigation (arrows, Enter, d-pad, South/East) is deliberately not rebindable, a broken binding should
+// never lock anyone out of the Controls screen.
+
+use crate::prelude::*;
+
+use crate::data::{ self, DataError };
+use crate::input::{ PadButton, InputState };
+use crate::storage;
+
+const BINDINGS_KEY: &str = "bindings";
+
+const DEFAULT_BINDINGS: &str = "\
+# action        key     key     pad
+MoveLeft        A       Left    DPadLeft
+MoveRight       D       Right   DPadRight
+MoveUp          W       Up      DPadUp
+MoveDown        S       Down    DPadDown
+Interact        E       -       South
+Escape          Escape  -       Start
+ToggleMap       M       -       Select
+ToggleDevMode   Tab     -       -
+";
+
+// INFO: Only these can be bound, anything else pressed while rebinding is ignored. It's also the lookup
+// table for reading key names back.
+const BINDABLE_KEYS: [KeyCode; 75] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G, KeyCode::H, KeyCode::I,
+    KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N, KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R,
+    KeyCode::S, KeyCode::T, KeyCode::U, KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+
+    KeyCode::Kp0, KeyCode::Kp1, KeyCode::Kp2, KeyCode::Kp3, KeyCode::Kp4,
+    KeyCode::Kp5, KeyCode::Kp6, KeyCode::Kp7, KeyCode::Kp8, KeyCode::Kp9,
+
+    KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+
+    KeyCode::Space, KeyCode::Enter, KeyCode::KpEnter, KeyCode::Tab, KeyCode::Escape, KeyCode::Backspace,
+    KeyCode::LeftShift, KeyCode::RightShift, KeyCode::LeftControl, KeyCode::RightControl,
+    KeyCode::LeftAlt, KeyCode::RightAlt,
+
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon, KeyCode::Apostrophe,
+    KeyCode::Minus, KeyCode::Equal, KeyCode::LeftBracket, KeyCode::RightBracket, KeyCode::Backslash,
+    KeyCode::GraveAccent, KeyCode::Insert, KeyCode::Delete,
+];
+
+// INFO: One per InputActions field, same order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InputAction {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Interact,
+    Escape,
+    ToggleMap,
+    ToggleDevMode,
+}
+
+impl InputAction {
+    pub const LENGTH: usize = std::mem::variant_count::<Self>();
+    pub const VARIANTS: [InputAction; Self::LENGTH] = const {
+        let mut variants = [InputAction::MoveLeft; Self::LENGTH];
+
+        let mut i = 0;
+        while i < Self::LENGTH {
+            variants[i] = unsafe { std::mem::transmute::<u8, InputAction>(i as u8) };
+            i += 1;
+        }
+
+        variants
+    };
+
+    pub fn name(self) -> &'static str {
+        match self {
+        | InputAction::MoveLeft      => "Move Left",
+        | InputAction::MoveRight     => "Move Right",
+        | InputAction::MoveUp        => "Move Up",
+        | InputAction::MoveDown      => "Move Down",
+        | InputAction::Interact      => "Interact",
+        | InputAction::Escape        => "Close",
+        | InputAction::ToggleMap     => "Map",
+        | InputAction::ToggleDevMode => "Dev Mode",
+        }
+    }
+
+    // INFO: Movement is active while held, everything else only on the frame it's pressed.
+    pub fn held(self) -> bool {
+        matches!(self, InputAction::MoveLeft | InputAction::MoveRight | InputAction::MoveUp | InputAction::MoveDown)
+    }
+
+    pub fn set(self, actions: &mut InputActions, value: bool) {
+        let field = match self {
+        | InputAction::MoveLeft      => &mut actions.move_left,
+        | InputAction::MoveRight     => &mut actions.move_right,
+        | InputAction::MoveUp        => &mut actions.move_up,
+        | InputAction::MoveDown      => &mut actions.move_down,
+        | InputAction::Interact      => &mut actions.interact,
+        | InputAction::Escape        => &mut actions.escape,
+        | InputAction::ToggleMap     => &mut actions.toggle_map,
+        | InputAction::ToggleDevMode => &mut actions.toggle_dev_mode,
+        };
+        *field |= value;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Binding {
+    pub keys: [Option<KeyCode>; 2],
+    pub pad: Option<PadButton>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BindingSlot {
+    Key(usize),
+    Pad,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings(pub [Binding; InputAction::LENGTH]);
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let empty = Binding { keys: [None, None], pad: None };
+        parse_bindings_over(Bindings([empty; InputAction::LENGTH]), DEFAULT_BINDINGS)
+            .unwrap_or_else(|err| panic!("default bindings {}", err))
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: InputAction) -> &Binding {
+        &self.0[action as usize]
+    }
+
+    pub fn set(&mut self, action: InputAction, slot: BindingSlot, value: SlotValue) {
+        let binding = &mut self.0[action as usize];
+        match (slot, value) {
+        | (BindingSlot::Key(i), SlotValue::Key(key)) => binding.keys[i] = key,
+        | (BindingSlot::Pad   , SlotValue::Pad(pad)) => binding.pad = pad,
+        | _ => {},
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SlotValue {
+    Key(Option<KeyCode>),
+    Pad(Option<PadButton>),
+}
+
+pub fn key_name(key: Option<KeyCode>) -> String {
+    match key {
+    | Some(key) => format!("{:?}", key),
+    | None => "-".to_string(),
+    }
+}
+
+pub fn pad_name(pad: Option<PadButton>) -> String {
+    match pad {
+    | Some(pad) => format!("{:?}", pad),
+    | None => "-".to_string(),
+    }
+}
+
+// INFO: Actions that aren't in the table keep their defaults, so tables saved before an action existed
+// still load.
+pub fn parse_bindings(source: &str) -> Result<Bindings, DataError> {
+    parse_bindings_over(Bindings::default(), source)
+}
+
+fn parse_bindings_over(mut bindings: Bindings, source: &str) -> Result<Bindings, DataError> {
+    for record in data::records(source) {
+        record.expect_fields(4)?;
+        let action: InputAction = record.variant(0, &InputAction::VARIANTS)?;
+
+        let mut keys = [None, None];
+        for (i, key) in keys.iter_mut().enumerate() {
+            *key = match record.str(i+1)? {
+            | "-" => None,
+            | _ => Some(record.variant(i+1, &BINDABLE_KEYS)?),
+            };
+        }
+        let pad = match record.str(3)? {
+        | "-" => None,
+        | _ => Some(record.variant(3, &PadButton::VARIANTS)?),
+        };
+
+        bindings.0[action as usize] = Binding { keys, pad };
+    }
+
+    Ok(bindings)
+}
+
+pub fn format_bindings(bindings: &Bindings) -> String {
+    let mut text = String::from("# action        key     key     pad\n");
+    for action in InputAction::VARIANTS {
+        let binding = bindings.get(action);
+        let line = format!(
+            "{:<15} {:<7} {:<7} {}\n",
+            format!("{:?}", action),
+            key_name(binding.keys[0]),
+            key_name(binding.keys[1]),
+            pad_name(binding.pad),
+        );
+        text.push_str(&line);
+    }
+    text
+}
+
+// INFO: Broken stored bindings fall back to the defaults instead of stopping the game from starting.
+pub fn load_bindings() -> Bindings {
+    let Some(bytes) = storage::read(BINDINGS_KEY) else { return Bindings::default() };
+
+    match parse_bindings(&String::from_utf8_lossy(&bytes)) {
+    | Ok(bindings) => bindings,
+    | Err(err) => {
+        warn!("Ignoring stored bindings: {}", err);
+        Bindings::default()
+    },
+    }
+}
+
+pub fn save_bindings(bindings: &Bindings) {
+    if !storage::write(BINDINGS_KEY, format_bindings(bindings).as_bytes()) {
+        warn!("Couldn't save bindings");
+    }
+}
+
+pub enum Capture {
+    Waiting,
+    Cancel,
+    Bind(SlotValue),
+}
+
+// INFO: What the Controls screen is waiting on. A key slot takes the next bindable key, a pad slot the next
+// gamepad button. Backspace clears the slot, Escape (or East for key slots) gives up.
+pub fn capture_binding(slot: BindingSlot, input: &InputState) -> Capture {
+    let pad_pressed = PadButton::VARIANTS.into_iter()
+        .find(|button| input.pad.is_down(*button) && !input.prev_pad.is_down(*button));
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Capture::Cancel;
+    }
+    if is_key_pressed(KeyCode::Backspace) {
+        return match slot {
+        | BindingSlot::Key(_) => Capture::Bind(SlotValue::Key(None)),
+        | BindingSlot::Pad    => Capture::Bind(SlotValue::Pad(None)),
+        };
+    }
+
+    match slot {
+    | BindingSlot::Key(_) => {
+        if pad_pressed == Some(PadButton::East) { return Capture::Cancel; }
+        match get_last_key_pressed().filter(|key| BINDABLE_KEYS.contains(key)) {
+        | Some(key) => Capture::Bind(SlotValue::Key(Some(key))),
+        | None => Capture::Waiting,
+        }
+    },
+    | BindingSlot::Pad => match pad_pressed {
+        | Some(pad) => Capture::Bind(SlotValue::Pad(Some(pad))),
+        | None => Capture::Waiting,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_cover_every_action() {
+        let bindings = Bindings::default();
+        assert_eq!(bindings.get(InputAction::MoveLeft).keys, [Some(KeyCode::A), Some(KeyCode::Left)]);
+        assert_eq!(bindings.get(InputAction::Interact).pad, Some(PadButton::South));
+        assert_eq!(bindings.get(InputAction::ToggleDevMode).pad, None);
+        for action in InputAction::VARIANTS {
+            assert!(bindings.get(action).keys[0].is_some(), "{:?}", action);
+        }
+    }
+
+    #[test]
+    fn bindings_round_trip_through_text() {
+        let mut bindings = Bindings::default();
+        bindings.set(InputAction::Interact, BindingSlot::Key(1), SlotValue::Key(Some(KeyCode::Space)));
+        bindings.set(InputAction::MoveUp, BindingSlot::Pad, SlotValue::Pad(None));
+
+        assert_eq!(parse_bindings(&format_bindings(&bindings)).unwrap(), bindings);
+    }
+
+    #[test]
+    fn missing_actions_keep_defaults_and_bad_names_fail() {
+        let bindings = parse_bindings("Interact F - North\n").unwrap();
+        assert_eq!(bindings.get(InputAction::Interact).keys, [Some(KeyCode::F), None]);
+        assert_eq!(bindings.get(InputAction::MoveLeft), Bindings::default().get(InputAction::MoveLeft));
+
+        assert!(parse_bindings("Interact Nope - North\n").is_err());
+        assert!(parse_bindings("Jump F - North\n").is_err());
+    }
+}
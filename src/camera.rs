@@ -0,0 +1,112 @@
+// INFO: Where the world is looked at from. It trails the player instead of sitting on them, leans a bit
+// towards where they are heading and never shows past the edges of the world. Shake comes from trauma, big
+// events add some and it wears off, the shake itself grows with trauma squared so small hits stay subtle.
+
+use crate::prelude::*;
+
+// INFO: Higher catches up faster, per second.
+const DAMPING: f32 = 8.0;
+const LOOK_AHEAD: Vec2 = vec2(24.0, 12.0);
+const LOOK_AHEAD_DAMPING: f32 = 2.5;
+// INFO: Further than this (teleports, new runs) the camera jumps instead of sliding across the world.
+const SNAP_DISTANCE: f32 = GAME_WIDTH_F32 * 2.0;
+
+const MAX_SHAKE: f32 = 4.0;
+const TRAUMA_DECAY: f32 = 1.2;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GameCamera {
+    pub pos: Vec2,
+    pub look_ahead: Vec2,
+    pub trauma: f32,
+    pub shake: Vec2,
+    pub time: f32,
+}
+
+impl GameCamera {
+    pub fn new(pos: Vec2) -> GameCamera {
+        GameCamera { pos, ..Default::default() }
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = f32::min(self.trauma + amount, 1.0);
+    }
+
+    // INFO: For things that shake for as long as they last, adding every frame would pin it at 1.0.
+    pub fn hold_trauma(&mut self, amount: f32) {
+        self.trauma = f32::max(self.trauma, amount);
+    }
+
+    // INFO: Direction is in -1..=1 on both axes, zero when standing still.
+    pub fn tick(&mut self, target: Vec2, direction: Vec2, layout: &WorldLayout, dt: f32) {
+        self.time += dt;
+
+        let look_ahead = direction * LOOK_AHEAD;
+        self.look_ahead += (look_ahead - self.look_ahead) * (1.0 - f32::exp(-LOOK_AHEAD_DAMPING*dt));
+
+        let desired = clamp_to_world(target + self.look_ahead, layout);
+        if self.pos.distance(desired) > SNAP_DISTANCE {
+            self.pos = desired;
+            self.look_ahead = Vec2::ZERO;
+        } else {
+            self.pos += (desired - self.pos) * (1.0 - f32::exp(-DAMPING*dt));
+        }
+
+        self.trauma = f32::max(self.trauma - TRAUMA_DECAY*dt, 0.0);
+
+        // INFO: Sines at unrelated frequencies instead of rand, the global generator belongs to gameplay.
+        let t = self.time;
+        let noise = vec2(
+            f32::sin(t*37.0) + f32::sin(t*61.3)*0.5,
+            f32::sin(t*43.7 + 1.3) + f32::sin(t*71.1)*0.5,
+        ) / 1.5;
+        self.shake = noise * self.trauma * self.trauma * MAX_SHAKE;
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.pos + self.shake
+    }
+}
+
+pub fn clamp_to_world(pos: Vec2, layout: &WorldLayout) -> Vec2 {
+    let half_view = vec2(GAME_WIDTH_F32, GAME_HEIGHT_F32) / 2.0;
+    let world_size = vec2(layout.tile_width_i32() as f32, layout.tile_height_i32() as f32) * TILE_SIDE_F32;
+
+    pos.clamp(half_view, world_size - half_view)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_catches_up_and_stays_inside_the_world() {
+        let layout = WorldLayout::new(32, 32, 13);
+        let target = layout.spawn_f32 * TILE_SIDE_F32;
+
+        let mut camera = GameCamera::new(target - vec2(40.0, 0.0));
+        camera.tick(target, Vec2::ZERO, &layout, 1.0/60.0);
+        assert!(camera.pos.x < target.x - 30.0);
+
+        for _ in 0..120 { camera.tick(target, Vec2::ZERO, &layout, 1.0/60.0); }
+        assert!(camera.pos.distance(target) < 0.5);
+
+        for _ in 0..600 { camera.tick(vec2(0.0, 0.0), Vec2::ZERO, &layout, 1.0/60.0); }
+        assert!(camera.pos.x >= GAME_WIDTH_F32/2.0 && camera.pos.y >= GAME_HEIGHT_F32/2.0);
+    }
+
+    #[test]
+    fn trauma_shakes_then_settles() {
+        let layout = WorldLayout::new(32, 32, 13);
+        let target = layout.spawn_f32 * TILE_SIDE_F32;
+        let mut camera = GameCamera::new(target);
+
+        camera.add_trauma(0.8);
+        camera.tick(target, Vec2::ZERO, &layout, 1.0/60.0);
+        assert!(camera.shake != Vec2::ZERO);
+
+        for _ in 0..60 { camera.tick(target, Vec2::ZERO, &layout, 1.0/60.0); }
+        assert_eq!(camera.trauma, 0.0);
+        assert_eq!(camera.center(), camera.pos);
+    }
+}
@@ -0,0 +1,90 @@
+// INFO: Plain text tables for game data. One record per line, fields are separated by whitespace and `#`
+// starts a comment. Small enough to hand-roll, so no serde/ron/toml just for a few tables.
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct DataError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+pub struct DataRecord<'a> {
+    pub line: usize,
+    pub fields: Vec<&'a str>,
+}
+
+pub fn records(source: &str) -> impl Iterator<Item = DataRecord<'_>> {
+    source.lines().enumerate().filter_map(|(i, line)| {
+        let line_without_comment = match line.find('#') {
+        | Some(comment) => &line[..comment],
+        | None => line,
+        };
+        let fields: Vec<&str> = line_without_comment.split_whitespace().collect();
+        if fields.is_empty() { return None; }
+
+        Some(DataRecord { line: i+1, fields })
+    })
+}
+
+impl<'a> DataRecord<'a> {
+    pub fn error(&self, message: impl Into<String>) -> DataError {
+        DataError { line: self.line, message: message.into() }
+    }
+
+    pub fn expect_fields(&self, count: usize) -> Result<(), DataError> {
+        if self.fields.len() != count {
+            return Err(self.error(format!("expected {} fields, found {}", count, self.fields.len())));
+        }
+        Ok(())
+    }
+
+    pub fn str(&self, index: usize) -> Result<&'a str, DataError> {
+        self.fields.get(index).copied().ok_or_else(|| self.error(format!("missing field {}", index+1)))
+    }
+
+    pub fn parse<T: FromStr>(&self, index: usize) -> Result<T, DataError> {
+        let field = self.str(index)?;
+        field.parse().map_err(|_| self.error(format!("can't parse \"{}\"", field)))
+    }
+
+    // INFO: "-" is the empty value for optional fields.
+    pub fn optional<T: FromStr>(&self, index: usize) -> Result<Option<T>, DataError> {
+        match self.str(index)? {
+        | "-" => Ok(None),
+        | _ => self.parse(index).map(Some),
+        }
+    }
+
+    // INFO: Matches against the Debug name, so enums only need their VARIANTS table.
+    pub fn variant<T: Copy + Debug>(&self, index: usize, variants: &[T]) -> Result<T, DataError> {
+        let field = self.str(index)?;
+        variant_by_name(variants, field).ok_or_else(|| self.error(format!("unknown name \"{}\"", field)))
+    }
+
+    // INFO: Comma separated list of known flag names, "-" for none of them.
+    pub fn flags<const N: usize>(&self, index: usize, names: [&str; N]) -> Result<[bool; N], DataError> {
+        let mut flags = [false; N];
+        let field = self.str(index)?;
+        if field == "-" { return Ok(flags); }
+
+        for flag in field.split(',') {
+            let Some(i) = names.iter().position(|name| *name == flag) else {
+                return Err(self.error(format!("unknown flag \"{}\"", flag)));
+            };
+            flags[i] = true;
+        }
+        Ok(flags)
+    }
+}
+
+pub fn variant_by_name<T: Copy + Debug>(variants: &[T], name: &str) -> Option<T> {
+    variants.iter().copied().find(|variant| format!("{:?}", variant) == name)
+}
@@ -13,8 +13,10 @@ pub struct DerivedState {
     pub player_touching_top: bool,
     pub player_touching_bottom: bool,
     pub player_mining: bool,
+    pub player_mining_pos: Vec2,
 
     pub player_at_overworld: bool,
+    pub player_below_barrier: bool,
     pub player_mining_speed: f32,
     pub player_ladder_speed: f32,
     pub player_bag_carry_capacity: usize,
@@ -1,5 +1,7 @@
 use crate::prelude::*;
 
+use crate::particles::{ Emitter };
+
 #[derive(Debug)]
 pub struct Player {
     pub trans: Transform,
@@ -13,6 +15,7 @@ pub struct Player {
     pub climb_momentum: f32,
     pub jetpack_fuel: f32,
     pub jetpack_out_of_fuel_tick: f32,
+    pub exhaust: Emitter,
 }
 
 #[derive(Debug)]
@@ -69,6 +72,7 @@ pub struct Demolisher {
     pub stage: usize,
     pub stage_tick: f32,
     pub momentum: f32,
+    pub dust: Emitter,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -123,7 +127,7 @@ impl ItemKind {
 
         let mut i = 0;
         while i < Self::LENGTH {
-            variants[i] = unsafe { std::mem::transmute(i as u8) };
+            variants[i] = unsafe { std::mem::transmute::<u8, ItemKind>(i as u8) };
             i += 1;
         }
 
@@ -240,3 +244,68 @@ impl BoxCollider {
         self.p1.y >= other.p1.y && self.p2.y >= other.p1.y
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box_at(x: f32, y: f32) -> BoxCollider {
+        BoxCollider::new(vec2(x, y), vec2(1.0, 1.0))
+    }
+
+    #[test]
+    fn ray_hits_box_with_near_side_normal() {
+        let target = BoxCollider::new(vec2(10.0, 0.0), vec2(10.0, 10.0));
+
+        let (point, normal, time) = target.ray_collides(vec2(0.0, 5.0), vec2(20.0, 0.0)).unwrap();
+        assert_eq!(point, vec2(10.0, 5.0));
+        assert_eq!(normal, vec2(-1.0, 0.0));
+        assert_eq!(time, 0.5);
+
+        let (point, normal, time) = target.ray_collides(vec2(15.0, 20.0), vec2(0.0, -20.0)).unwrap();
+        assert_eq!(point, vec2(15.0, 10.0));
+        assert_eq!(normal, vec2(0.0, 1.0));
+        assert_eq!(time, 0.5);
+    }
+
+    #[test]
+    fn ray_misses_box_beside_or_behind_it() {
+        let target = BoxCollider::new(vec2(10.0, 0.0), vec2(10.0, 10.0));
+
+        assert!(target.ray_collides(vec2(0.0, 20.0), vec2(20.0, 0.0)).is_none());
+        assert!(target.ray_collides(vec2(30.0, 5.0), vec2(20.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn collides_only_within_velocity() {
+        let mover = unit_box_at(0.0, 0.0);
+        let wall = BoxCollider::new(vec2(3.0, -5.0), vec2(1.0, 10.0));
+
+        assert!(mover.collides(wall, vec2(1.0, 0.0)).is_none());
+        assert!(mover.collides(wall, vec2(0.0, 0.0)).is_none());
+
+        let (_, normal, time) = mover.collides(wall, vec2(4.0, 0.0)).unwrap();
+        assert_eq!(normal, vec2(-1.0, 0.0));
+        assert_eq!(time, 0.5);
+    }
+
+    #[test]
+    fn contains_requires_whole_box_inside() {
+        let outer = BoxCollider::new(vec2(0.0, 0.0), vec2(10.0, 10.0));
+
+        assert!(unit_box_at(2.0, 2.0).contains(outer));
+        assert!(unit_box_at(9.0, 9.0).contains(outer));
+        assert!(!unit_box_at(9.5, 2.0).contains(outer));
+        assert!(!unit_box_at(-0.5, 2.0).contains(outer));
+        assert!(!outer.contains(unit_box_at(2.0, 2.0)));
+    }
+
+    #[test]
+    fn intersects_counts_touching_edges() {
+        let a = unit_box_at(0.0, 0.0);
+
+        assert!(a.intersects(unit_box_at(1.0, 0.0)));
+        assert!(a.intersects(unit_box_at(0.5, 0.5)));
+        assert!(!a.intersects(unit_box_at(1.5, 0.0)));
+    }
+}
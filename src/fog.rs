@@ -0,0 +1,78 @@
+// INFO: Tiles start out undiscovered and are drawn as plain dark rock (see TileChunk::drawn_tiles and
+// bake_lighting), so caves and ores have to be found instead of read off the screen. The player discovers
+// whatever is right around them, through walls too, and anything further away they have a clear line to.
+// Discovered state lives in the chunks, the minimap and saves read it from there.
+
+use crate::prelude::*;
+
+use crate::world::{ WorldTiles };
+
+// INFO: In tiles.
+const NEAR_RADIUS: i32 = 2;
+pub const SIGHT_RADIUS: i32 = 9;
+
+// INFO: Samples the line between the two tile centers every half a tile, the tiles at either end never block.
+fn in_line_of_sight(tiles: &WorldTiles, from: IVec2, to: IVec2) -> bool {
+    let delta = (to - from).as_vec2();
+    let steps = (delta.length() * 2.0).ceil() as i32;
+
+    for step in 1..steps {
+        let pos = (from.as_vec2() + Vec2::splat(0.5) + delta * (step as f32 / steps as f32)).floor().as_ivec2();
+        if pos == from || pos == to { continue; }
+        if !tiles.at_tile_pos(pos).kind.can_walk_through() { return false; }
+    }
+    true
+}
+
+pub fn discover_around(world: &mut World, bump: &Bump, eye: IVec2) {
+    let layout = world.layout;
+    let tiles = world.tiles();
+
+    let mut seen = Vec::with_capacity_in(64, bump);
+    for y in -SIGHT_RADIUS..=SIGHT_RADIUS {
+        for x in -SIGHT_RADIUS..=SIGHT_RADIUS {
+            let distance_squared = x*x + y*y;
+            if distance_squared > SIGHT_RADIUS*SIGHT_RADIUS { continue; }
+
+            let tile_pos = eye + ivec2(x, y);
+            let inside = tile_pos.x >= 0 && tile_pos.y >= 0 && tile_pos.x < layout.tile_width_i32() && tile_pos.y < layout.tile_height_i32();
+            if !inside || world.is_discovered(tile_pos) { continue; }
+
+            if distance_squared <= NEAR_RADIUS*NEAR_RADIUS || in_line_of_sight(&tiles, eye, tile_pos) {
+                seen.push(tile_pos);
+            }
+        }
+    }
+
+    for tile_pos in seen {
+        world.discover(tile_pos);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::headless::headless_game;
+
+    #[test]
+    fn walls_block_sight_until_dug_open() {
+        let mut game = headless_game(1);
+        let spawn = game.world.layout.spawn_i32;
+        assert!(game.world.is_discovered(spawn));
+        assert!(!game.world.is_discovered(spawn - ivec2(0, 3)));
+
+        // INFO: The room's floor is solid, only the near radius gets through it.
+        discover_around(&mut game.world, &game.bump, spawn);
+        assert!(game.world.is_discovered(spawn - ivec2(0, 2)));
+        assert!(!game.world.is_discovered(spawn - ivec2(0, 3)));
+
+        let mut commands = game.world.commands(&game.bump);
+        commands.set_tile_area(spawn - ivec2(0, 6), ivec2(1, 6), Tile::BackgroundStone);
+        game.world.apply_commands(commands);
+
+        discover_around(&mut game.world, &game.bump, spawn);
+        assert!(game.world.is_discovered(spawn - ivec2(0, 7)));
+        assert!(!game.world.is_discovered(spawn - ivec2(3, 7)));
+    }
+}
@@ -0,0 +1,184 @@
+// INFO: Steps a Game without a window or audio device. Assets are placeholders (see AssetState::headless),
+// sounds pile up in the sound player and get thrown away every step, input comes from the caller.
+
+use std::task::{ Context, Poll, Waker };
+
+use crate::prelude::*;
+
+use crate::asset::init_headless_assets;
+use crate::init::{ init, reset_run };
+use crate::update::simulate;
+
+pub const DT: f32 = 1.0 / 60.0;
+
+pub fn headless_game(seed: u64) -> Game {
+    let mut game = block_on(init(block_on(init_headless_assets())));
+    reset_run(&mut game, seed);
+    game.main_ui_state = MainUIState::InGame;
+    game
+}
+
+pub fn step(game: &mut Game, input: InputActions) {
+    game.input_actions = input;
+    simulate(game, DT);
+    game.sound_player.commands.clear();
+}
+
+pub fn hold(game: &mut Game, input: InputActions, seconds: f32) {
+    for _ in 0..(seconds / DT).ceil() as usize {
+        step(game, input);
+    }
+}
+
+pub fn idle(game: &mut Game, seconds: f32) {
+    hold(game, NONE, seconds);
+}
+
+// INFO: Nothing awaits when headless, so futures complete on their first poll.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let future = std::pin::pin!(future);
+    match future.poll(&mut Context::from_waker(Waker::noop())) {
+    | Poll::Ready(output) => output,
+    | Poll::Pending => unreachable!("headless futures shouldn't wait on anything"),
+    }
+}
+
+const NONE: InputActions = InputActions {
+    move_left: false, move_right: false, move_up: false, move_down: false,
+    interact: false, escape: false, toggle_map: false, toggle_dev_mode: false,
+};
+const DOWN: InputActions = InputActions { move_down: true, ..NONE };
+const RIGHT: InputActions = InputActions { move_right: true, ..NONE };
+const INTERACT: InputActions = InputActions { interact: true, ..NONE };
+const ESCAPE: InputActions = InputActions { escape: true, ..NONE };
+
+fn set_tile(game: &mut Game, tile_pos: IVec2, tile: Tile) {
+    let mut commands = game.world.commands(&game.bump);
+    commands.set_tile(tile_pos, tile);
+    game.world.apply_commands(commands);
+}
+
+// INFO: Spawn is right between two tiles, so step into the right one before digging down.
+fn center_on_spawn_shaft(game: &mut Game) {
+    idle(game, 0.5);
+    hold(game, RIGHT, 0.1);
+}
+
+#[test]
+fn digging_down_leaves_a_ladder_shaft() {
+    let mut game = headless_game(1);
+    center_on_spawn_shaft(&mut game);
+
+    let start_tile = world_pos_to_tile_pos(game.player.trans.pos);
+    hold(&mut game, DOWN, 4.0);
+    let end_tile = world_pos_to_tile_pos(game.player.trans.pos);
+
+    assert!(end_tile.y <= start_tile.y - 3, "player only got from {} to {}", start_tile, end_tile);
+    let tiles = game.world.tiles();
+    for y in end_tile.y..start_tile.y {
+        assert!(tiles.at_tile_pos(ivec2(start_tile.x, y)).kind.can_climb());
+    }
+}
+
+#[test]
+fn mining_ore_puts_it_in_the_bag() {
+    let mut game = headless_game(1);
+    center_on_spawn_shaft(&mut game);
+
+    let below = world_pos_to_tile_pos(game.player.trans.pos) - ivec2(0, 1);
+    set_tile(&mut game, below, Tile::StoneIronOre);
+
+    hold(&mut game, DOWN, 2.0);
+
+    assert_eq!(game.player.carrying.slice(), &[ItemKind::IronOre]);
+    assert!(game.unlocked_ores[ItemKind::IronOre as usize]);
+}
+
+#[test]
+fn minecart_sells_cargo_for_its_value() {
+    let mut game = headless_game(1);
+    idle(&mut game, 0.5);
+
+    let cargo = [ItemKind::CopperOre, ItemKind::IronOre, ItemKind::GoldOre, ItemKind::RawRuby];
+    for kind in cargo {
+        game.player.carrying.push(kind);
+    }
+    game.player.trans.pos = game.minecart.trans.pos;
+
+    for _ in cargo {
+        step(&mut game, INTERACT);
+        idle(&mut game, 0.2);
+    }
+    assert_eq!(game.player.carrying.length, 0);
+    assert_eq!(game.minecart.carrying.length, cargo.len());
+
+    let mut waited = 0.0;
+    while game.minecart.carrying.length > 0 {
+        assert!(waited < 30.0, "minecart never came back");
+        idle(&mut game, 1.0);
+        waited += 1.0;
+    }
+    assert!(game.minecart.movement == MinecartMovement::Idle);
+
+    let value: i32 = cargo.iter().map(|kind| kind.value()).sum();
+    let coins: i32 = game.coins.iter().map(|coin| coin.amount).sum();
+    assert_eq!(coins, value);
+
+    idle(&mut game, 5.0);
+    for _ in 0..game.coins.len() * 2 {
+        let Some(coin) = game.coins.last() else { break };
+        game.player.trans.pos = coin.trans.pos;
+        step(&mut game, NONE);
+    }
+    assert!(game.coins.is_empty());
+    assert_eq!(game.money, value);
+}
+
+#[test]
+fn elevator_takes_the_player_down_and_back_up() {
+    let mut game = headless_game(1);
+    let layout = game.world.layout;
+    game.upgrades.jetpack.kind = JetpackUpgradeKind::Jetpack;
+    idle(&mut game, 0.5);
+    assert!(game.elevator_spawned);
+
+    let inside = game.elevator_platform.trans.pos + vec2(game.elevator_platform.trans.size.x/2.0, 5.0);
+    game.player.trans.pos = inside;
+
+    let mut waited = 0.0;
+    while game.elevator_platform.trans.pos.y > layout.elevator_platform_end_stop.y {
+        assert!(waited < 60.0, "elevator never reached the bottom");
+        step(&mut game, NONE);
+        waited += DT;
+    }
+    assert!(game.player.trans.pos.y < layout.elevator_platform_end.y);
+    assert!(game.player.trans.collider().contains(game.elevator_platform.trans.collider()));
+
+    let mut waited = 0.0;
+    while game.elevator_platform.trans.pos.y < layout.elevator_platform_start.y {
+        assert!(waited < 60.0, "elevator never came back up");
+        step(&mut game, NONE);
+        waited += DT;
+    }
+    assert!(game.player.trans.pos.y >= layout.elevator_platform_start.y);
+}
+
+#[test]
+fn escape_pauses_and_resumes_the_run() {
+    let mut game = headless_game(1);
+    idle(&mut game, 0.5);
+
+    step(&mut game, ESCAPE);
+    assert!(game.main_ui_state == MainUIState::Paused);
+
+    let time = game.total_time;
+    let pos = game.player.trans.pos;
+    hold(&mut game, RIGHT, 1.0);
+    assert_eq!(game.total_time, time);
+    assert_eq!(game.player.trans.pos, pos);
+
+    step(&mut game, ESCAPE);
+    assert!(game.main_ui_state == MainUIState::InGame);
+    hold(&mut game, RIGHT, 0.5);
+    assert!(game.player.trans.pos.x > pos.x);
+}
@@ -0,0 +1,191 @@
+// INFO: Native dev builds watch the files every texture and sound was loaded from (see Assets::texture_sources)
+// and swap in whatever changed, so a sprite sheet can be tweaked without restarting and replaying to the
+// same spot. Textures are patched in place instead of replaced, every sprite, animation and chunk mesh holds
+// a handle to the same texture and keeps it. Sheets in the atlas only patch their own region, so they have to
+// keep their size. Polls modification times, asset/ is small enough for that.
+
+use std::path::{ Path, PathBuf };
+use std::time::SystemTime;
+
+use crate::prelude::*;
+
+use crate::SoundCommand;
+use crate::asset::{ TextureSource };
+use crate::stereo::{ LoadedSound, load_sound_from_bytes };
+use crate::tile::{ tile_bounds };
+use crate::aseprite::{ parse_aseprite, sheet_image };
+
+// INFO: In seconds.
+const POLL_INTERVAL: f32 = 0.5;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum WatchedAsset {
+    // INFO: Index into Assets::texture_sources.
+    Texture(usize),
+    // INFO: Index into Assets::sound_sources.
+    Sound(usize),
+}
+
+struct WatchedFile {
+    path: PathBuf,
+    asset: WatchedAsset,
+    modified: Option<SystemTime>,
+}
+
+pub struct AssetWatcher {
+    files: Vec<WatchedFile>,
+    poll_timer: f32,
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+impl AssetWatcher {
+    pub fn new(assets: &Assets) -> AssetWatcher {
+        let textures = assets.texture_sources.iter().enumerate()
+            .map(|(i, source)| (source.path, WatchedAsset::Texture(i)));
+        let sounds = assets.sound_sources.iter().enumerate()
+            .map(|(i, (path, _))| (*path, WatchedAsset::Sound(i)));
+
+        let files = textures.chain(sounds)
+            .map(|(path, asset)| WatchedFile { path: PathBuf::from(path), asset, modified: modified_at(Path::new(path)) })
+            .collect();
+
+        AssetWatcher { files, poll_timer: 0.0 }
+    }
+
+    fn changed(&mut self) -> Vec<(PathBuf, WatchedAsset)> {
+        let mut changed = Vec::new();
+        for file in &mut self.files {
+            let modified = modified_at(&file.path);
+            if modified.is_none() || modified == file.modified { continue; }
+
+            file.modified = modified;
+            changed.push((file.path.clone(), file.asset));
+        }
+        changed
+    }
+}
+
+pub async fn poll(game: &mut Game, watcher: &mut AssetWatcher, dt: f32) {
+    watcher.poll_timer -= dt;
+    if watcher.poll_timer > 0.0 { return; }
+    watcher.poll_timer = POLL_INTERVAL;
+
+    for (path, asset) in watcher.changed() {
+        // INFO: Editors often write in several steps, a file that doesn't load yet gets picked up again on
+        // its next write.
+        let reloaded = match asset {
+        | WatchedAsset::Texture(i) => reload_texture(game, i, &path),
+        | WatchedAsset::Sound(i) => reload_sound(game, i, &path).await,
+        };
+
+        match reloaded {
+        | Ok(()) => info!("Reloaded {}", path.display()),
+        | Err(err) => warn!("Couldn't reload {}: {}", path.display(), err),
+        }
+    }
+}
+
+fn reload_texture(game: &mut Game, i: usize, path: &Path) -> Result<(), String> {
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    // INFO: Only the pixels of .aseprite sheets are reloaded, new tags or frame sizes need a restart.
+    let image = match path.extension().is_some_and(|extension| extension == "aseprite") {
+    | true  => parse_aseprite(&bytes).map(|file| sheet_image(&file)).map_err(|err| err.to_string())?,
+    | false => Image::from_file_with_format(&bytes, None).map_err(|err| err.to_string())?,
+    };
+
+    let TextureSource { texture, region, .. } = &mut game.assets.texture_sources[i];
+    let resized = region.w as u16 != image.width || region.h as u16 != image.height;
+    let whole_texture = region.w == texture.width() && region.h == texture.height();
+
+    match (resized, whole_texture) {
+    | (false, _) => texture.update_part(&image, region.x as i32, region.y as i32, region.w as i32, region.h as i32),
+    | (true, true) => {
+        // HACK: Texture2D has no resize of its own, going through miniquad keeps the texture id every handle uses.
+        unsafe { get_internal_gl() }.quad_context.texture_resize(
+            texture.raw_miniquad_id(), image.width as u32, image.height as u32, Some(&image.bytes)
+        );
+        *region = Rect::new(0.0, 0.0, image.width as f32, image.height as f32);
+    },
+    // INFO: Neighbours on the atlas page are in the way, packing again would move every sprite's frames.
+    | (true, false) => return Err("sheets in the atlas can't change size without a restart".to_string()),
+    }
+
+    // INFO: Only the tile set has a texture of its own to resize, its bounds are UVs baked into the chunk meshes.
+    let texture = texture.weak_clone();
+    let tile_set = &mut game.assets.tile_set;
+    if resized && texture.raw_miniquad_id() == tile_set.texture.raw_miniquad_id() {
+        tile_set.bounds = tile_bounds(texture.size(), tile_set.tile_size);
+        game.world.recalculate_all_meshes = true;
+    }
+    Ok(())
+}
+
+async fn reload_sound(game: &mut Game, i: usize, path: &Path) -> Result<(), String> {
+    let sound = game.assets.sound_sources[i].1;
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    let Some(slot) = game.assets.sounds.get_mut(sound.id) else {
+        return Err("sound was never loaded".to_string());
+    };
+    let panned = matches!(slot, LoadedSound::Panned(_));
+    let loaded = load_sound_from_bytes(&bytes, panned).await?;
+
+    let old = std::mem::replace(slot, loaded);
+    old.stop();
+
+    // INFO: One shots just play the new version next time, loops and music start over with it right away.
+    let sound_player = &mut game.sound_player;
+    let looping = sound_player.loops.iter()
+        .map(|looping| (looping.sound, looping.volume, looping.pan))
+        .chain(sound_player.music.playing.iter().map(|(sound, volume)| (*sound, *volume, 0.0)))
+        .find(|(other, _, _)| other.id == sound.id);
+
+    if let Some((sound, volume, pan)) = looping {
+        sound_player.commands.push(SoundCommand::Play { sound, volume, pan, looped: true });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::headless::{ headless_game };
+
+    #[test]
+    fn every_texture_and_sound_is_watched() {
+        let game = headless_game(1);
+        let watcher = AssetWatcher::new(&game.assets);
+
+        assert!(watcher.files.iter().any(|file| file.path == Path::new("asset/tile_set.png")));
+        assert!(watcher.files.iter().any(|file| file.path == Path::new("asset/player.png")));
+        assert!(watcher.files.iter().any(|file| file.path == Path::new("asset/soundtrack.flac")));
+
+        let sounds = watcher.files.iter().filter(|file| matches!(file.asset, WatchedAsset::Sound(_))).count();
+        assert_eq!(sounds, game.assets.sound_sources.len());
+    }
+
+    #[test]
+    fn only_written_files_count_as_changed() {
+        // INFO: Named after the process, test runs side by side don't touch each other's file.
+        let path = std::env::temp_dir().join(format!("miner_hot_reload_test_{}.png", std::process::id()));
+        std::fs::write(&path, b"before").unwrap();
+
+        let mut watcher = AssetWatcher {
+            files: vec![WatchedFile { path: path.clone(), asset: WatchedAsset::Texture(3), modified: modified_at(&path) }],
+            poll_timer: 0.0,
+        };
+        assert!(watcher.changed().is_empty());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        assert_eq!(watcher.changed(), [(path.clone(), WatchedAsset::Texture(3))]);
+        assert!(watcher.changed().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,12 +2,195 @@ use std::collections::HashMap;
 
 use crate::prelude::*;
 
+use crate::bindings;
+use crate::input::InputState;
+use crate::mixer::{ Mixer, Listener };
+use crate::music::Music;
+use crate::camera::GameCamera;
+use crate::particles::{ self, Particles, Emitter };
+use crate::sprite::{ SpriteBatch };
+use crate::viewport::Viewport;
+use crate::minimap::Minimap;
+use crate::save;
+use crate::settings;
+use crate::worldgen;
+
 pub async fn init(assets: Assets) -> Game {
     let bump = Bump::new();
-    
+
+    let seed = rand::rand() as u64;
+    let world_profiles = worldgen::load_profiles();
+    let (_, width, height) = WORLD_SIZES[DEFAULT_WORLD_SIZE_I];
+    let world = World::new(&assets.tile_set, &bump, seed, &world_profiles[0], width, height);
+
+    let player = new_player(&assets, &world.layout);
+    let statue = new_statue(&assets, &world.layout);
+    let minecart = new_minecart(&assets, &world.layout);
+    let elevator_cage = new_elevator_cage(&assets, &world.layout);
+    let elevator_platform = new_elevator_platform(&assets, &world.layout);
+    let demolisher = new_demolisher(&assets, &world.layout);
+    let minimap = Minimap::new(&world);
+
+    let ui_inventory_bar_frame = UIEntity {
+        sprite: assets.ui_inventory_bar_frame.derive_sprite(),
+        anim: assets.ui_inventory_bar_frame.derive_anim(),
+    };
+
+    let ui_fuel_bar_frame = UIEntity {
+        sprite: assets.ui_fuel_bar_frame.derive_sprite(),
+        anim: assets.ui_fuel_bar_frame.derive_anim(),
+    };
+
+    let settings = settings::load_settings();
+
+    let mut sound_player = SoundPlayer {
+        music: Music::new(assets.music_layers),
+        mixer: Mixer::default(),
+        listener: Listener::default(),
+        loops: Vec::with_capacity(8),
+        loops_silenced: false,
+        commands: Vec::with_capacity(32),
+    };
+    settings::apply_settings(&settings, &mut sound_player, false);
+    sound_player.tick(0.0);
+
+    let has_save = save::has_save(settings.save_slot);
+
+    Game {
+        total_time: 0.0,
+        viewport: Viewport::default(),
+        world_target: None,
+        ui_state: UIState::default(),
+        input: InputState::default(),
+        bindings: bindings::load_bindings(),
+        controls_rebinding: None,
+        settings,
+        dev_mode: false,
+        bump,
+        assets,
+        world,
+        visible_chunks: Vec::with_capacity(16),
+        exposed_gems: Vec::with_capacity(16),
+        camera: GameCamera::new(player.trans.pos),
+        particles: Particles::default(),
+        sprite_batch: SpriteBatch::new(),
+        minimap,
+        money: 0,
+        has_save,
+        autosave_tick: 0.0,
+        save_name: String::new(),
+        deepest_depth: 0,
+        save_slots: Vec::with_capacity(save::SAVE_SLOTS),
+        slot_renaming: None,
+        slot_name_input: String::new(),
+        slot_delete_armed: None,
+        seed_input: seed.to_string(),
+        world_profiles,
+        world_profile_i: 0,
+        world_size_i: DEFAULT_WORLD_SIZE_I,
+
+        coins: Vec::with_capacity(240),
+        dropped_items: Vec::with_capacity(16),
+        unlocked_ores: [false; ItemKind::LENGTH],
+
+        player,
+        statue,
+        minecart,
+
+        elevator_cage,
+        elevator_platform,
+        elevator_spawned: false,
+
+        demolisher,
+        demolisher_spawned: false,
+        demolisher_started: false,
+
+        sfx_pickaxe: 0,
+        sfx_pickaxe_played: 0,
+
+        main_ui_state: MainUIState::MainMenu,
+        ui_inventory_bar_frame,
+        ui_fuel_bar_frame,
+        ui_show_statue: false,
+        ui_show_map: false,
+
+        sound_player,
+
+        tile_durability_map: HashMap::with_capacity(32),
+        tile_cant_dig_map: HashMap::with_capacity(32),
+
+        derived: DerivedState::default(),
+        late_derived: LateDerivedState::default(),
+        input_actions: InputActions::default(),
+        upgrades: Default::default(),
+    }
+}
+
+// INFO: Throws away the current run and generates a fresh world from seed, the selected profile and size.
+// Anything that isn't part of a run (assets, sound player, ui state) is kept as is.
+pub fn reset_run(game: &mut Game, seed: u64) {
+    let assets = &game.assets;
+
+    game.bump.reset();
+    let profile = &game.world_profiles[game.world_profile_i];
+    let (_, width, height) = WORLD_SIZES[game.world_size_i];
+    game.world = World::new(&assets.tile_set, &game.bump, seed, profile, width, height);
+    game.seed_input = seed.to_string();
+
+    place_entities(game);
+
+    let assets = &game.assets;
+    game.ui_inventory_bar_frame.anim = assets.ui_inventory_bar_frame.derive_anim();
+    game.ui_fuel_bar_frame.anim = assets.ui_fuel_bar_frame.derive_anim();
+
+    game.total_time = 0.0;
+    game.money = 0;
+    game.autosave_tick = 0.0;
+    game.save_name.clear();
+    game.deepest_depth = 0;
+
+    game.coins.clear();
+    game.dropped_items.clear();
+    game.particles.clear();
+    game.minimap = Minimap::new(&game.world);
+    game.unlocked_ores = [false; ItemKind::LENGTH];
+
+    game.elevator_spawned = false;
+    game.demolisher_spawned = false;
+    game.demolisher_started = false;
+
+    game.sfx_pickaxe = 0;
+    game.sfx_pickaxe_played = 0;
+    game.ui_show_statue = false;
+    game.ui_show_map = false;
+
+    game.tile_durability_map.clear();
+    game.tile_cant_dig_map.clear();
+
+    game.derived = DerivedState::default();
+    game.late_derived = LateDerivedState::default();
+    game.input_actions = InputActions::default();
+    game.upgrades = Default::default();
+}
+
+// INFO: Puts every landmark entity back where the world's layout wants it, in its initial state.
+pub fn place_entities(game: &mut Game) {
+    let assets = &game.assets;
+    let layout = &game.world.layout;
+
+    game.player = new_player(assets, layout);
+    game.statue = new_statue(assets, layout);
+    game.minecart = new_minecart(assets, layout);
+    game.elevator_cage = new_elevator_cage(assets, layout);
+    game.elevator_platform = new_elevator_platform(assets, layout);
+    game.demolisher = new_demolisher(assets, layout);
+    game.camera = GameCamera::new(game.player.trans.pos);
+}
+
+fn new_player(assets: &Assets, layout: &WorldLayout) -> Player {
     let mut player = Player {
         trans: Transform {
-            pos: WORLD_SPAWN_F32 * Vec2::splat(TILE_SIDE_F32),
+            pos: layout.spawn_f32 * Vec2::splat(TILE_SIDE_F32),
             size: vec2(16.0, 16.0),
             offset: vec2(-7.5, 0.0),
         },
@@ -21,25 +204,30 @@ pub async fn init(assets: Assets) -> Game {
         climb_momentum: 0.0,
         jetpack_fuel: 0.0,
         jetpack_out_of_fuel_tick: 0.0,
+        exhaust: Emitter::new(particles::EXHAUST, 40.0),
     };
 
     player.last_positions[0] = player.trans.pos;
 
-    let player = player;
+    player
+}
 
-    let statue = SimpleEntity {
+fn new_statue(assets: &Assets, layout: &WorldLayout) -> SimpleEntity {
+    SimpleEntity {
         trans: Transform {
-            pos: (STATUE * ivec2(CHUNK_SIDE_I32, CHUNK_SIDE_I32)).as_vec2(),
+            pos: (layout.statue * ivec2(CHUNK_SIDE_I32, CHUNK_SIDE_I32)).as_vec2(),
             size: vec2(32.0, 48.0),
             offset: vec2(0.0, 0.0),
         },
         sprite: assets.statue.derive_sprite(),
         anim: assets.statue.derive_anim(),
-    };
+    }
+}
 
-    let minecart = Minecart {
+fn new_minecart(assets: &Assets, layout: &WorldLayout) -> Minecart {
+    Minecart {
         trans: Transform {
-            pos: MINECART_START,
+            pos: layout.minecart_start,
             size: vec2(15.0, 7.0),
             offset: vec2(0.0, 0.0),
         },
@@ -50,25 +238,29 @@ pub async fn init(assets: Assets) -> Game {
         carrying: Array::new(Item::default()),
         cooldown: 0.0,
         movement: MinecartMovement::Idle,
-    };
+    }
+}
 
-    let elevator_cage = SimpleEntity {
+fn new_elevator_cage(assets: &Assets, layout: &WorldLayout) -> SimpleEntity {
+    SimpleEntity {
         trans: Transform {
-            pos: (ELEVATOR_CAGE * ivec2(CHUNK_SIDE_I32, CHUNK_SIDE_I32)).as_vec2(),
+            pos: (layout.elevator_cage * ivec2(CHUNK_SIDE_I32, CHUNK_SIDE_I32)).as_vec2(),
             size: vec2(0.0, 0.0),
             offset: vec2(0.0, 0.0),
         },
         sprite: assets.elevator_cage.derive_sprite(),
         anim: assets.elevator_cage.derive_anim(),
-    };
+    }
+}
 
+fn new_elevator_platform(assets: &Assets, layout: &WorldLayout) -> ElevatorPlatform {
     let elevator_platform_transform = Transform {
-        pos: ELEVATOR_PLATFORM_START,
+        pos: layout.elevator_platform_start,
         size: vec2(44.0, 42.0),
         offset: vec2(2.0, 0.0),
     };
 
-    let elevator_platform = ElevatorPlatform {
+    ElevatorPlatform {
         trans: elevator_platform_transform,
         sprite: assets.elevator_platform_idle.derive_sprite(),
         anim: assets.elevator_platform_idle.derive_anim(),
@@ -76,91 +268,22 @@ pub async fn init(assets: Assets) -> Game {
         down_or_up: false,
         player_inside_for: 0.0,
         walk_collider: vec2(44.0, -5.0),
-    };
+    }
+}
 
-    let demolisher = Demolisher {
+fn new_demolisher(assets: &Assets, layout: &WorldLayout) -> Demolisher {
+    Demolisher {
         trans: Transform {
-            pos: DEMOLISHER,
+            pos: layout.demolisher,
             size: vec2(52.0, 10.0),
             offset: vec2(0.0, 0.0),
         },
-        prev_pos: DEMOLISHER,
+        prev_pos: layout.demolisher,
         sprite: assets.demolisher_idle.derive_sprite(),
         anim: assets.demolisher_idle.derive_anim(),
         stage: 0,
         stage_tick: 0.0,
         momentum: 40.0,
-    };
-
-    let ui_inventory_bar_frame = UIEntity {
-        sprite: assets.ui_inventory_bar_frame.derive_sprite(),
-        anim: assets.ui_inventory_bar_frame.derive_anim(),
-    };
-    
-    let ui_fuel_bar_frame = UIEntity {
-        sprite: assets.ui_fuel_bar_frame.derive_sprite(),
-        anim: assets.ui_fuel_bar_frame.derive_anim(),
-    };
-        
-    let world = World::new(&assets.tile_set, &bump);
-    
-    get_default_font().populate_font_cache(&Font::latin_character_list(), 16);    
-    get_default_font().populate_font_cache(&Font::latin_character_list(), 32);
-    
-    let mut sound_player = SoundPlayer {
-        music: assets.sfx_soundtrack.clone(),
-        music_playing: true,
-        sound_playing: true,
-        current_music_playing: false,
-    };
-    sound_player.tick_music();
-
-    Game {
-        total_time: 0.0,
-        window_to_draw_size: vec2(1.0, 1.0),
-        ui_state: UIState {
-            mouse_div: vec2(1.0, 1.0),
-            last_clicked_button_hash: None,
-        },
-        dev_mode: false,
-        bump,
-        assets,
-        world,
-        visible_chunks: Vec::with_capacity(16),
-        money: 0,
-
-        coins: Vec::with_capacity(240),
-        dropped_items: Vec::with_capacity(16),
-        unlocked_ores: [false; ItemKind::LENGTH],
-        
-        player,
-        statue,
-        minecart,
-
-        elevator_cage,
-        elevator_platform,
-        elevator_spawned: false,
-
-        demolisher,
-        demolisher_spawned: false,
-        demolisher_started: false,
-
-        sfx_pickaxe: 0,
-        sfx_pickaxe_played: 0,
-
-        main_ui_state: MainUIState::MainMenu,
-        ui_inventory_bar_frame,
-        ui_fuel_bar_frame,
-        ui_show_statue: false,
-
-        sound_player,
-        
-        tile_durability_map: HashMap::with_capacity(32),
-        tile_cant_dig_map: HashMap::with_capacity(32),
-        
-        derived: DerivedState::default(),
-        late_derived: LateDerivedState::default(),
-        input_actions: InputActions::default(),
-        upgrades: Default::default(),
+        dust: Emitter::new(particles::DUST, 50.0),
     }
 }
@@ -0,0 +1,311 @@
+// INFO: Input layer. Keyboard and gamepads are polled once a frame and folded into InputActions for simulate
+// and UINav for the menus. Native gamepads come through gilrs, web ones through the miner_gamepad plugin at
+// the end of patched_js/mq_js_bundle.js. Touch needs nothing here, macroquad turns the first touch into mouse
+// events, which is enough for the menus. There are no on screen sticks for moving around.
+
+use crate::prelude::*;
+
+use crate::bindings::{ Bindings, InputAction };
+use crate::ui::UINav;
+
+pub const STICK_DEADZONE: f32 = 0.35;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadButton {
+    South,
+    East,
+    West,
+    North,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Start,
+    Select,
+}
+
+impl PadButton {
+    pub const LENGTH: usize = std::mem::variant_count::<Self>();
+    pub const VARIANTS: [PadButton; Self::LENGTH] = const {
+        let mut variants = [PadButton::South; Self::LENGTH];
+
+        let mut i = 0;
+        while i < Self::LENGTH {
+            variants[i] = unsafe { std::mem::transmute::<u8, PadButton>(i as u8) };
+            i += 1;
+        }
+
+        variants
+    };
+}
+
+// INFO: Every connected pad merged into one, buttons are or'ed and the stick that is pushed furthest wins.
+// Stick is in screen direction, so positive y is down.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct PadState {
+    pub down: [bool; PadButton::LENGTH],
+    pub stick: Vec2,
+}
+
+impl PadState {
+    pub fn is_down(&self, button: PadButton) -> bool {
+        self.down[button as usize]
+    }
+
+    pub fn left(&self) -> bool  { self.is_down(PadButton::DPadLeft)  || self.stick.x < -STICK_DEADZONE }
+    pub fn right(&self) -> bool { self.is_down(PadButton::DPadRight) || self.stick.x >  STICK_DEADZONE }
+    pub fn up(&self) -> bool    { self.is_down(PadButton::DPadUp)    || self.stick.y < -STICK_DEADZONE }
+    pub fn down(&self) -> bool  { self.is_down(PadButton::DPadDown)  || self.stick.y >  STICK_DEADZONE }
+}
+
+#[derive(Default)]
+pub struct InputState {
+    pub pad: PadState,
+    pub prev_pad: PadState,
+    backend: backend::PadBackend,
+}
+
+pub fn poll_gamepads(input: &mut InputState) {
+    input.prev_pad = input.pad;
+    input.pad = backend::poll(&mut input.backend);
+}
+
+// INFO: Stick always moves, everything else goes through the bindings. While a menu or panel is open the
+// buttons belong to it, otherwise confirming an upgrade would also count as interacting with the statue and
+// close it. Only Escape gets through, and East (back) counts as Escape too.
+pub fn map_pad_actions(bindings: &Bindings, pad: &PadState, prev_pad: &PadState, ui_active: bool) -> InputActions {
+    let pressed = |button: PadButton| pad.is_down(button) && !prev_pad.is_down(button);
+
+    let mut actions = InputActions {
+        move_left : pad.stick.x < -STICK_DEADZONE,
+        move_right: pad.stick.x >  STICK_DEADZONE,
+        move_up   : pad.stick.y < -STICK_DEADZONE,
+        move_down : pad.stick.y >  STICK_DEADZONE,
+        escape    : pressed(PadButton::East) && ui_active,
+        ..Default::default()
+    };
+
+    for action in InputAction::VARIANTS {
+        let Some(button) = bindings.get(action).pad else { continue };
+        if ui_active && !action.held() && action != InputAction::Escape { continue; }
+
+        match action.held() {
+        | true  => action.set(&mut actions, pad.is_down(button)),
+        | false => action.set(&mut actions, pressed(button)),
+        }
+    }
+
+    actions
+}
+
+// INFO: Directions only count on the frame they start, so holding the stick moves focus once.
+pub fn map_pad_nav(pad: &PadState, prev_pad: &PadState, ui_active: bool) -> UINav {
+    if !ui_active { return UINav::default(); }
+
+    let pressed = |button: PadButton| pad.is_down(button) && !prev_pad.is_down(button);
+
+    UINav {
+        up     : pad.up()    && !prev_pad.up(),
+        down   : pad.down()  && !prev_pad.down(),
+        left   : pad.left()  && !prev_pad.left(),
+        right  : pad.right() && !prev_pad.right(),
+        confirm: pressed(PadButton::South),
+        back   : pressed(PadButton::East),
+    }
+}
+
+pub fn map_key_actions(bindings: &Bindings) -> InputActions {
+    let mut actions = InputActions::default();
+
+    for action in InputAction::VARIANTS {
+        for key in bindings.get(action).keys.into_iter().flatten() {
+            match action.held() {
+            | true  => action.set(&mut actions, is_key_down(key)),
+            | false => action.set(&mut actions, is_key_pressed(key)),
+            }
+        }
+    }
+
+    actions
+}
+
+// INFO: Arrows only, WASD would fight with typing into text inputs. Enter belongs to a focused text input.
+pub fn map_key_nav(text_input_focused: bool) -> UINav {
+    UINav {
+        up     : is_key_pressed(KeyCode::Up),
+        down   : is_key_pressed(KeyCode::Down),
+        left   : is_key_pressed(KeyCode::Left),
+        right  : is_key_pressed(KeyCode::Right),
+        confirm: !text_input_focused && (is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space)),
+        back   : is_key_pressed(KeyCode::Escape),
+    }
+}
+
+impl InputActions {
+    pub fn or(self, other: InputActions) -> InputActions {
+        InputActions {
+            move_left      : self.move_left       || other.move_left,
+            move_right     : self.move_right      || other.move_right,
+            move_up        : self.move_up         || other.move_up,
+            move_down      : self.move_down       || other.move_down,
+            interact       : self.interact        || other.interact,
+            escape         : self.escape          || other.escape,
+            toggle_map     : self.toggle_map      || other.toggle_map,
+            toggle_dev_mode: self.toggle_dev_mode || other.toggle_dev_mode,
+        }
+    }
+}
+
+impl UINav {
+    pub fn or(self, other: UINav) -> UINav {
+        UINav {
+            up     : self.up      || other.up,
+            down   : self.down    || other.down,
+            left   : self.left    || other.left,
+            right  : self.right   || other.right,
+            confirm: self.confirm || other.confirm,
+            back   : self.back    || other.back,
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+mod backend {
+    use gilrs::{ Axis, Button, Gilrs };
+
+    use crate::prelude::*;
+
+    use super::{ PadButton, PadState };
+
+    const BUTTONS: [Button; PadButton::LENGTH] = [
+        Button::South,
+        Button::East,
+        Button::West,
+        Button::North,
+        Button::DPadUp,
+        Button::DPadDown,
+        Button::DPadLeft,
+        Button::DPadRight,
+        Button::Start,
+        Button::Select,
+    ];
+
+    // INFO: Gilrs is created on the first poll instead of in init, so headless games never go looking for
+    // devices.
+    #[derive(Default)]
+    pub struct PadBackend {
+        gilrs: Option<Gilrs>,
+        tried: bool,
+    }
+
+    pub fn poll(backend: &mut PadBackend) -> PadState {
+        if !backend.tried {
+            backend.tried = true;
+            match Gilrs::new() {
+            | Ok(gilrs) => backend.gilrs = Some(gilrs),
+            | Err(err) => warn!("Gamepads are unavailable: {}", err),
+            }
+        }
+
+        let mut state = PadState::default();
+        let Some(gilrs) = &mut backend.gilrs else { return state };
+
+        // INFO: Gilrs only updates gamepad state while events are pumped, the events themselves aren't needed.
+        while gilrs.next_event().is_some() {}
+
+        for (_, pad) in gilrs.gamepads() {
+            for (i, button) in BUTTONS.iter().enumerate() {
+                state.down[i] |= pad.is_pressed(*button);
+            }
+            let stick = vec2(pad.value(Axis::LeftStickX), -pad.value(Axis::LeftStickY));
+            if stick.length_squared() > state.stick.length_squared() {
+                state.stick = stick;
+            }
+        }
+
+        state
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod backend {
+    use crate::prelude::*;
+
+    use super::{ PadButton, PadState };
+
+    unsafe extern "C" {
+        fn miner_gamepad_read(out: *mut f32, out_len: usize) -> i32;
+    }
+
+    #[unsafe(no_mangle)]
+    pub extern "C" fn miner_gamepad_crate_version() -> u32 {
+        1
+    }
+
+    #[derive(Default)]
+    pub struct PadBackend;
+
+    // INFO: Plugin writes buttons in PadButton order as 0 or 1, then the left stick x and y.
+    pub fn poll(_backend: &mut PadBackend) -> PadState {
+        let mut out = [0.0f32; PadButton::LENGTH + 2];
+        let connected = unsafe { miner_gamepad_read(out.as_mut_ptr(), out.len()) };
+
+        let mut state = PadState::default();
+        if connected == 0 { return state; }
+
+        for (down, value) in state.down.iter_mut().zip(out) {
+            *down = value != 0.0;
+        }
+        state.stick = vec2(out[PadButton::LENGTH], out[PadButton::LENGTH+1]);
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad_with(buttons: &[PadButton], stick: Vec2) -> PadState {
+        let mut pad = PadState { stick, ..Default::default() };
+        for button in buttons {
+            pad.down[*button as usize] = true;
+        }
+        pad
+    }
+
+    #[test]
+    fn stick_inside_deadzone_does_nothing() {
+        let pad = pad_with(&[], vec2(0.2, -0.3));
+        let actions = map_pad_actions(&Bindings::default(), &pad, &PadState::default(), false);
+        assert!(!actions.move_left && !actions.move_right && !actions.move_up && !actions.move_down);
+
+        let pad = pad_with(&[], vec2(0.9, -0.5));
+        let actions = map_pad_actions(&Bindings::default(), &pad, &PadState::default(), false);
+        assert!(actions.move_right && actions.move_up);
+    }
+
+    #[test]
+    fn face_buttons_go_to_the_ui_while_it_is_open() {
+        let prev = PadState::default();
+        let pad = pad_with(&[PadButton::South], Vec2::ZERO);
+
+        assert!(map_pad_actions(&Bindings::default(), &pad, &prev, false).interact);
+        assert!(!map_pad_nav(&pad, &prev, false).confirm);
+
+        assert!(!map_pad_actions(&Bindings::default(), &pad, &prev, true).interact);
+        assert!(map_pad_nav(&pad, &prev, true).confirm);
+
+        // INFO: Held from the previous frame, not pressed again.
+        assert!(!map_pad_nav(&pad, &pad, true).confirm);
+    }
+
+    #[test]
+    fn held_stick_moves_focus_once() {
+        let prev = PadState::default();
+        let pad = pad_with(&[], vec2(0.0, 1.0));
+
+        assert!(map_pad_nav(&pad, &prev, true).down);
+        assert!(!map_pad_nav(&pad, &pad, true).down);
+    }
+}
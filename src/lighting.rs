@@ -0,0 +1,131 @@
+// INFO: Light is baked into the vertex colors of the chunk meshes once a frame, so it costs nothing extra to
+// draw and works on WebGL1 the same. Only visible chunks are baked, the rest keep whatever they had last.
+// Light at a vertex is the brighter of the depth ambient and the player's lamp, plus tinted glow from gems
+// that have been dug free.
+
+use crate::prelude::*;
+
+use crate::world::{ WorldTiles };
+
+// INFO: In tiles. Ambient fades out over the first SURFACE_FADE tiles under the spawn, then can't get darker
+// than a floor that goes from SHALLOW_FLOOR right under the surface to DEEP_FLOOR at the bottom of the world.
+const SURFACE_FADE: f32 = 12.0;
+const SHALLOW_FLOOR: f32 = 0.35;
+const DEEP_FLOOR: f32 = 0.08;
+
+const PLAYER_LIGHT_RADIUS: f32 = 6.0 * TILE_SIDE_F32;
+const GEM_LIGHT_RADIUS: f32 = 2.5 * TILE_SIDE_F32;
+const GEM_LIGHT_STRENGTH: f32 = 0.6;
+// INFO: Undiscovered tiles are drawn as stone, this keeps them darker than any real stone next to them.
+const FOG_SHADE: f32 = 0.35;
+
+pub fn gem_glow(tile: Tile) -> Option<Vec3> {
+    match tile {
+    | Tile::StoneEmerald  => Some(vec3(0.2 , 1.0 , 0.4 )),
+    | Tile::StoneRuby     => Some(vec3(1.0 , 0.2 , 0.25)),
+    | Tile::StoneSapphire => Some(vec3(0.25, 0.45, 1.0 )),
+    | _ => None,
+    }
+}
+
+pub fn ambient_light(layout: &WorldLayout, world_y: f32) -> f32 {
+    let surface_y = layout.spawn_f32.y * TILE_SIDE_F32;
+    let depth = f32::max(surface_y - world_y, 0.0) / TILE_SIDE_F32;
+
+    let floor = SHALLOW_FLOOR + (DEEP_FLOOR - SHALLOW_FLOOR) * (depth / layout.spawn_f32.y).clamp(0.0, 1.0);
+    f32::max(1.0 - depth/SURFACE_FADE, floor)
+}
+
+// INFO: Smoothstep, so the edge of a light doesn't show up as a ring.
+fn falloff(distance: f32, radius: f32) -> f32 {
+    let t = (1.0 - distance/radius).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0*t)
+}
+
+// INFO: A gem only glows once one of its sides is open, buried ones would give the ore away.
+fn is_exposed(tiles: &WorldTiles, layout: &WorldLayout, tile_pos: IVec2) -> bool {
+    [ivec2(1, 0), ivec2(-1, 0), ivec2(0, 1), ivec2(0, -1)].into_iter().any(|offset| {
+        let pos = tile_pos + offset;
+        let inside = pos.x >= 0 && pos.y >= 0 && pos.x < layout.tile_width_i32() && pos.y < layout.tile_height_i32();
+        inside && tiles.at_tile_pos(pos).kind.can_walk_through()
+    })
+}
+
+// INFO: Tile position and glow color of every discovered gem in the given chunks that has been dug free.
+// Found once a frame into Game::exposed_gems, sparkles and bake_lighting both read it from there.
+pub fn find_exposed_gems(world: &World, chunks: &[IVec2], gems: &mut Vec<(IVec2, Vec3)>) {
+    let layout = world.layout;
+    let tiles = world.tiles();
+
+    gems.clear();
+    for &chunk_pos in chunks {
+        let chunk_tile_pos = chunk_pos_to_tile_pos(chunk_pos);
+        for (i, tile) in world.chunk_at(chunk_pos).tiles.iter().enumerate() {
+            let Some(color) = gem_glow(*tile) else { continue };
+
+            let tile_pos = chunk_tile_pos + ivec2((i % CHUNK_SIDE) as i32, (i / CHUNK_SIDE) as i32);
+            if !world.is_discovered(tile_pos) { continue; }
+            if !is_exposed(&tiles, &layout, tile_pos) { continue; }
+
+            gems.push((tile_pos, color));
+        }
+    }
+}
+
+// INFO: White light only, what sprites drawn over the world get multiplied by.
+pub fn light_at(layout: &WorldLayout, pos: Vec2, player_pos: Vec2) -> f32 {
+    f32::max(ambient_light(layout, pos.y), falloff(pos.distance(player_pos), PLAYER_LIGHT_RADIUS))
+}
+
+pub fn bake_lighting(world: &mut World, visible_chunks: &[IVec2], gem_lights: &[(IVec2, Vec3)], player_pos: Vec2) {
+    let layout = world.layout;
+
+    for &chunk_pos in visible_chunks {
+        let chunk_world_pos = chunk_pos_to_world_pos(chunk_pos);
+        let chunk = &world.chunks[chunk_index_at(chunk_pos, layout.width)];
+        let mesh = &mut world.meshes[chunk_index_at(chunk_pos, layout.width)];
+
+        // INFO: Four vertices per tile, in the same order as the tiles.
+        for (vertex_i, vertex) in mesh.0.vertices.iter_mut().enumerate() {
+            let pos = chunk_world_pos + vertex.position.xy();
+
+            let mut color = Vec3::splat(light_at(&layout, pos, player_pos));
+            for (tile_pos, glow) in gem_lights {
+                let center = tile_pos_to_world_pos(*tile_pos) + vec2(TILE_SIDE_F32, TILE_SIDE_F32)/2.0;
+                color += *glow * GEM_LIGHT_STRENGTH * falloff(pos.distance(center), GEM_LIGHT_RADIUS);
+            }
+            if !chunk.discovered[vertex_i / 4] {
+                color *= FOG_SHADE;
+            }
+
+            let color = (color.min(Vec3::ONE) * 255.0).as_uvec3();
+            vertex.color = [color.x as u8, color.y as u8, color.z as u8, 255];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ambient_darkens_with_depth_down_to_a_floor() {
+        let layout = WorldLayout::new(32, 32, 13);
+        let surface_y = layout.spawn_f32.y * TILE_SIDE_F32;
+
+        assert_eq!(ambient_light(&layout, surface_y + 40.0), 1.0);
+        let shallow = ambient_light(&layout, surface_y - 4.0*TILE_SIDE_F32);
+        let deep = ambient_light(&layout, surface_y - 100.0*TILE_SIDE_F32);
+        let bottom = ambient_light(&layout, 0.0);
+
+        assert!(shallow < 1.0 && deep < shallow && bottom <= deep);
+        assert!((bottom - DEEP_FLOOR).abs() < 0.001);
+    }
+
+    #[test]
+    fn light_falls_off_to_nothing_at_its_radius() {
+        assert_eq!(falloff(0.0, 10.0), 1.0);
+        assert_eq!(falloff(10.0, 10.0), 0.0);
+        assert!(falloff(3.0, 10.0) > falloff(6.0, 10.0));
+    }
+}
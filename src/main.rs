@@ -9,7 +9,7 @@ use std::sync::{LazyLock, Mutex};
 
 use bumpalo::Bump;
 use macroquad::prelude::coroutines::start_coroutine;
-use macroquad::{audio, prelude::*};
+use macroquad::prelude::*;
 
 mod init;
 mod asset;
@@ -22,15 +22,50 @@ mod entity;
 mod derived;
 mod ui;
 mod upgrades;
+mod storage;
+mod save;
+mod data;
+mod worldgen;
+mod input;
+mod bindings;
+mod settings;
+mod mixer;
+mod stereo;
+mod music;
+mod lighting;
+mod camera;
+mod particles;
+mod viewport;
+mod minimap;
+#[cfg(not(target_family = "wasm"))]
+mod hot_reload;
+mod fog;
+mod manifest;
+mod aseprite;
+mod atlas;
+#[cfg(test)]
+mod headless;
 
 use init::    { init };
 use asset::   { init_assets };
-use asset::   { Assets };
+use asset::   { Assets, SoundAsset };
 use ui::      { UIState };
+use input::   { InputState };
+use bindings::{ Bindings, InputAction, BindingSlot };
+use settings::{ Settings };
+use mixer::   { Mixer, Bus, Listener };
+use music::   { Music };
+use camera::  { GameCamera };
+use particles::{ Particles };
+use sprite::  { SpriteBatch };
+use viewport::{ Viewport };
+use minimap:: { Minimap };
+use save::    { SlotInfo, SaveError };
 use upgrades::{ Upgrades };
 use update::  { update };
 use render::  { render };
 use world::   { World };
+use worldgen::{ WorldGenProfile };
 use derived:: { DerivedState, LateDerivedState };
 
 use entity::*;
@@ -44,7 +79,8 @@ pub mod prelude {
     pub use super::{ debug_generic, debug_point, debug_rect };
     pub use super::{ Game, MainUIState, InputActions, SoundPlayer, GameMesh, Array };
 
-    pub use crate::asset::{ Assets };
+    pub use crate::asset::{ Assets, SoundAsset };
+    pub use crate::mixer::{ Bus };
 
     pub use crate::sprite::{ Sprite, Animation, SpriteAsset };
     pub use crate::sprite::{ tick_animation };
@@ -54,7 +90,7 @@ pub mod prelude {
     pub use crate::tile::{ Tile, TileBounds };
     pub use crate::tile::consts::*;
 
-    pub use crate::world::{ World };
+    pub use crate::world::{ World, WorldLayout };
     pub use crate::world::consts::*;
     pub use crate::world::conversions::*;
 
@@ -89,14 +125,44 @@ use consts::*;
 
 pub struct Game {
     pub total_time: f32,
-    pub window_to_draw_size: Vec2,
+    pub viewport: Viewport,
+    // INFO: Created on first render, headless games never get one.
+    pub world_target: Option<RenderTarget>,
     pub ui_state: UIState,
+    pub input: InputState,
+    pub bindings: Bindings,
+    // INFO: Slot on the Controls screen that is waiting for a key or button.
+    pub controls_rebinding: Option<(InputAction, BindingSlot)>,
+    pub settings: Settings,
     pub dev_mode: bool,
     pub bump: Bump,
     pub assets: Assets,
     pub world: World,
     pub visible_chunks: Vec<IVec2>,
+    // INFO: Dug free gems in visible_chunks, see lighting::find_exposed_gems.
+    pub exposed_gems: Vec<(IVec2, Vec3)>,
+    pub camera: GameCamera,
+    pub particles: Particles,
+    // INFO: World sprites for the frame being rendered, see sprite.rs.
+    pub sprite_batch: SpriteBatch,
+    pub minimap: Minimap,
     pub money: i32,
+    pub has_save: bool,
+    pub autosave_tick: f32,
+    // INFO: Name shown on the Saves screen, empty means the slot's default label.
+    pub save_name: String,
+    // INFO: In tiles below the spawn room.
+    pub deepest_depth: i32,
+    // INFO: Read when the Saves screen is opened and after every change made on it.
+    pub save_slots: Vec<Result<SlotInfo, SaveError>>,
+    pub slot_renaming: Option<usize>,
+    pub slot_name_input: String,
+    // INFO: Delete needs a second press on the same slot.
+    pub slot_delete_armed: Option<usize>,
+    pub seed_input: String,
+    pub world_profiles: Vec<WorldGenProfile>,
+    pub world_profile_i: usize,
+    pub world_size_i: usize,
 
     pub player: Player,
     pub statue: SimpleEntity,
@@ -121,6 +187,7 @@ pub struct Game {
     pub ui_inventory_bar_frame: UIEntity,
     pub ui_fuel_bar_frame: UIEntity,
     pub ui_show_statue: bool,
+    pub ui_show_map: bool,
 
     pub sound_player: SoundPlayer,
 
@@ -133,14 +200,25 @@ pub struct Game {
     pub upgrades: Upgrades,
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum MainUIState {
     MainMenu,
     MainMenuCredits,
+    MainMenuControls,
+    MainMenuSettings,
+    MainMenuSaves,
     InGame,
+    Paused,
+    PausedSettings,
 }
 
-#[derive(Default)]
+impl MainUIState {
+    pub fn is_paused(self) -> bool {
+        matches!(self, MainUIState::Paused | MainUIState::PausedSettings)
+    }
+}
+
+#[derive(Default, Copy, Clone)]
 pub struct InputActions {
     pub move_left: bool,
     pub move_right: bool,
@@ -148,34 +226,147 @@ pub struct InputActions {
     pub move_down: bool,
     pub interact: bool,
     pub escape: bool,
+    pub toggle_map: bool,
     pub toggle_dev_mode: bool,
 }
 
 pub struct SoundPlayer {
-    pub music: audio::Sound,
-    pub music_playing: bool,
-    pub sound_playing: bool,
-    pub current_music_playing: bool,
+    pub music: Music,
+    pub mixer: Mixer,
+    pub listener: Listener,
+    // INFO: Looped sounds that are playing, so volume changes, pausing and moving sources reach them too.
+    pub loops: Vec<LoopingSound>,
+    pub loops_silenced: bool,
+    pub commands: Vec<SoundCommand>,
+}
+
+pub struct LoopingSound {
+    pub sound: SoundAsset,
+    pub bus: Bus,
+    // INFO: None plays at full volume wherever the listener is, e.g. the jetpack on the player itself.
+    pub pos: Option<Vec2>,
+    // INFO: Last volume and pan handed to the backend.
+    pub volume: f32,
+    pub pan: f32,
+}
+
+// INFO: Sounds are queued and handed to the audio backend once a frame by apply_sound_commands, so update
+// can run where there is no audio device, e.g. tests.
+pub enum SoundCommand {
+    Play { sound: SoundAsset, volume: f32, pan: f32, looped: bool },
+    Stop { sound: SoundAsset },
+    SetVolume { sound: SoundAsset, volume: f32, pan: f32 },
 }
 
 impl SoundPlayer {
-    pub fn tick_music(&mut self) {
-        if self.music_playing && self.music_playing != self.current_music_playing {
-            audio::play_sound(&self.music, audio::PlaySoundParams { looped: true, volume: 0.4 });
-            self.current_music_playing = self.music_playing;
+    // INFO: Music always plays, muting it is volume 0 so unmuting doesn't restart the track. Same goes for
+    // layers that aren't heard right now, see music.rs.
+    pub fn tick(&mut self, dt: f32) {
+        self.mixer.tick(dt);
+        self.music.tick(dt);
+
+        let bus_volume = self.mixer.bus_volume(Bus::Music);
+        for (i, sound) in self.music.layers.into_iter().enumerate() {
+            if self.music.layers[..i].contains(&sound) { continue; }
+
+            let volume = self.music.level_of(&sound) * bus_volume;
+            match self.music.playing.iter_mut().find(|(other, _)| *other == sound) {
+            | None => {
+                self.commands.push(SoundCommand::Play { sound, volume, pan: 0.0, looped: true });
+                self.music.playing.push((sound, volume));
+            },
+            | Some((_, current)) => {
+                if (*current - volume).abs() <= 0.001 { continue; }
+                *current = volume;
+                self.commands.push(SoundCommand::SetVolume { sound, volume, pan: 0.0 });
+            },
+            }
         }
-        if !self.music_playing && self.music_playing != self.current_music_playing {
-            audio::stop_sound(&self.music);
-            self.current_music_playing = self.music_playing;
+
+        self.update_loop_volumes();
+    }
+    fn volume_of(&self, bus: Bus, sound: &SoundAsset, pos: Option<Vec2>) -> f32 {
+        let attenuation = pos.map_or(1.0, |pos| self.listener.attenuation(pos));
+        sound.level * self.mixer.bus_volume(bus) * attenuation
+    }
+    fn pan_of(&self, pos: Option<Vec2>) -> f32 {
+        pos.map_or(0.0, |pos| self.listener.pan(pos))
+    }
+    pub fn play_sound(&mut self, bus: Bus, sound: &SoundAsset, looped: bool) {
+        self.start_sound(bus, sound, None, looped);
+    }
+    // INFO: For sounds coming from somewhere in the world. One-shots keep the volume and pan they started
+    // with, loops follow their source, see move_sound.
+    pub fn play_sound_at(&mut self, bus: Bus, sound: &SoundAsset, pos: Vec2, looped: bool) {
+        self.start_sound(bus, sound, Some(pos), looped);
+    }
+    fn start_sound(&mut self, bus: Bus, sound: &SoundAsset, pos: Option<Vec2>, looped: bool) {
+        if sound.ducks_music {
+            self.mixer.start_duck();
+        }
+
+        let volume = if looped && self.loops_silenced { 0.0 } else { self.volume_of(bus, sound, pos) };
+        let pan = self.pan_of(pos);
+        self.commands.push(SoundCommand::Play { sound: *sound, volume, pan, looped });
+        if looped {
+            self.loops.retain(|other| other.sound != *sound);
+            self.loops.push(LoopingSound { sound: *sound, bus, pos, volume, pan });
+        }
+    }
+    pub fn move_sound(&mut self, sound: &SoundAsset, pos: Vec2) {
+        for looping in self.loops.iter_mut().filter(|other| other.sound == *sound) {
+            looping.pos = Some(pos);
         }
     }
-    pub fn play_sound(&self, sound: &audio::Sound, volume: f32, looped: bool) {
-        if self.sound_playing {
-            audio::play_sound(sound, audio::PlaySoundParams { looped, volume });
+    pub fn stop_sound(&mut self, sound: &SoundAsset) {
+        if sound.ducks_music {
+            self.mixer.stop_duck();
         }
+        self.loops.retain(|other| other.sound != *sound);
+        self.commands.push(SoundCommand::Stop { sound: *sound });
+    }
+    // INFO: Pausing only silences loops, one-shots are short enough to just finish.
+    pub fn silence_loops(&mut self, silenced: bool) {
+        if self.loops_silenced == silenced { return; }
+        self.loops_silenced = silenced;
+        self.update_loop_volumes();
     }
-    pub fn stop_sound(&self, sound: &audio::Sound) {
-        audio::stop_sound(sound);
+    pub fn stop_loops(&mut self) {
+        for looping in std::mem::take(&mut self.loops) {
+            self.commands.push(SoundCommand::Stop { sound: looping.sound });
+        }
+    }
+    // INFO: Music catches up on its own in tick, loops need to be told. Only changed volumes and pans are sent.
+    pub fn update_loop_volumes(&mut self) {
+        for i in 0..self.loops.len() {
+            let LoopingSound { sound, bus, pos, volume: current, pan: current_pan } = self.loops[i];
+            let volume = if self.loops_silenced { 0.0 } else { self.volume_of(bus, &sound, pos) };
+            let pan = self.pan_of(pos);
+            if (current - volume).abs() <= 0.001 && (current_pan - pan).abs() <= 0.001 { continue; }
+
+            self.loops[i].volume = volume;
+            self.loops[i].pan = pan;
+            self.commands.push(SoundCommand::SetVolume { sound, volume, pan });
+        }
+    }
+}
+
+pub fn apply_sound_commands(game: &mut Game) {
+    for command in game.sound_player.commands.drain(..) {
+        match command {
+        | SoundCommand::Play { sound, volume, pan, looped } => {
+            let Some(sound) = game.assets.sounds.get(sound.id) else { continue };
+            sound.play(volume, pan, looped);
+        },
+        | SoundCommand::Stop { sound } => {
+            let Some(sound) = game.assets.sounds.get(sound.id) else { continue };
+            sound.stop();
+        },
+        | SoundCommand::SetVolume { sound, volume, pan } => {
+            let Some(sound) = game.assets.sounds.get(sound.id) else { continue };
+            sound.set_volume(volume, pan);
+        },
+        }
     }
 }
 
@@ -199,7 +390,9 @@ fn window_conf() -> Conf {
 #[macroquad::main(window_conf)]
 async fn main() {
     let loading_screen = asset::get_loading_screen_asset().await;
-    {
+
+    // INFO: Settings aren't loaded yet, so always pixel perfect. Set every frame to follow window resizes.
+    fn set_loading_camera() {
         let mut ui_camera_origin = Rect {
             x: -(GAME_WIDTH_F32 / 2.0),
             y: -(GAME_HEIGHT_F32 / 2.0),
@@ -212,17 +405,26 @@ async fn main() {
         ui_camera_origin.w *= 4.0;
         ui_camera_origin.h *= 4.0;
         
+        let viewport = viewport::fit_viewport(vec2(screen_width(), screen_height()), false);
         let mut camera = Camera2D::from_display_rect(ui_camera_origin);
         camera.zoom.y *= -1.0;
+        camera.viewport = Some(viewport.gl_viewport(screen_height()));
         set_camera(&camera);
     }
 
-    sprite::draw_ui(vec2(0.0, 0.0), vec2(4.0, 4.0), &loading_screen.derive_sprite());
+    let mut sprite_batch = SpriteBatch::new();
+    set_loading_camera();
+    sprite_batch.draw_ui(vec2(0.0, 0.0), vec2(4.0, 4.0), &loading_screen.derive_sprite());
+    sprite_batch.flush();
     next_frame().await;
 
     async fn load() -> Game {
         rand::srand(miniquad::date::now() as u64);
         let assets = init_assets().await;
+
+        get_default_font().populate_font_cache(&Font::latin_character_list(), 16);
+        get_default_font().populate_font_cache(&Font::latin_character_list(), 32);
+
         let game = init(assets).await;
         game
     }
@@ -235,13 +437,38 @@ async fn main() {
             game = loaded;
             break;
         }
-        sprite::draw_ui(vec2(0.0, 0.0), vec2(4.0, 4.0), &loading_screen.derive_sprite());
+        set_loading_camera();
+        sprite_batch.draw_ui(vec2(0.0, 0.0), vec2(4.0, 4.0), &loading_screen.derive_sprite());
+        sprite_batch.flush();
         next_frame().await;
     }
 
+    settings::apply_settings(&game.settings, &mut game.sound_player, true);
+    prevent_quit();
+
+    // INFO: Only dev builds watch asset/, building the watcher already stats every file.
+    #[cfg(not(target_family = "wasm"))]
+    let mut asset_watcher = update::DEV_MODE.then(|| hot_reload::AssetWatcher::new(&game.assets));
+
     loop {
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(asset_watcher) = &mut asset_watcher {
+            hot_reload::poll(&mut game, asset_watcher, get_frame_time()).await;
+        }
+
         update(&mut game);
+        save::tick_autosave(&mut game, get_frame_time());
         render(&mut game);
+        apply_sound_commands(&mut game);
+
+        if is_quit_requested() {
+            let in_run = game.main_ui_state == MainUIState::InGame || game.main_ui_state.is_paused();
+            if in_run && !game.demolisher_started {
+                save::save_game(&mut game);
+            }
+            break;
+        }
+
         next_frame().await;
     }
 }
@@ -347,6 +574,8 @@ impl<T: Clone, const N: usize> Array<T, N> {
         Self::with_length(default_element, 0)
     }
     pub fn with_length(default_element: T, length: usize) -> Self {
+        assert!(length <= N, "Array length {} is over its capacity {}", length, N);
+
         let items = vec![default_element; N].into_boxed_slice();
         let slice = Box::leak(items);
         let slice = slice.as_mut_ptr() as _;
@@ -384,4 +613,63 @@ impl<T: Clone, const N: usize> Array<T, N> {
     pub fn slice(&self) -> &[T] {
         &self.items[..self.length]
     }
+    pub fn slice_mut(&mut self) -> &mut [T] {
+        &mut self.items[..self.length]
+    }
+    // INFO: Order isn't kept, the last item takes the removed one's place.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.length, "Array index {} is over its length {}", index, self.length);
+
+        self.length -= 1;
+        self.items.swap(index, self.length);
+        self.items[self.length].clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array_push_pop_is_lifo() {
+        let mut array = Array::<i32, 3>::new(0);
+        assert!(array.push(1));
+        assert!(array.push(2));
+        assert_eq!(array.slice(), &[1, 2]);
+        assert_eq!(array.pop(), Some(2));
+        assert_eq!(array.pop(), Some(1));
+        assert_eq!(array.pop(), None);
+    }
+
+    #[test]
+    fn array_refuses_to_push_over_capacity() {
+        let mut array = Array::<i32, 2>::new(0);
+        assert!(array.push(1));
+        assert!(array.push(2));
+        assert!(!array.push(3));
+        assert_eq!(array.slice(), &[1, 2]);
+        assert_eq!(array.cap(), 2);
+    }
+
+    #[test]
+    fn array_swap_remove_moves_last_into_place() {
+        let mut array = Array::<i32, 4>::new(0);
+        for i in 1..=4 { array.push(i); }
+        assert_eq!(array.swap_remove(1), 2);
+        assert_eq!(array.slice(), &[1, 4, 3]);
+    }
+
+    #[test]
+    fn array_with_length_exposes_default_elements() {
+        let mut array = Array::<i32, 4>::with_length(7, 2);
+        assert_eq!(array.slice(), &[7, 7]);
+        array.clear();
+        assert!(array.slice().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn array_with_length_over_capacity_panics() {
+        Array::<i32, 2>::with_length(0, 3);
+    }
 }
@@ -0,0 +1,246 @@
+// INFO: Sprite manifests, one per texture in data/sprites/ with the texture's name. They name every animation
+// on a sheet along with its frames, how long each frame is shown and how it loops, init_assets only asks for
+// animations by name. Embedded like the tile registry, so web builds get them too. Records (see data.rs):
+//
+//   cell  W H                           cell size for the grid records below it, every cell has a 1px border
+//                                       so it takes W+2 by H+2 pixels of the sheet
+//   grid  NAME MODE COL ROW FRAMES MS   FRAMES cells to the right of cell COL,ROW (counted from 0), wrapping
+//                                       onto the next row of cells at the right edge of the texture
+//   rect  NAME MODE X Y W H MS          one frame at an exact rect in pixels, more rect records with the same
+//                                       name add frames to it
+//
+// MODE is a LoopMode. MS is how long frames are shown in milliseconds, one number for all of them or a comma
+// separated list with one per frame, "-" holds the frame.
+//
+// Problems are collected while loading instead of stopping at the first one (see AssetState::manifest_errors),
+// so a broken sheet reports every missing and out of bounds frame in one go.
+
+use crate::prelude::*;
+
+use crate::asset::{ AssetState };
+use crate::atlas::{ AtlasImage };
+use crate::data::{ self, DataError, DataRecord };
+use crate::sprite::{ LoopMode, new_sprite_asset };
+
+#[derive(Debug)]
+pub struct ManifestAnim {
+    pub name: String,
+    pub line: usize,
+    pub mode: LoopMode,
+    pub frames: Vec<Rect>,
+    // INFO: In seconds, one per frame.
+    pub durations: Vec<f32>,
+}
+
+#[derive(Debug, Default)]
+pub struct SpriteManifest {
+    pub anims: Vec<ManifestAnim>,
+}
+
+fn durations(record: &DataRecord, index: usize, frame_count: usize) -> Result<Vec<f32>, DataError> {
+    let field = record.str(index)?;
+    if field == "-" {
+        return Ok(vec![NOT_SO_VERY_BIG_NUMBER; frame_count]);
+    }
+
+    let mut durations = Vec::with_capacity(frame_count);
+    for ms in field.split(',') {
+        let ms: f32 = ms.parse().map_err(|_| record.error(format!("can't parse \"{}\"", ms)))?;
+        if ms <= 0.0 {
+            return Err(record.error("frame durations have to be positive"));
+        }
+        durations.push(ms / 1000.0);
+    }
+
+    match durations.len() {
+    | 1 => Ok(vec![durations[0]; frame_count]),
+    | count if count == frame_count => Ok(durations),
+    | count => Err(record.error(format!("{} durations for {} frames", count, frame_count))),
+    }
+}
+
+// INFO: Grids need the texture's width to know where rows of cells wrap.
+pub fn parse_manifest(source: &str, texture_size: Vec2) -> Result<SpriteManifest, DataError> {
+    let mut manifest = SpriteManifest::default();
+    let mut cell: Option<Vec2> = None;
+
+    for record in data::records(source) {
+        match record.str(0)? {
+        | "cell" => {
+            record.expect_fields(3)?;
+            cell = Some(vec2(record.parse(1)?, record.parse(2)?));
+        },
+        | "grid" => {
+            record.expect_fields(7)?;
+            let Some(cell) = cell else {
+                return Err(record.error("grid before any cell size"));
+            };
+
+            let frame_count: usize = record.parse(5)?;
+            if frame_count == 0 {
+                return Err(record.error("grid without frames"));
+            }
+
+            let stride = cell + Vec2::splat(2.0);
+            let per_row = usize::max((texture_size.x / stride.x) as usize, 1);
+            let first: usize = record.parse(3)?;
+            let row: usize = record.parse(4)?;
+
+            let frames = (first..first+frame_count).map(|i| {
+                let pos = vec2((i % per_row) as f32, (row + i / per_row) as f32) * stride + Vec2::ONE;
+                Rect::new(pos.x, pos.y, cell.x, cell.y)
+            }).collect();
+
+            let name = record.str(1)?;
+            if manifest.anims.iter().any(|anim| anim.name == name) {
+                return Err(record.error(format!("{} is defined twice", name)));
+            }
+
+            manifest.anims.push(ManifestAnim {
+                name: name.to_string(),
+                line: record.line,
+                mode: record.variant(2, &LoopMode::VARIANTS)?,
+                frames,
+                durations: durations(&record, 6, frame_count)?,
+            });
+        },
+        | "rect" => {
+            record.expect_fields(8)?;
+            let frame = Rect::new(record.parse(3)?, record.parse(4)?, record.parse(5)?, record.parse(6)?);
+            let mode = record.variant(2, &LoopMode::VARIANTS)?;
+            let duration = durations(&record, 7, 1)?[0];
+
+            let name = record.str(1)?;
+            match manifest.anims.iter_mut().find(|anim| anim.name == name) {
+            | Some(anim) if anim.mode != mode => {
+                return Err(record.error(format!("{} was {:?} on line {}", name, anim.mode, anim.line)));
+            },
+            | Some(anim) => {
+                anim.frames.push(frame);
+                anim.durations.push(duration);
+            },
+            | None => manifest.anims.push(ManifestAnim {
+                name: name.to_string(),
+                line: record.line,
+                mode,
+                frames: vec![frame],
+                durations: vec![duration],
+            }),
+            }
+        },
+        | other => return Err(record.error(format!("unknown record \"{}\"", other))),
+        }
+    }
+
+    Ok(manifest)
+}
+
+pub fn out_of_bounds(manifest: &SpriteManifest, texture_size: Vec2) -> Vec<String> {
+    let mut errors = Vec::new();
+    for anim in &manifest.anims {
+        for (i, frame) in anim.frames.iter().enumerate() {
+            if frame.x >= 0.0 && frame.y >= 0.0 && frame.right() <= texture_size.x && frame.bottom() <= texture_size.y { continue; }
+
+            errors.push(format!(
+                "line {}: frame {} of {} ({} {} {} {}) is outside the {}x{} texture",
+                anim.line, i, anim.name, frame.x, frame.y, frame.w, frame.h, texture_size.x, texture_size.y,
+            ));
+        }
+    }
+    errors
+}
+
+pub struct Sheet {
+    pub path: &'static str,
+    pub image: AtlasImage,
+    pub manifest: SpriteManifest,
+}
+
+pub fn load_sheet(state: &mut AssetState, path: &'static str, source: &str, image: AtlasImage) -> Sheet {
+    let texture_size = state.atlas.size(image);
+
+    let manifest = match parse_manifest(source, texture_size) {
+    | Ok(manifest) => manifest,
+    | Err(err) => {
+        state.manifest_errors.push(format!("{} {}", path, err));
+        SpriteManifest::default()
+    },
+    };
+
+    for err in out_of_bounds(&manifest, texture_size) {
+        state.manifest_errors.push(format!("{} {}", path, err));
+    }
+
+    Sheet { path, image, manifest }
+}
+
+impl Sheet {
+    pub fn anim(&self, state: &mut AssetState, name: &str) -> SpriteAsset {
+        let Some(anim) = self.manifest.anims.iter().find(|anim| anim.name == name) else {
+            state.manifest_errors.push(format!("{} has no animation named {}", self.path, name));
+            // INFO: Stands in so loading can carry on and report everything else too.
+            let (texture, frames) = state.atlas.place(self.image, &[Rect::new(0.0, 0.0, 1.0, 1.0)]);
+            return new_sprite_asset(state, &texture, frames, vec![NOT_SO_VERY_BIG_NUMBER], LoopMode::Loop);
+        };
+        let (texture, frames) = state.atlas.place(self.image, &anim.frames);
+        new_sprite_asset(state, &texture, frames, anim.durations.clone(), anim.mode)
+    }
+
+    // INFO: Every frame of the animation as a still of its own, for sheets that are picked from by index.
+    pub fn cells(&self, state: &mut AssetState, name: &str) -> Box<[SpriteAsset]> {
+        let Some(anim) = self.manifest.anims.iter().find(|anim| anim.name == name) else {
+            return Box::new([self.anim(state, name)]);
+        };
+        let (texture, frames) = state.atlas.place(self.image, &anim.frames);
+        frames.into_iter()
+            .map(|frame| new_sprite_asset(state, &texture, vec![frame], vec![NOT_SO_VERY_BIG_NUMBER], LoopMode::Loop))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = "
+        cell 16 16
+        grid idle  Loop     0 0 2 400
+        grid hit   PingPong 2 0 3 100,200,300
+        grid wrap  Once     3 0 2 -
+        rect door  Loop     0 18 32 16 250
+        rect door  Loop     32 18 32 16 500
+    ";
+
+    #[test]
+    fn grids_and_rects_make_frames() {
+        let manifest = parse_manifest(SHEET, vec2(72.0, 36.0)).unwrap();
+        let anim = |name: &str| manifest.anims.iter().find(|anim| anim.name == name).unwrap();
+
+        assert_eq!(anim("idle").frames, [Rect::new(1.0, 1.0, 16.0, 16.0), Rect::new(19.0, 1.0, 16.0, 16.0)]);
+        assert_eq!(anim("idle").durations, [0.4, 0.4]);
+        assert_eq!(anim("hit").durations, [0.1, 0.2, 0.3]);
+        assert_eq!(anim("hit").mode, LoopMode::PingPong);
+
+        // INFO: 72 pixels fit 4 cells, the fifth one wraps onto the second row.
+        assert_eq!(anim("wrap").frames, [Rect::new(55.0, 1.0, 16.0, 16.0), Rect::new(1.0, 19.0, 16.0, 16.0)]);
+        assert_eq!(anim("wrap").durations, [NOT_SO_VERY_BIG_NUMBER; 2]);
+
+        assert_eq!(anim("door").frames.len(), 2);
+        assert_eq!(anim("door").durations, [0.25, 0.5]);
+    }
+
+    #[test]
+    fn broken_manifests_are_reported() {
+        let error = |source: &str| parse_manifest(source, vec2(64.0, 64.0)).unwrap_err().to_string();
+
+        assert_eq!(error("grid idle Loop 0 0 2 400"), "line 1: grid before any cell size");
+        assert_eq!(error("cell 8 8\ngrid idle Loop 0 0 2 1,2,3"), "line 2: 3 durations for 2 frames");
+        assert_eq!(error("cell 8 8\ngrid idle Bounce 0 0 2 100"), "line 2: unknown name \"Bounce\"");
+        assert_eq!(error("frame idle"), "line 1: unknown record \"frame\"");
+
+        let manifest = parse_manifest(SHEET, vec2(36.0, 18.0)).unwrap();
+        let errors = out_of_bounds(&manifest, vec2(36.0, 18.0));
+        assert_eq!(errors.len(), 7);
+        assert_eq!(errors[0], "line 4: frame 0 of hit (1 19 16 16) is outside the 36x18 texture");
+    }
+}
@@ -0,0 +1,389 @@
+// INFO: What the player has discovered of the world (see fog), one pixel per tile. The map keeps tiles as they
+// were last seen, so a tunnel dug behind the player's back only shows up once they come back. The image is
+// the map itself, the texture is only made on the first draw (headless has
+// no GL) and after that gets just the rect that changed, uploading a Huge world every frame would cost more
+// than everything else on screen.
+
+use crate::prelude::*;
+
+use crate::lighting;
+use crate::fog;
+use crate::tile::{ TileChunk };
+
+// INFO: In tiles.
+const MINIMAP_TILES: IVec2 = ivec2(48, 36);
+const MINIMAP_SCALE: f32 = 2.0;
+// INFO: In pixels, see thumbnail.
+pub const THUMBNAIL_SIDE: usize = 64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MapCategory {
+    Open,
+    Ladder,
+    Stone,
+    HardStone,
+    Ore,
+    Barrier,
+    Wall,
+}
+
+pub fn map_category(tile: Tile) -> MapCategory {
+    match tile {
+    | Tile::Barrier   => MapCategory::Barrier,
+    | Tile::HardStone => MapCategory::HardStone,
+    | _ if tile.can_climb() => MapCategory::Ladder,
+    | _ if tile.can_walk_through() => MapCategory::Open,
+    | _ if tile.item_drop() != ItemKind::Air => MapCategory::Ore,
+    | _ if tile.can_mine() => MapCategory::Stone,
+    | _ => MapCategory::Wall,
+    }
+}
+
+// INFO: Ores take the color of what they drop, gems the same tint they glow with.
+pub fn map_color(tile: Tile) -> Color {
+    match map_category(tile) {
+    | MapCategory::Open      => Color::from_hex(0x2b2424),
+    | MapCategory::Ladder    => Color::from_hex(0xc29a5b),
+    | MapCategory::Stone     => Color::from_hex(0x6b6464),
+    | MapCategory::HardStone => Color::from_hex(0x3e4a61),
+    | MapCategory::Barrier   => Color::from_hex(0xb33831),
+    | MapCategory::Wall      => Color::from_hex(0x171313),
+    | MapCategory::Ore       => match tile.item_drop() {
+        | ItemKind::CopperOre => Color::from_hex(0xd2773a),
+        | ItemKind::IronOre   => Color::from_hex(0xc7cfcc),
+        | ItemKind::GoldOre   => Color::from_hex(0xf2c94c),
+        | _ => {
+            let glow = lighting::gem_glow(tile).unwrap_or(Vec3::ONE);
+            Color::new(glow.x, glow.y, glow.z, 1.0)
+        },
+        },
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Landmark {
+    Statue,
+    Minecart,
+    Elevator,
+    Demolisher,
+}
+
+impl Landmark {
+    pub const LENGTH: usize = std::mem::variant_count::<Self>();
+    pub const VARIANTS: [Landmark; Self::LENGTH] = const {
+        let mut variants = [Landmark::Statue; Self::LENGTH];
+
+        let mut i = 0;
+        while i < Self::LENGTH {
+            variants[i] = unsafe { std::mem::transmute::<u8, Landmark>(i as u8) };
+            i += 1;
+        }
+
+        variants
+    };
+
+    pub fn name(self) -> &'static str {
+        match self {
+        | Landmark::Statue     => "Statue",
+        | Landmark::Minecart   => "Minecart",
+        | Landmark::Elevator   => "Elevator",
+        | Landmark::Demolisher => "Demolisher",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+        | Landmark::Statue     => Color::from_hex(0x4da6ff),
+        | Landmark::Minecart   => Color::from_hex(0xf2a65e),
+        | Landmark::Elevator   => Color::from_hex(0x7ec850),
+        | Landmark::Demolisher => Color::from_hex(0xe83b3b),
+        }
+    }
+}
+
+pub struct Minimap {
+    // INFO: In tiles, same as the world.
+    pub width: usize,
+    pub height: usize,
+    // INFO: Top row is the top of the world, image rows go down while world y goes up.
+    pub image: Image,
+    // INFO: Tiles changed since the last upload, min inclusive and max exclusive.
+    pub dirty: Option<(IVec2, IVec2)>,
+    pub texture: Option<Texture2D>,
+}
+
+impl Minimap {
+    // INFO: Paints everything already discovered, for new runs and loaded saves alike.
+    pub fn new(world: &World) -> Minimap {
+        let width = world.layout.tile_width_i32() as usize;
+        let height = world.layout.tile_height_i32() as usize;
+
+        let mut minimap = Minimap {
+            width,
+            height,
+            image: Image::gen_image_color(width as u16, height as u16, BLANK),
+            dirty: None,
+            texture: None,
+        };
+
+        let tiles = world.tiles();
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                if !world.is_discovered(ivec2(x, y)) { continue; }
+                minimap.paint(ivec2(x, y), tiles.at_tile_pos(ivec2(x, y)).kind);
+            }
+        }
+        minimap
+    }
+
+    fn paint(&mut self, tile_pos: IVec2, tile: Tile) {
+        let pixel_i = (self.height - 1 - tile_pos.y as usize) * self.width + tile_pos.x as usize;
+        let color: [u8; 4] = map_color(tile).into();
+
+        let pixel = &mut self.image.get_image_data_mut()[pixel_i];
+        if *pixel == color { return; }
+        *pixel = color;
+
+        self.dirty = Some(match self.dirty {
+        | Some((min, max)) => (min.min(tile_pos), max.max(tile_pos + IVec2::ONE)),
+        | None => (tile_pos, tile_pos + IVec2::ONE),
+        });
+    }
+
+    // INFO: Discovered tiles in sight are repainted every time, they are the only ones that can change while
+    // watched.
+    pub fn repaint_around(&mut self, world: &World, eye: IVec2) {
+        let tiles = world.tiles();
+
+        for y in -fog::SIGHT_RADIUS..=fog::SIGHT_RADIUS {
+            for x in -fog::SIGHT_RADIUS..=fog::SIGHT_RADIUS {
+                let tile_pos = eye + ivec2(x, y);
+                if !world.is_discovered(tile_pos) { continue; }
+
+                self.paint(tile_pos, tiles.at_tile_pos(tile_pos).kind);
+            }
+        }
+    }
+
+    pub fn texture(&mut self) -> Texture2D {
+        let Some(texture) = &self.texture else {
+            let texture = Texture2D::from_image(&self.image);
+            texture.set_filter(FilterMode::Nearest);
+            self.dirty = None;
+            return self.texture.insert(texture).clone();
+        };
+
+        if let Some((min, max)) = self.dirty.take() {
+            let size = max - min;
+            let y = self.height as i32 - max.y;
+            let part = self.image.sub_image(Rect::new(min.x as f32, y as f32, size.x as f32, size.y as f32));
+            texture.update_part(&part, min.x, y, size.x, size.y);
+        }
+        texture.clone()
+    }
+}
+
+// INFO: A square picture of a save's map for the Saves screen, built straight from its chunks (width and
+// height are in chunks) so a slot can be shown without loading it. Each pixel covers a block of tiles and
+// shows the first discovered tunnel in it, so dug paths stand out, otherwise whatever rock was discovered.
+pub fn thumbnail(chunks: &[TileChunk], width: usize, height: usize) -> Image {
+    let tile_width = width * CHUNK_SIDE;
+    let tile_height = height * CHUNK_SIDE;
+    let block = usize::max(tile_width, tile_height).div_ceil(THUMBNAIL_SIDE).max(1);
+    let offset = ivec2(
+        (THUMBNAIL_SIDE - tile_width.div_ceil(block)) as i32 / 2,
+        (THUMBNAIL_SIDE - tile_height.div_ceil(block)) as i32 / 2,
+    );
+
+    let mut image = Image::gen_image_color(THUMBNAIL_SIDE as u16, THUMBNAIL_SIDE as u16, BLANK);
+    for block_y in 0..tile_height.div_ceil(block) {
+        for block_x in 0..tile_width.div_ceil(block) {
+            let mut found = None;
+
+            'block: for y in block_y*block..usize::min((block_y+1)*block, tile_height) {
+                for x in block_x*block..usize::min((block_x+1)*block, tile_width) {
+                    let tile_pos = ivec2(x as i32, y as i32);
+                    let chunk = &chunks[chunk_index_at(tile_pos_to_chunk_pos(tile_pos), width)];
+                    if !chunk.discovered[tile_index_at(tile_pos)] { continue; }
+
+                    let tile = chunk.tiles[tile_index_at(tile_pos)];
+                    if tile.can_walk_through() {
+                        found = Some(tile);
+                        break 'block;
+                    }
+                    found.get_or_insert(tile);
+                }
+            }
+
+            let Some(tile) = found else { continue };
+            let pos = offset + ivec2(block_x as i32, (tile_height.div_ceil(block) - 1 - block_y) as i32);
+            image.set_pixel(pos.x as u32, pos.y as u32, map_color(tile));
+        }
+    }
+    image
+}
+
+// INFO: Window is in tiles (y up), dest is in UI pixels. Landmarks are only drawn once the ground they are on
+// has been discovered, so the map never shows something the player hasn't found.
+fn draw_map_window(minimap: &mut Minimap, world: &World, window: Rect, dest: Rect, landmarks: &[(Landmark, Vec2)], player_pos: Vec2, time: f32) {
+    let texture = minimap.texture();
+    let source = Rect::new(window.x, minimap.height as f32 - window.y - window.h, window.w, window.h);
+
+    draw_rectangle(dest.x, dest.y, dest.w, dest.h, Color::from_hex(0x0b0909));
+    draw_texture_ex(&texture, dest.x, dest.y, WHITE, DrawTextureParams {
+        dest_size: Some(dest.size()),
+        source: Some(source),
+        ..Default::default()
+    });
+    draw_rectangle_lines(dest.x-2.0, dest.y-2.0, dest.w+4.0, dest.h+4.0, 2.0, Color::from_hex(0xc7cfcc));
+
+    let to_dest = |world_pos: Vec2| -> Option<Vec2> {
+        let tile_pos = world_pos / TILE_SIDE_F32;
+        if !window.contains(tile_pos) { return None; }
+        let t = (tile_pos - window.point()) / window.size();
+        Some(vec2(dest.x + t.x*dest.w, dest.y + (1.0-t.y)*dest.h))
+    };
+
+    let marker_size = f32::max(dest.w / window.w * 2.0, 6.0);
+    for &(landmark, pos) in landmarks {
+        if !world.is_discovered(world_pos_to_tile_pos(pos)) { continue; }
+        let Some(pos) = to_dest(pos) else { continue };
+
+        let half = marker_size / 2.0;
+        draw_rectangle(pos.x-half-1.0, pos.y-half-1.0, marker_size+2.0, marker_size+2.0, BLACK);
+        draw_rectangle(pos.x-half, pos.y-half, marker_size, marker_size, landmark.color());
+    }
+
+    let blink = (time * 3.0) as i32 % 2 == 0;
+    if let Some(pos) = to_dest(player_pos).filter(|_| blink) {
+        let half = marker_size / 2.0;
+        draw_rectangle(pos.x-half, pos.y-half, marker_size, marker_size, WHITE);
+    }
+}
+
+// INFO: Expects the UI camera. Follows the player and stops at the edges of the world like the camera does.
+pub fn draw_minimap(minimap: &mut Minimap, world: &World, pos: Vec2, landmarks: &[(Landmark, Vec2)], player_pos: Vec2, time: f32) {
+    let size = MINIMAP_TILES.as_vec2();
+    let world_size = vec2(minimap.width as f32, minimap.height as f32);
+    let corner = (player_pos / TILE_SIDE_F32 - size/2.0).round().clamp(Vec2::ZERO, world_size - size);
+
+    let window = Rect::new(corner.x, corner.y, size.x, size.y);
+    let dest = Rect::new(pos.x, pos.y, size.x*MINIMAP_SCALE, size.y*MINIMAP_SCALE);
+    draw_map_window(minimap, world, window, dest, landmarks, player_pos, time);
+}
+
+pub fn minimap_size() -> Vec2 {
+    MINIMAP_TILES.as_vec2() * MINIMAP_SCALE
+}
+
+// INFO: Expects the UI camera. Whole world fitted on screen with a legend under it.
+pub fn draw_full_map(minimap: &mut Minimap, world: &World, landmarks: &[(Landmark, Vec2)], player_pos: Vec2, time: f32) {
+    draw_rectangle(0.0, 0.0, UI_WIDTH_F32, UI_HEIGHT_F32, Color::from_rgba(0, 0, 0, 200));
+
+    let world_size = vec2(minimap.width as f32, minimap.height as f32);
+    let area = vec2(UI_WIDTH_F32 - 48.0, UI_HEIGHT_F32 - 96.0);
+    let scale = f32::min(area.x / world_size.x, area.y / world_size.y);
+    let size = world_size * scale;
+    let pos = vec2((UI_WIDTH_F32 - size.x) / 2.0, 24.0);
+
+    let window = Rect::new(0.0, 0.0, world_size.x, world_size.y);
+    draw_map_window(minimap, world, window, Rect::new(pos.x, pos.y, size.x, size.y), landmarks, player_pos, time);
+
+    let legend = [
+        ( "Ladder"    , map_color(Tile::BackgroundStoneLadder) ),
+        ( "Ore"       , map_color(Tile::StoneGoldOre)          ),
+        ( "Hard Stone", map_color(Tile::HardStone)             ),
+        ( "Barrier"   , map_color(Tile::Barrier)               ),
+    ];
+    let landmark_legend = Landmark::VARIANTS.map(|landmark| (landmark.name(), landmark.color()));
+
+    let mut cursor = vec2(24.0, UI_HEIGHT_F32 - 48.0);
+    for row in [&legend[..], &landmark_legend[..]] {
+        cursor.x = 24.0;
+        for (name, color) in row {
+            draw_rectangle(cursor.x, cursor.y-10.0, 10.0, 10.0, *color);
+            draw_text(name, cursor.x+16.0, cursor.y, 16.0, WHITE);
+            cursor.x += 140.0;
+        }
+        cursor.y += 20.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::headless::{ headless_game, idle };
+    use crate::save::{ SaveData };
+
+    #[test]
+    fn tiles_fall_into_their_categories() {
+        assert_eq!(map_category(Tile::BackgroundStoneLadder), MapCategory::Ladder);
+        assert_eq!(map_category(Tile::BackgroundStone), MapCategory::Open);
+        assert_eq!(map_category(Tile::StoneIronOre), MapCategory::Ore);
+        assert_eq!(map_category(Tile::StoneRuby), MapCategory::Ore);
+        assert_eq!(map_category(Tile::Stone), MapCategory::Stone);
+        assert_eq!(map_category(Tile::HardStone), MapCategory::HardStone);
+        assert_eq!(map_category(Tile::Barrier), MapCategory::Barrier);
+        assert_eq!(map_category(Tile::WorldBoundary), MapCategory::Wall);
+    }
+
+    fn bytes(color: Color) -> [u8; 4] {
+        color.into()
+    }
+
+    fn pixel(minimap: &Minimap, tile_pos: IVec2) -> [u8; 4] {
+        minimap.image.get_image_data()[(minimap.height - 1 - tile_pos.y as usize) * minimap.width + tile_pos.x as usize]
+    }
+
+    #[test]
+    fn discovered_tiles_are_painted_as_they_change() {
+        let mut game = headless_game(1);
+        let spawn = game.world.layout.spawn_i32;
+        let floor = spawn - ivec2(0, 1);
+
+        let mut minimap = Minimap::new(&game.world);
+        assert_eq!(pixel(&minimap, floor), bytes(map_color(Tile::Stone)));
+        assert_eq!(pixel(&minimap, ivec2(1, 1)), bytes(BLANK));
+
+        let mut commands = game.world.commands(&game.bump);
+        commands.set_tile(floor, Tile::BackgroundStoneLadder);
+        game.world.apply_commands(commands);
+
+        minimap.dirty = None;
+        minimap.repaint_around(&game.world, spawn);
+        assert_eq!(pixel(&minimap, floor), bytes(map_color(Tile::BackgroundStoneLadder)));
+        assert_eq!(minimap.dirty, Some((floor, floor + IVec2::ONE)));
+    }
+
+    #[test]
+    fn thumbnail_shows_only_discovered_tiles() {
+        let game = headless_game(1);
+        let layout = game.world.layout;
+        let image = thumbnail(&game.world.chunks, layout.width, layout.height);
+        assert_eq!((image.width as usize, image.height as usize), (THUMBNAIL_SIDE, THUMBNAIL_SIDE));
+
+        // INFO: Only the surface is discovered on a new run, the top of the map has color and the bottom doesn't.
+        let data = image.get_image_data();
+        let row_has_color = |y: usize| data[y*THUMBNAIL_SIDE..(y+1)*THUMBNAIL_SIDE].iter().any(|pixel| pixel[3] != 0);
+        let first_row = (0..THUMBNAIL_SIDE).find(|y| row_has_color(*y)).unwrap();
+        assert!(!row_has_color(THUMBNAIL_SIDE - 1 - first_row));
+    }
+
+    #[test]
+    fn discovered_tiles_survive_a_save() {
+        let mut game = headless_game(1);
+        let mut commands = game.world.commands(&game.bump);
+        commands.set_tile_area(game.world.layout.spawn_i32 - ivec2(0, 6), ivec2(1, 6), Tile::BackgroundStone);
+        game.world.apply_commands(commands);
+        idle(&mut game, 0.1);
+
+        let bytes = SaveData::capture(&game).encode();
+        let mut loaded = headless_game(2);
+        SaveData::decode(&bytes).unwrap().apply(&mut loaded);
+
+        let discovered = |game: &Game| game.world.chunks.iter().map(|chunk| chunk.discovered).collect::<Vec<_>>();
+        assert_eq!(discovered(&loaded), discovered(&game));
+        assert_eq!(loaded.minimap.image.bytes, game.minimap.image.bytes);
+    }
+}
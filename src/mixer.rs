@@ -0,0 +1,184 @@
+// INFO: Volume side of the sound player. Every sound plays on a bus, what reaches the backend is the sound's own
+// level (see load_assets) * bus volume * master. Music is also ducked for a while after a loud one-shot starts,
+// macroquad can't tell how long a sound is, so the duck simply holds for DUCK_HOLD seconds.
+// Sounds coming from somewhere in the world are quieter the further they are from the Listener.
+
+use crate::prelude::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bus {
+    Music,
+    Sfx,
+    Ui,
+    Ambience,
+}
+
+impl Bus {
+    pub const LENGTH: usize = std::mem::variant_count::<Self>();
+    pub const VARIANTS: [Bus; Self::LENGTH] = const {
+        let mut variants = [Bus::Music; Self::LENGTH];
+
+        let mut i = 0;
+        while i < Self::LENGTH {
+            variants[i] = unsafe { std::mem::transmute::<u8, Bus>(i as u8) };
+            i += 1;
+        }
+
+        variants
+    };
+
+    pub fn name(self) -> &'static str {
+        match self {
+        | Bus::Music    => "Music",
+        | Bus::Sfx      => "Sound",
+        | Bus::Ui       => "Interface",
+        | Bus::Ambience => "Ambience",
+        }
+    }
+
+    // INFO: Used for settings keys, changing these forgets stored volumes.
+    pub fn key(self) -> &'static str {
+        match self {
+        | Bus::Music    => "music",
+        | Bus::Sfx      => "sfx",
+        | Bus::Ui       => "ui",
+        | Bus::Ambience => "ambience",
+        }
+    }
+}
+
+const DUCK_LEVEL: f32 = 0.3;
+const DUCK_HOLD: f32 = 2.5;
+const DUCK_ATTACK: f32 = 4.0;
+const DUCK_RELEASE: f32 = 0.8;
+
+#[derive(Debug, Clone)]
+pub struct Mixer {
+    pub master: f32,
+    pub volumes: [f32; Bus::LENGTH],
+    pub muted: [bool; Bus::LENGTH],
+    pub duck: f32,
+    pub duck_hold: f32,
+}
+
+impl Default for Mixer {
+    fn default() -> Mixer {
+        Mixer {
+            master: 1.0,
+            volumes: [1.0; Bus::LENGTH],
+            muted: [false; Bus::LENGTH],
+            duck: 1.0,
+            duck_hold: 0.0,
+        }
+    }
+}
+
+impl Mixer {
+    pub fn bus_volume(&self, bus: Bus) -> f32 {
+        if self.muted[bus as usize] { return 0.0; }
+
+        let duck = if bus == Bus::Music { self.duck } else { 1.0 };
+        self.master * self.volumes[bus as usize] * duck
+    }
+
+    pub fn start_duck(&mut self) {
+        self.duck_hold = DUCK_HOLD;
+    }
+
+    pub fn stop_duck(&mut self) {
+        self.duck_hold = 0.0;
+    }
+
+    // INFO: Ducks quickly, comes back slowly so the music doesn't jump back in.
+    pub fn tick(&mut self, dt: f32) {
+        if self.duck_hold > 0.0 {
+            self.duck_hold -= dt;
+            self.duck = f32::max(self.duck - DUCK_ATTACK*dt, DUCK_LEVEL);
+        } else {
+            self.duck = f32::min(self.duck + DUCK_RELEASE*dt, 1.0);
+        }
+    }
+}
+
+// INFO: World pixels, the screen is 160 wide so anything on screen is still clearly heard.
+const HEAR_NEAR: f32 = 48.0;
+const HEAR_FAR: f32 = 192.0;
+// INFO: How far to the side a sound has to be to only come out of one speaker. A screen width, so the edges
+// of the screen are clearly off to one side without going silent on the other.
+const PAN_FULL: f32 = 160.0;
+
+// INFO: Follows the player, see simulate.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Listener {
+    pub pos: Vec2,
+}
+
+impl Listener {
+    // INFO: Full volume up close, then eased out so sounds fade away instead of cutting off at HEAR_FAR.
+    pub fn attenuation(&self, pos: Vec2) -> f32 {
+        let t = ((self.pos.distance(pos) - HEAR_NEAR) / (HEAR_FAR - HEAR_NEAR)).clamp(0.0, 1.0);
+        (1.0 - t) * (1.0 - t)
+    }
+    // INFO: -1 is all the way left, 1 all the way right. Height doesn't matter, there are only two speakers.
+    pub fn pan(&self, pos: Vec2) -> f32 {
+        ((pos.x - self.pos.x) / PAN_FULL).clamp(-1.0, 1.0)
+    }
+}
+
+// INFO: Volume of the left and right copy of a sound, see stereo. The far side fades out while the near one
+// stays at full volume, so a centered sound is exactly as loud as it was before it got split.
+pub fn side_gains(pan: f32) -> [f32; 2] {
+    [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bus_volume_multiplies_master_and_respects_mute() {
+        let mut mixer = Mixer { master: 0.5, ..Default::default() };
+        mixer.volumes[Bus::Sfx as usize] = 0.5;
+        assert_eq!(mixer.bus_volume(Bus::Sfx), 0.25);
+        assert_eq!(mixer.bus_volume(Bus::Ui), 0.5);
+
+        mixer.muted[Bus::Sfx as usize] = true;
+        assert_eq!(mixer.bus_volume(Bus::Sfx), 0.0);
+    }
+
+    #[test]
+    fn ducking_only_touches_music_and_wears_off() {
+        let mut mixer = Mixer::default();
+        mixer.start_duck();
+        for _ in 0..60 { mixer.tick(1.0/60.0); }
+
+        assert_eq!(mixer.bus_volume(Bus::Music), DUCK_LEVEL);
+        assert_eq!(mixer.bus_volume(Bus::Sfx), 1.0);
+
+        for _ in 0..600 { mixer.tick(1.0/60.0); }
+        assert_eq!(mixer.bus_volume(Bus::Music), 1.0);
+    }
+
+    #[test]
+    fn attenuation_falls_off_with_distance() {
+        let listener = Listener { pos: vec2(100.0, 100.0) };
+        assert_eq!(listener.attenuation(vec2(100.0, 100.0 + HEAR_NEAR)), 1.0);
+        assert_eq!(listener.attenuation(vec2(100.0 + HEAR_FAR, 100.0)), 0.0);
+
+        let near = listener.attenuation(vec2(100.0 + HEAR_NEAR + 20.0, 100.0));
+        let far = listener.attenuation(vec2(100.0 + HEAR_NEAR + 60.0, 100.0));
+        assert!(near < 1.0 && far < near && far > 0.0);
+    }
+
+    #[test]
+    fn pan_follows_the_side_a_sound_is_on() {
+        let listener = Listener { pos: vec2(100.0, 100.0) };
+        assert_eq!(listener.pan(vec2(100.0, 300.0)), 0.0);
+        assert_eq!(listener.pan(vec2(100.0 - PAN_FULL*2.0, 100.0)), -1.0);
+        assert_eq!(listener.pan(vec2(100.0 + PAN_FULL/2.0, 100.0)), 0.5);
+
+        assert_eq!(side_gains(0.0), [1.0, 1.0]);
+        assert_eq!(side_gains(0.5), [0.5, 1.0]);
+        assert_eq!(side_gains(-1.0), [1.0, 0.0]);
+    }
+}
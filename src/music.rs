@@ -0,0 +1,127 @@
+// INFO: Soundtrack is split into layers, one per stage of a run. Every layer is its own looped sound and they
+// all start together so they stay in sync, changing stage only crossfades their volumes. Layers without a file
+// of their own fall back to the soundtrack (see load_assets), layers sharing a sound just add up, so with no
+// stems at all the fade only moves the soundtrack between layer levels.
+
+use crate::prelude::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MusicLayer {
+    Overworld,
+    Shallow,
+    Deep,
+    Jetpack,
+    Finale,
+}
+
+impl MusicLayer {
+    pub const LENGTH: usize = std::mem::variant_count::<Self>();
+    pub const VARIANTS: [MusicLayer; Self::LENGTH] = const {
+        let mut variants = [MusicLayer::Overworld; Self::LENGTH];
+
+        let mut i = 0;
+        while i < Self::LENGTH {
+            variants[i] = unsafe { std::mem::transmute::<u8, MusicLayer>(i as u8) };
+            i += 1;
+        }
+
+        variants
+    };
+}
+
+// INFO: Seconds for a full swap from one layer to another.
+const CROSSFADE_TIME: f32 = 3.0;
+
+pub struct Music {
+    pub layers: [SoundAsset; MusicLayer::LENGTH],
+    pub target: MusicLayer,
+    pub weights: [f32; MusicLayer::LENGTH],
+    // INFO: Volume last handed to the backend for each distinct sound, empty until the first tick starts them.
+    pub playing: Vec<(SoundAsset, f32)>,
+}
+
+impl Music {
+    pub fn new(layers: [SoundAsset; MusicLayer::LENGTH]) -> Music {
+        let target = MusicLayer::Overworld;
+        let mut weights = [0.0; MusicLayer::LENGTH];
+        weights[target as usize] = 1.0;
+
+        Music { layers, target, weights, playing: Vec::with_capacity(MusicLayer::LENGTH) }
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        let step = dt / CROSSFADE_TIME;
+        for layer in MusicLayer::VARIANTS {
+            let weight = &mut self.weights[layer as usize];
+            *weight = match layer == self.target {
+            | true  => f32::min(*weight + step, 1.0),
+            | false => f32::max(*weight - step, 0.0),
+            };
+        }
+    }
+
+    // INFO: Level of a sound before the Music bus, every layer playing it counts.
+    pub fn level_of(&self, sound: &SoundAsset) -> f32 {
+        let mut level = 0.0;
+        for (layer, weight) in self.layers.iter().zip(self.weights) {
+            if layer == sound { level += layer.level * weight; }
+        }
+        level
+    }
+}
+
+// INFO: Later stages win, the demolisher going off is the end of the run wherever the player is.
+pub fn music_layer(derived: &DerivedState, demolisher_started: bool) -> MusicLayer {
+    if demolisher_started {
+        MusicLayer::Finale
+    } else if derived.player_at_overworld {
+        MusicLayer::Overworld
+    } else if derived.player_can_use_jetpack {
+        MusicLayer::Jetpack
+    } else if derived.player_below_barrier {
+        MusicLayer::Deep
+    } else {
+        MusicLayer::Shallow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sound(id: usize) -> SoundAsset {
+        SoundAsset { id, level: 0.4, ducks_music: false }
+    }
+
+    #[test]
+    fn crossfade_moves_between_layers_and_keeps_shared_sounds_level() {
+        let mut music = Music::new([sound(0), sound(1), sound(1), sound(0), sound(0)]);
+        assert!((music.level_of(&sound(0)) - 0.4).abs() < 0.001);
+
+        music.target = MusicLayer::Shallow;
+        music.tick(CROSSFADE_TIME / 2.0);
+        assert!((music.level_of(&sound(0)) - 0.2).abs() < 0.001);
+        assert!((music.level_of(&sound(1)) - 0.2).abs() < 0.001);
+
+        // INFO: Shallow and Deep share a sound, so moving between them changes nothing.
+        music.tick(CROSSFADE_TIME);
+        music.target = MusicLayer::Deep;
+        music.tick(CROSSFADE_TIME / 2.0);
+        assert_eq!(music.level_of(&sound(0)), 0.0);
+        assert!((music.level_of(&sound(1)) - 0.4).abs() < 0.001);
+    }
+
+    #[test]
+    fn later_stages_win() {
+        let mut derived = DerivedState { player_at_overworld: true, ..Default::default() };
+        assert_eq!(music_layer(&derived, false), MusicLayer::Overworld);
+        assert_eq!(music_layer(&derived, true), MusicLayer::Finale);
+
+        derived.player_at_overworld = false;
+        assert_eq!(music_layer(&derived, false), MusicLayer::Shallow);
+        derived.player_below_barrier = true;
+        assert_eq!(music_layer(&derived, false), MusicLayer::Deep);
+        derived.player_can_use_jetpack = true;
+        assert_eq!(music_layer(&derived, false), MusicLayer::Jetpack);
+    }
+}
@@ -0,0 +1,281 @@
+// INFO: Cosmetic particles. They live in one fixed pool, so a busy frame drops new particles instead of
+// allocating, and have their own generator so they never touch the global one gameplay rolls with.
+// ParticleSpec says what a kind of particle looks like, bursts spawn a bunch at once and Emitters spawn a
+// steady stream at a rate.
+
+use crate::prelude::*;
+
+use crate::lighting;
+use crate::sprite::{ SpriteBatch, SpriteLayer };
+use crate::world::{ WorldTiles };
+
+pub const MAX_PARTICLES: usize = 512;
+
+const BOUNCE: f32 = 0.3;
+const FRICTION: f32 = 0.6;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ParticleSpec {
+    // INFO: Picked between the two at random.
+    pub colors: [Color; 2],
+    pub speed: [f32; 2],
+    // INFO: Radians, 0 is right and positive is counter clockwise (world y is up).
+    pub angle: f32,
+    pub spread: f32,
+    pub life: [f32; 2],
+    pub size: [f32; 2],
+    pub gravity: f32,
+    pub collides: bool,
+    // INFO: Emissive particles ignore the light, see render.
+    pub emissive: bool,
+}
+
+pub const DEBRIS: ParticleSpec = ParticleSpec {
+    colors: [Color::new(0.45, 0.42, 0.4, 1.0), Color::new(0.3, 0.28, 0.27, 1.0)],
+    speed: [30.0, 70.0],
+    angle: std::f32::consts::FRAC_PI_2,
+    spread: 1.4,
+    life: [0.6, 1.2],
+    size: [1.0, 2.0],
+    gravity: 220.0,
+    collides: true,
+    emissive: false,
+};
+
+pub const SPARKLE: ParticleSpec = ParticleSpec {
+    colors: [WHITE, WHITE],
+    speed: [2.0, 6.0],
+    angle: std::f32::consts::FRAC_PI_2,
+    spread: std::f32::consts::PI,
+    life: [0.4, 0.8],
+    size: [1.0, 1.0],
+    gravity: 0.0,
+    collides: false,
+    emissive: true,
+};
+
+pub const EXHAUST: ParticleSpec = ParticleSpec {
+    colors: [Color::new(1.0, 0.75, 0.3, 1.0), Color::new(0.55, 0.55, 0.55, 1.0)],
+    speed: [25.0, 45.0],
+    angle: -std::f32::consts::FRAC_PI_2,
+    spread: 0.35,
+    life: [0.2, 0.4],
+    size: [1.0, 2.0],
+    gravity: 0.0,
+    collides: true,
+    emissive: true,
+};
+
+pub const DUST: ParticleSpec = ParticleSpec {
+    colors: [Color::new(0.55, 0.5, 0.45, 0.8), Color::new(0.4, 0.37, 0.34, 0.8)],
+    speed: [10.0, 35.0],
+    angle: std::f32::consts::FRAC_PI_2 * 0.6,
+    spread: 0.9,
+    life: [0.8, 1.6],
+    size: [2.0, 3.0],
+    gravity: -8.0,
+    collides: false,
+    emissive: false,
+};
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Particle {
+    pub pos: Vec2,
+    pub velocity: Vec2,
+    pub color: Color,
+    pub size: f32,
+    pub life: f32,
+    pub max_life: f32,
+    pub gravity: f32,
+    pub collides: bool,
+    pub emissive: bool,
+}
+
+pub struct Particles {
+    pub pool: Array<Particle, MAX_PARTICLES>,
+    rng: rand::RandGenerator,
+}
+
+impl Default for Particles {
+    fn default() -> Particles {
+        let rng = rand::RandGenerator::new();
+        rng.srand(0x9a271c1e5);
+        Particles { pool: Array::new(Particle::default()), rng }
+    }
+}
+
+impl Particles {
+    pub fn clear(&mut self) {
+        self.pool.clear();
+    }
+
+    // INFO: Color overrides the spec's colors, for things like gem sparkles that take their tile's color.
+    pub fn burst(&mut self, spec: &ParticleSpec, pos: Vec2, count: usize, color: Option<Color>) {
+        for _ in 0..count {
+            let rng = &self.rng;
+            let angle = spec.angle + rng.gen_range(-spec.spread, spec.spread);
+            let speed = rng.gen_range(spec.speed[0], spec.speed[1]);
+            let life = rng.gen_range(spec.life[0], spec.life[1]);
+            let color = color.unwrap_or(spec.colors[rng.gen_range(0, 2)]);
+
+            let particle = Particle {
+                pos,
+                velocity: Vec2::from_angle(angle) * speed,
+                color,
+                size: rng.gen_range(spec.size[0], spec.size[1]).round(),
+                life,
+                max_life: life,
+                gravity: spec.gravity,
+                collides: spec.collides,
+                emissive: spec.emissive,
+            };
+            if !self.pool.push(particle) { return; }
+        }
+    }
+
+    // INFO: Somewhere inside a rect instead of a point.
+    pub fn burst_in(&mut self, spec: &ParticleSpec, rect: Rect, count: usize, color: Option<Color>) {
+        for _ in 0..count {
+            let pos = vec2(
+                rect.x + self.rng.gen_range(0.0, rect.w),
+                rect.y + self.rng.gen_range(0.0, rect.h),
+            );
+            self.burst(spec, pos, 1, color);
+        }
+    }
+
+    // INFO: For random rates that are too low to go through an Emitter, e.g. each gem sparkling now and then.
+    pub fn chance(&self, per_second: f32, dt: f32) -> bool {
+        self.rng.gen_range(0.0, 1.0) < per_second * dt
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Emitter {
+    pub spec: ParticleSpec,
+    // INFO: Particles per second.
+    pub rate: f32,
+    pub accumulated: f32,
+}
+
+impl Emitter {
+    pub const fn new(spec: ParticleSpec, rate: f32) -> Emitter {
+        Emitter { spec, rate, accumulated: 0.0 }
+    }
+
+    pub fn emit(&mut self, particles: &mut Particles, pos: Vec2, dt: f32) {
+        self.accumulated += self.rate * dt;
+        let count = self.accumulated as usize;
+        self.accumulated -= count as f32;
+        particles.burst(&self.spec, pos, count, None);
+    }
+}
+
+fn is_solid(tiles: &WorldTiles, layout: &WorldLayout, pos: Vec2) -> bool {
+    let tile_pos = world_pos_to_tile_pos(pos);
+    if pos.x < 0.0 || pos.y < 0.0 || tile_pos.x >= layout.tile_width_i32() || tile_pos.y >= layout.tile_height_i32() {
+        return true;
+    }
+    !tiles.at_tile_pos(tile_pos).kind.can_walk_through()
+}
+
+// INFO: Colliding particles move one axis at a time, so hitting a floor only stops the fall and they slide
+// to a halt instead of sticking to walls.
+pub fn tick_particles(particles: &mut Particles, tiles: &WorldTiles, layout: &WorldLayout, dt: f32) {
+    let mut i = 0;
+    while i < particles.pool.length {
+        let particle = &mut particles.pool.items[i];
+
+        particle.life -= dt;
+        let outside = particle.collides && is_solid(tiles, layout, particle.pos);
+        if particle.life <= 0.0 || outside {
+            particles.pool.swap_remove(i);
+            continue;
+        }
+
+        particle.velocity.y -= particle.gravity * dt;
+        let step = particle.velocity * dt;
+
+        if !particle.collides {
+            particle.pos += step;
+        } else {
+            let x_pos = particle.pos + vec2(step.x, 0.0);
+            if is_solid(tiles, layout, x_pos) {
+                particle.velocity.x *= -BOUNCE;
+            } else {
+                particle.pos = x_pos;
+            }
+
+            let y_pos = particle.pos + vec2(0.0, step.y);
+            if is_solid(tiles, layout, y_pos) {
+                particle.velocity.y *= -BOUNCE;
+                particle.velocity.x *= FRICTION;
+            } else {
+                particle.pos = y_pos;
+            }
+        }
+
+        i += 1;
+    }
+}
+
+// INFO: Goes into the world's sprite batch, between dropped items and the player. Particles fade out over the
+// last third of their life.
+pub fn draw_particles(sprite_batch: &mut SpriteBatch, particles: &Particles, layout: &WorldLayout, player_pos: Vec2) {
+    for particle in particles.pool.slice() {
+        let mut color = particle.color;
+        if !particle.emissive {
+            let light = lighting::light_at(layout, particle.pos, player_pos);
+            color.r *= light;
+            color.g *= light;
+            color.b *= light;
+        }
+        color.a *= f32::min(particle.life / particle.max_life * 3.0, 1.0);
+
+        let half = particle.size / 2.0;
+        let rect = Rect::new(particle.pos.x - half, particle.pos.y - half, particle.size, particle.size);
+        sprite_batch.draw_rect(SpriteLayer::Particles, rect, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::headless::headless_game;
+
+    #[test]
+    fn debris_lands_on_the_floor_and_dies() {
+        let game = headless_game(1);
+        let layout = game.world.layout;
+        let tiles = game.world.tiles();
+
+        // INFO: Spawn room floor is solid, the tile above it is open.
+        let floor_y = layout.spawn_f32.y * TILE_SIDE_F32;
+        let pos = vec2(layout.spawn_f32.x * TILE_SIDE_F32 + 4.0, floor_y + 10.0);
+
+        let mut particles = Particles::default();
+        particles.burst(&DEBRIS, pos, 8, None);
+        assert_eq!(particles.pool.length, 8);
+
+        for _ in 0..20 { tick_particles(&mut particles, &tiles, &layout, 1.0/60.0); }
+        for particle in particles.pool.slice() {
+            assert!(particle.pos.y >= floor_y, "{:?}", particle.pos);
+        }
+
+        for _ in 0..120 { tick_particles(&mut particles, &tiles, &layout, 1.0/60.0); }
+        assert_eq!(particles.pool.length, 0);
+    }
+
+    #[test]
+    fn pool_drops_particles_over_capacity() {
+        let mut particles = Particles::default();
+        particles.burst(&SPARKLE, Vec2::ZERO, MAX_PARTICLES + 10, None);
+        assert_eq!(particles.pool.length, MAX_PARTICLES);
+
+        let mut emitter = Emitter::new(DUST, 30.0);
+        particles.clear();
+        for _ in 0..60 { emitter.emit(&mut particles, Vec2::ZERO, 1.0/60.0); }
+        assert!((29..=30).contains(&particles.pool.length));
+    }
+}
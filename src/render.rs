@@ -1,14 +1,31 @@
 use crate::prelude::*;
 
 use crate::apply_debug_commands;
-use crate::sprite::{
-    draw_sprite, draw_sprite_scaled, draw_sprite_rotated, draw_sprite_offset,
-    draw_ui_partial, draw_ui_rotated, draw_ui,
-};
+use crate::sprite::{ SpriteBatch, SpriteLayer };
 use crate::ui::*;
+use crate::bindings::{ Bindings, BindingSlot, InputAction, key_name, pad_name, save_bindings };
+use crate::update::DEV_MODE;
+use crate::save::{ self, SaveError };
+use crate::settings;
+use crate::lighting;
+use crate::particles;
+use crate::minimap::{ self, Landmark };
+use crate::init::reset_run;
 
 
+// INFO: Writes the name being typed into the slot. Only Enter or focus loss confirm, leaving the Saves screen
+// mid rename throws the name away.
+fn finish_slot_rename(slot_renaming: &mut Option<usize>, name: &str) {
+    let Some(slot) = slot_renaming.take() else { return };
+    if let Err(err) = save::rename_slot(slot, name) {
+        warn!("Couldn't rename save: {}", err);
+    }
+}
+
 pub fn render(game: &mut Game) {
+    // INFO: Only what's drawn needs light, simulate never looks at vertex colors.
+    lighting::bake_lighting(&mut game.world, &game.visible_chunks, &game.exposed_gems, game.player.trans.pos);
+
     let statue = &game.statue;
     let minecart = &game.minecart;
     let ui_inventory_bar_frame = &game.ui_inventory_bar_frame;
@@ -21,9 +38,11 @@ pub fn render(game: &mut Game) {
     let derived = &game.derived;
     let assets = &game.assets;
     let world = &game.world;
+    let layout = world.layout;
     let visible_chunks = &game.visible_chunks;
     let upgrades = &mut game.upgrades;
     let sound_player = &mut game.sound_player;
+    let sprite_batch = &mut game.sprite_batch;
 
     let world_origin = Rect {
         x: -(GAME_WIDTH_F32 / 2.0),
@@ -32,69 +51,79 @@ pub fn render(game: &mut Game) {
         h: GAME_HEIGHT_F32,
     };
     
+    let mut camera_origin = world_origin;
+    let camera_center = game.camera.center();
+    camera_origin.x += camera_center.x;
+    camera_origin.y += camera_center.y;
+    
+    let world_target = game.world_target.get_or_insert_with(|| {
+        let target = render_target(GAME_WIDTH as u32, GAME_HEIGHT as u32);
+        target.texture.set_filter(FilterMode::Nearest);
+        target
+    }).clone();
+    let screen_viewport = Some(game.viewport.gl_viewport(screen_height()));
+
+    {
+        let camera = Camera2D {
+            render_target: Some(world_target.clone()),
+            ..Camera2D::from_display_rect(camera_origin)
+        };
+        set_camera(&camera);
+        clear_background(BLACK);
+    }
+
     // draw chunks :::
+    // INFO: Chunk meshes are built around their own origin, a model matrix moves each into place so they all
+    // share the one camera.
     for &chunk_pos in visible_chunks {
         let world_pos = chunk_pos_to_world_pos(chunk_pos);
-        
-        let mut mesh_camera_origin = world_origin;
-        mesh_camera_origin.x += player.trans.pos.x - world_pos.x;
-        mesh_camera_origin.y += player.trans.pos.y - world_pos.y;
 
-        let camera = Camera2D::from_display_rect(mesh_camera_origin);
-        set_camera(&camera);
-        
+        unsafe { get_internal_gl() }.quad_gl.push_model_matrix(Mat4::from_translation(world_pos.extend(0.0)));
         let mesh = world.mesh_at(chunk_pos);
         draw_mesh(&mesh.0);
-    }
-    
-    let mut camera_origin = world_origin;
-    camera_origin.x += player.trans.pos.x;
-    camera_origin.y += player.trans.pos.y;
-    
-    {
-        let camera = Camera2D::from_display_rect(camera_origin);
-        set_camera(&camera);
+        unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
     }
 
     let tiles = world.tiles();
     for (&tile_pos, &durability) in &game.tile_durability_map {
         let tile = tiles.at_tile_pos(tile_pos);
         let index = usize::clamp(((durability/tile.kind.durability())*4.0) as usize, 0, 3);
-        draw_sprite(tile.world_pos(), &assets.cracking[index].derive_sprite());
+        sprite_batch.draw_sprite(SpriteLayer::Ground, tile.world_pos(), &assets.cracking[index].derive_sprite());
     }
     
     for (&tile_pos, &cant_dig) in &game.tile_cant_dig_map {
         let tile = tiles.at_tile_pos(tile_pos);
         let index = usize::clamp((cant_dig*2.9) as usize, 0, 2);
-        draw_sprite(tile.world_pos(), &assets.cant_dig[index].derive_sprite());
+        sprite_batch.draw_sprite(SpriteLayer::Ground, tile.world_pos(), &assets.cant_dig[index].derive_sprite());
     }
     let _ = tiles;
     
-    // draw_sprite_offset(crusher.trans.pos, crusher.trans.offset, &crusher.sprite);
+    // sprite_batch.draw_sprite_offset(SpriteLayer::Props, crusher.trans.pos, crusher.trans.offset, &crusher.sprite);
 
     let rail_start_sprite = assets.rail_start.derive_sprite();
     let rail_sprite = assets.rail.derive_sprite();
     let rail_diagonal_sprite = assets.rail_diagonal.derive_sprite();
     
-    draw_sprite(tile_pos_to_world_pos(RAIL_START), &rail_start_sprite);
+    sprite_batch.draw_sprite(SpriteLayer::Ground, tile_pos_to_world_pos(layout.rail_start), &rail_start_sprite);
 
     // draw rail :::
-    for x in RAIL_START.x+1..=RAIL_STRAIGHT_END.x {
-        let tile_pos = ivec2(x, RAIL_START.y);
+    for x in layout.rail_start.x+1..=layout.rail_straight_end.x {
+        let tile_pos = ivec2(x, layout.rail_start.y);
         let world_pos = tile_pos_to_world_pos(tile_pos);
-        draw_sprite(world_pos, &rail_sprite);
+        sprite_batch.draw_sprite(SpriteLayer::Ground, world_pos, &rail_sprite);
     }
     
-    for (i, x) in (RAIL_STRAIGHT_END.x+1..=RAIL_DIAGONAL_END.x).enumerate() {
-        let tile_pos = ivec2(x, RAIL_START.y);
+    for (i, x) in (layout.rail_straight_end.x+1..=layout.rail_diagonal_end.x).enumerate() {
+        let tile_pos = ivec2(x, layout.rail_start.y);
         let world_pos = tile_pos_to_world_pos(tile_pos);
-        draw_sprite(world_pos+vec2(i as f32 * -2.0, i as f32 * 7.0), &rail_diagonal_sprite);
+        sprite_batch.draw_sprite(SpriteLayer::Ground, world_pos+vec2(i as f32 * -2.0, i as f32 * 7.0), &rail_diagonal_sprite);
     }
 
-    draw_sprite_rotated(minecart.trans.pos, minecart.rotation, &minecart.sprite);
-    draw_sprite(statue.trans.pos, &statue.sprite);
+    sprite_batch.draw_sprite_rotated(SpriteLayer::Props, minecart.trans.pos, minecart.rotation, &minecart.sprite);
+    sprite_batch.draw_sprite(SpriteLayer::Props, statue.trans.pos, &statue.sprite);
     
-    draw_sprite_scaled(
+    sprite_batch.draw_sprite_scaled(
+        SpriteLayer::Props,
         // INFO: *0.5 and /2.0 because as two separate operations because 0.5 is for scaling, 2.0 is for offsetting
         // by half. This makes the intent clear.
         statue.trans.pos+vec2(statue.trans.size.x-assets.sign_sell.derive_sprite().texture_frame.w*0.5/2.0, 0.0)-vec2(0.5, 0.0),
@@ -102,8 +131,9 @@ pub fn render(game: &mut Game) {
         &assets.sign_sell.derive_sprite(),
     );
     
-    draw_sprite_scaled(
-        MINECART_START-vec2(statue.trans.size.x-assets.sign_sell_here.derive_sprite().texture_frame.w*0.5/2.0, 0.0)-vec2(0.5, 0.0),
+    sprite_batch.draw_sprite_scaled(
+        SpriteLayer::Props,
+        layout.minecart_start-vec2(statue.trans.size.x-assets.sign_sell_here.derive_sprite().texture_frame.w*0.5/2.0, 0.0)-vec2(0.5, 0.0),
         vec2(0.5, 0.5),
         &assets.sign_sell_here.derive_sprite(),
     );
@@ -111,13 +141,15 @@ pub fn render(game: &mut Game) {
     if game.elevator_spawned {
         let descend_sprite = assets.sign_descend.derive_sprite();
         let jetpack_hint_sprite = assets.sign_jetpack_hint.derive_sprite();
-        draw_sprite_scaled(
+        sprite_batch.draw_sprite_scaled(
+            SpriteLayer::Props,
             statue.trans.pos-vec2(descend_sprite.texture_frame.w*0.5/2.0, 0.0)-vec2(0.5, 0.0),
             vec2(0.5, 0.5),
             &descend_sprite,
         );
-        draw_sprite_scaled(
-            ELEVATOR_PLATFORM_END -
+        sprite_batch.draw_sprite_scaled(
+            SpriteLayer::Props,
+            layout.elevator_platform_end -
                 vec2(jetpack_hint_sprite.texture_frame.w*0.5/2.0, 0.0) +
                 vec2(4.5*TILE_SIDE_F32, jetpack_hint_sprite.texture_frame.h/2.0+1.0),
             vec2(0.5, 0.5),
@@ -126,7 +158,8 @@ pub fn render(game: &mut Game) {
     }
 
     for coin in &game.coins {
-        draw_sprite_scaled(
+        sprite_batch.draw_sprite_scaled(
+            SpriteLayer::Coins,
             coin.trans.pos + vec2(0.0, derived.time_sine_4[coin.sine_index]),
             vec2(0.25, 0.25),
             &coin.sprite,
@@ -134,54 +167,72 @@ pub fn render(game: &mut Game) {
     }
     
     if game.elevator_spawned {
-        draw_sprite(elevator_platform.trans.pos, &elevator_platform.sprite);
+        sprite_batch.draw_sprite(SpriteLayer::Machines, elevator_platform.trans.pos, &elevator_platform.sprite);
     }
 
     if game.demolisher_spawned {
-        draw_sprite(demolisher.trans.pos, &demolisher.sprite);
+        sprite_batch.draw_sprite(SpriteLayer::Machines, demolisher.trans.pos, &demolisher.sprite);
     }
 
     // draw dropped items
     for item in &game.dropped_items {
-        draw_sprite_scaled(item.trans.pos, vec2(1.0, 1.0), &assets.items[item.kind as usize].derive_sprite());
+        sprite_batch.draw_sprite_scaled(SpriteLayer::Items, item.trans.pos, vec2(1.0, 1.0), &assets.items[item.kind as usize].derive_sprite());
     }
+
+    particles::draw_particles(sprite_batch, &game.particles, &layout, player.trans.pos);
     
     
     // draw player :::
     if !game.demolisher_started {
-        draw_sprite_offset(player.trans.pos, player.trans.offset, &player.sprite);
+        sprite_batch.draw_sprite_offset(SpriteLayer::Player, player.trans.pos, player.trans.offset, &player.sprite);
     }
     
     if game.elevator_spawned {
-        draw_sprite(elevator_cage.trans.pos, &elevator_cage.sprite);
+        sprite_batch.draw_sprite(SpriteLayer::Front, elevator_cage.trans.pos, &elevator_cage.sprite);
     }
+
+    sprite_batch.flush();
         
+    // present world :::
+    {
+        set_default_camera();
+        let filter = if game.settings.smooth_scaling { FilterMode::Linear } else { FilterMode::Nearest };
+        world_target.texture.set_filter(filter);
+
+        let rect = game.viewport.rect;
+        draw_texture_ex(&world_target.texture, rect.x, rect.y, WHITE, DrawTextureParams {
+            dest_size: Some(rect.size()),
+            ..Default::default()
+        });
+    }
+
     // ui    
     {    
-        set_camera(&Camera2D::from_display_rect(camera_origin));
+        set_camera(&Camera2D { viewport: screen_viewport, ..Camera2D::from_display_rect(camera_origin) });
     }
 
     // overlay ui
     if derived.ui_show_statue_key {
-        let pos = statue.trans.pos + statue.trans.size/2.0 - assets.ui_keys.texture.size()/2.0 * vec2(0.5, 0.5);
-        draw_sprite_scaled(pos, vec2(0.5, 0.5), &assets.ui_keys.derive_sprite());
+        let pos = statue.trans.pos + statue.trans.size/2.0 - assets.ui_keys.frames[0].size()/2.0 * vec2(0.5, 0.5);
+        sprite_batch.draw_sprite_scaled(SpriteLayer::Overlay, pos, vec2(0.5, 0.5), &assets.ui_keys.derive_sprite());
     }
     if game.demolisher_spawned && derived.ui_show_demolisher_key {
         let mut pos = demolisher.trans.pos
             + vec2(demolisher.trans.size.x/2.0, 0.0)
-            - vec2(assets.ui_keys.texture.size().x/2.0, 0.0) * vec2(0.5, 0.5);
-        pos.y += assets.ui_keys.texture.size().y/2.0;
+            - vec2(assets.ui_keys.frames[0].size().x/2.0, 0.0) * vec2(0.5, 0.5);
+        pos.y += assets.ui_keys.frames[0].size().y/2.0;
         pos.y += demolisher.trans.size.y;
-        draw_sprite_scaled(pos, vec2(0.5, 0.5), &assets.ui_keys.derive_sprite());
+        sprite_batch.draw_sprite_scaled(SpriteLayer::Overlay, pos, vec2(0.5, 0.5), &assets.ui_keys.derive_sprite());
     }
     if derived.ui_show_minecart_key {
         let mut pos = minecart.trans.pos
             + vec2(minecart.trans.size.x/2.0, 0.0)
-            - vec2(assets.ui_keys.texture.size().x/2.0, 0.0) * vec2(0.5, 0.5);
-        pos.y += assets.ui_keys.texture.size().y/2.0;
+            - vec2(assets.ui_keys.frames[0].size().x/2.0, 0.0) * vec2(0.5, 0.5);
+        pos.y += assets.ui_keys.frames[0].size().y/2.0;
         pos.y += minecart.trans.size.y;
-        draw_sprite_scaled(pos, vec2(0.5, 0.5), &assets.ui_keys.derive_sprite());
+        sprite_batch.draw_sprite_scaled(SpriteLayer::Overlay, pos, vec2(0.5, 0.5), &assets.ui_keys.derive_sprite());
     }
+    sprite_batch.flush();
         
     // actual ui
     
@@ -194,9 +245,13 @@ pub fn render(game: &mut Game) {
         
         let mut camera = Camera2D::from_display_rect(ui_camera_origin);
         camera.zoom.y *= -1.0;
+        camera.viewport = screen_viewport;
         set_camera(&camera);
     }
 
+    let mut pressed_continue = false;
+    let mut pressed_new_game = false;
+
     if game.main_ui_state == MainUIState::MainMenu {
         let prev_mouse_div = game.ui_state.mouse_div;
         game.ui_state.mouse_div /= 4.0;
@@ -205,21 +260,62 @@ pub fn render(game: &mut Game) {
         let title_sprite = &assets.ui_title[f32::min(game.total_time, 3.0) as usize].derive_sprite();
 
         cursor.x -= (title_sprite.texture_frame.w*2.0 - 240.0) / 2.0;
-        draw_ui(cursor, vec2(2.0, 2.0), title_sprite);
+        sprite_batch.draw_ui(cursor, vec2(2.0, 2.0), title_sprite);
 
         cursor.x += (title_sprite.texture_frame.w*2.0 - 240.0) / 2.0;
         cursor.y += title_sprite.texture_frame.h*2.0 + 8.0;
 
-        if ui_button(&mut game.ui_state, "Start Game", cursor, 240.0, false, None, &assets.ui_button) {
-            game.main_ui_state = MainUIState::InGame;
-            sound_player.play_sound(&assets.sfx_ui_positive, 0.1, false);
+        if ui_button(&mut game.ui_state, sprite_batch, "Continue", cursor, 156.0, !game.has_save, None, &assets.ui_button) {
+            pressed_continue = true;
+        }
+
+        if ui_button(&mut game.ui_state, sprite_batch, "Saves", cursor+vec2(164.0, 0.0), 76.0, false, None, &assets.ui_button) {
+            game.save_slots = save::read_slots();
+            game.slot_delete_armed = None;
+            game.main_ui_state = MainUIState::MainMenuSaves;
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+        
+        cursor += vec2(0.0, 32.0);
+
+        // INFO: Seed of the world New Game starts, it's the seed of the pregenerated world until edited.
+        ui_digit_input(&mut game.ui_state, sprite_batch, "Seed", &mut game.seed_input, 20, cursor, 240.0, &assets.ui_button);
+
+        cursor += vec2(0.0, 32.0);
+
+        let profile_label = ["World: ", &game.world_profiles[game.world_profile_i].name].concat();
+        if ui_button(&mut game.ui_state, sprite_batch, &profile_label, cursor, 116.0, false, None, &assets.ui_button) {
+            game.world_profile_i = (game.world_profile_i + 1) % game.world_profiles.len();
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        let size_label = ["Size: ", WORLD_SIZES[game.world_size_i].0].concat();
+        if ui_button(&mut game.ui_state, sprite_batch, &size_label, cursor+vec2(124.0, 0.0), 116.0, false, None, &assets.ui_button) {
+            game.world_size_i = (game.world_size_i + 1) % WORLD_SIZES.len();
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        cursor += vec2(0.0, 32.0);
+
+        if ui_button(&mut game.ui_state, sprite_batch, "New Game", cursor, 240.0, false, None, &assets.ui_button) {
+            pressed_new_game = true;
         }
         
         cursor += vec2(0.0, 32.0);
 
-        if ui_button(&mut game.ui_state, "Show Credits", cursor, 240.0, false, None, &assets.ui_button) {
+        if ui_button(&mut game.ui_state, sprite_batch, "Controls", cursor, 74.0, false, None, &assets.ui_button) {
+            game.main_ui_state = MainUIState::MainMenuControls;
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        if ui_button(&mut game.ui_state, sprite_batch, "Settings", cursor+vec2(83.0, 0.0), 74.0, false, None, &assets.ui_button) {
+            game.main_ui_state = MainUIState::MainMenuSettings;
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        if ui_button(&mut game.ui_state, sprite_batch, "Credits", cursor+vec2(166.0, 0.0), 74.0, false, None, &assets.ui_button) {
             game.main_ui_state = MainUIState::MainMenuCredits;
-            sound_player.play_sound(&assets.sfx_ui_positive, 0.1, false);
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
         }
 
         game.ui_state.mouse_div = prev_mouse_div;
@@ -254,9 +350,369 @@ pub fn render(game: &mut Game) {
         draw_text("Built with macroquad & bumpalo using Rust.", pos.x, pos.y, 16.0, WHITE);
         pos += vec2(0.0, 24.0);
 
-        if ui_button(&mut game.ui_state, "Back", pos, 300.0, false, None, &assets.ui_button) {
+        let back = ui_button(&mut game.ui_state, sprite_batch, "Back", pos, 300.0, false, None, &assets.ui_button);
+        if back || game.ui_state.nav.back {
+            game.main_ui_state = MainUIState::MainMenu;
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        game.ui_state.mouse_div = prev_mouse_div;
+    }
+
+    if game.main_ui_state == MainUIState::MainMenuSaves {
+        let prev_mouse_div = game.ui_state.mouse_div;
+        game.ui_state.mouse_div /= 4.0;
+
+        draw_rectangle(0.0, 0.0, UI_WIDTH_F32, UI_HEIGHT_F32, Color::from_rgba(0, 0, 0, 125));
+
+        let thumbnail_side = minimap::THUMBNAIL_SIDE as f32;
+        let info_width = 200.0;
+        let button_width = 76.0;
+        let row_width = thumbnail_side + 8.0 + info_width + (button_width+8.0)*2.0 - 8.0;
+
+        let mut cursor = vec2((UI_WIDTH_F32-row_width)/2.0, UI_HEIGHT_F32/8.0);
+
+        draw_text("Saves", cursor.x, cursor.y, 32.0, WHITE);
+        cursor.y += 16.0;
+
+        let empty_slot = game.save_slots.iter().position(|slot| matches!(slot, Err(SaveError::Missing)));
+        let mut slots_changed = false;
+
+        for (slot, slot_info) in game.save_slots.iter_mut().enumerate() {
+            let active = slot == game.settings.save_slot;
+            let info_pos = cursor + vec2(thumbnail_side + 8.0, 0.0);
+            let buttons_pos = info_pos + vec2(info_width, 0.0);
+
+            let frame_color = if active { WHITE } else { Color::from_hex(0x6b6464) };
+            draw_rectangle(cursor.x, cursor.y, thumbnail_side, thumbnail_side, Color::from_hex(0x0b0909));
+            draw_rectangle_lines(cursor.x-2.0, cursor.y-2.0, thumbnail_side+4.0, thumbnail_side+4.0, 2.0, frame_color);
+
+            let label = match slot_info {
+            | Ok(info) => save::slot_label(slot, &info.name),
+            | Err(_)   => save::slot_label(slot, ""),
+            };
+            let title = match active {
+            | true  => [&label, " (active)"].concat(),
+            | false => label,
+            };
+
+            match slot_info {
+            | Ok(info) => {
+                let texture = info.texture.get_or_insert_with(|| {
+                    let texture = Texture2D::from_image(&info.thumbnail);
+                    texture.set_filter(FilterMode::Nearest);
+                    texture
+                });
+                draw_texture(texture, cursor.x, cursor.y, WHITE);
+
+                if game.slot_renaming == Some(slot) {
+                    let name = format!("Name##slot_{}", slot);
+                    ui_text_input(&mut game.ui_state, sprite_batch, &name, &mut game.slot_name_input, save::MAX_SLOT_NAME, |char| char.is_ascii_graphic() || char == ' ', info_pos, info_width-8.0, &assets.ui_button);
+
+                    // INFO: Enter or clicking elsewhere releases focus, that's when the name is written.
+                    if !ui_is_text_input_focused(&game.ui_state, &name) {
+                        finish_slot_rename(&mut game.slot_renaming, &game.slot_name_input);
+                        slots_changed = true;
+                    }
+                } else {
+                    draw_text(&title, info_pos.x, info_pos.y+12.0, 16.0, WHITE);
+                }
+
+                let seconds = info.total_time as u32;
+                let lines = [
+                    format!("Money: {}", info.money),
+                    format!("Played: {}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60),
+                    format!("Deepest: {}  Upgrades: {}", info.deepest_depth, info.upgrades_owned),
+                ];
+                for (i, line) in lines.iter().enumerate() {
+                    draw_text(line, info_pos.x, info_pos.y + 30.0 + i as f32 * 14.0, 16.0, Color::from_hex(0xc7cfcc));
+                }
+            },
+            | Err(SaveError::Missing) => {
+                draw_text(&title, info_pos.x, info_pos.y+12.0, 16.0, WHITE);
+                draw_text("Empty", info_pos.x, info_pos.y+30.0, 16.0, Color::from_hex(0xc7cfcc));
+            },
+            | Err(err) => {
+                draw_text(&title, info_pos.x, info_pos.y+12.0, 16.0, WHITE);
+                draw_text("Unreadable save", info_pos.x, info_pos.y+30.0, 16.0, Color::from_hex(0xb33831));
+                draw_text(&err.to_string(), info_pos.x, info_pos.y+44.0, 16.0, Color::from_hex(0xc7cfcc));
+            },
+            }
+
+            let is_save = slot_info.is_ok();
+            let is_empty = matches!(slot_info, Err(SaveError::Missing));
+
+            if ui_button(&mut game.ui_state, sprite_batch, &format!("Select##slot_{}", slot), buttons_pos, button_width, active, None, &assets.ui_button) {
+                game.settings.save_slot = slot;
+                settings::save_settings(&game.settings);
+                game.has_save = is_save;
+                game.slot_delete_armed = None;
+                sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+            }
+
+            // INFO: Copies go into the first empty slot, there is nothing to overwrite by accident.
+            let copy_disabled = !is_save || empty_slot.is_none();
+            if ui_button(&mut game.ui_state, sprite_batch, &format!("Copy##slot_{}", slot), buttons_pos+vec2(button_width+8.0, 0.0), button_width, copy_disabled, None, &assets.ui_button) {
+                let copied = empty_slot.is_some_and(|to| save::copy_slot(slot, to));
+                let sound = if copied { &assets.sfx_ui_positive } else { &assets.sfx_ui_negative };
+                sound_player.play_sound(Bus::Ui, sound, false);
+                game.slot_delete_armed = None;
+                slots_changed = true;
+            }
+
+            if ui_button(&mut game.ui_state, sprite_batch, &format!("Rename##slot_{}", slot), buttons_pos+vec2(0.0, 32.0), button_width, !is_save, None, &assets.ui_button) {
+                if let Ok(info) = slot_info {
+                    game.slot_name_input = info.name.clone();
+                }
+                game.slot_renaming = Some(slot);
+                game.slot_delete_armed = None;
+                ui_focus_text_input(&mut game.ui_state, &format!("Name##slot_{}", slot));
+                sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+            }
+
+            let armed = game.slot_delete_armed == Some(slot);
+            let delete_label = if armed { format!("Sure?##delete_slot_{}", slot) } else { format!("Delete##delete_slot_{}", slot) };
+            if ui_button(&mut game.ui_state, sprite_batch, &delete_label, buttons_pos+vec2(button_width+8.0, 32.0), button_width, is_empty, None, &assets.ui_button) {
+                match armed {
+                | true => {
+                    save::delete_slot(slot);
+                    if active {
+                        game.has_save = false;
+                    }
+                    game.slot_delete_armed = None;
+                    slots_changed = true;
+                },
+                | false => game.slot_delete_armed = Some(slot),
+                }
+                sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+            }
+
+            cursor.y += thumbnail_side + 12.0;
+        }
+
+        if slots_changed {
+            game.save_slots = save::read_slots();
+        }
+
+        let back = ui_button(&mut game.ui_state, sprite_batch, "Back##saves", cursor, row_width, false, None, &assets.ui_button);
+        if back || game.ui_state.nav.back {
+            game.slot_renaming = None;
+            game.slot_delete_armed = None;
+            game.ui_state.focused_text_input_hash = None;
+            game.main_ui_state = MainUIState::MainMenu;
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        game.ui_state.mouse_div = prev_mouse_div;
+    }
+
+    let mut pressed_quit_to_menu = false;
+
+    if game.main_ui_state == MainUIState::Paused {
+        let prev_mouse_div = game.ui_state.mouse_div;
+        game.ui_state.mouse_div /= 4.0;
+
+        draw_rectangle(0.0, 0.0, UI_WIDTH_F32, UI_HEIGHT_F32, Color::from_rgba(0, 0, 0, 125));
+
+        let mut cursor = vec2(UI_WIDTH_F32/2.0, UI_HEIGHT_F32/3.0)-vec2(240.0/2.0, 0.0);
+
+        let title_size = measure_text("Paused", None, 32, 1.0);
+        draw_text("Paused", cursor.x + (240.0-title_size.width)/2.0, cursor.y, 32.0, WHITE);
+        cursor.y += 24.0;
+
+        if ui_button(&mut game.ui_state, sprite_batch, "Resume", cursor, 240.0, false, None, &assets.ui_button) {
+            game.main_ui_state = MainUIState::InGame;
+            sound_player.silence_loops(false);
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+        cursor += vec2(0.0, 32.0);
+
+        if ui_button(&mut game.ui_state, sprite_batch, "Settings##pause", cursor, 240.0, false, None, &assets.ui_button) {
+            game.main_ui_state = MainUIState::PausedSettings;
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+        cursor += vec2(0.0, 32.0);
+
+        if ui_button(&mut game.ui_state, sprite_batch, "Quit to Menu", cursor, 240.0, false, None, &assets.ui_button) {
+            pressed_quit_to_menu = true;
+        }
+
+        game.ui_state.mouse_div = prev_mouse_div;
+    }
+
+    if game.main_ui_state == MainUIState::MainMenuSettings || game.main_ui_state == MainUIState::PausedSettings {
+        let prev_mouse_div = game.ui_state.mouse_div;
+        game.ui_state.mouse_div /= 4.0;
+
+        draw_rectangle(0.0, 0.0, UI_WIDTH_F32, UI_HEIGHT_F32, Color::from_rgba(0, 0, 0, 125));
+
+        let mut cursor = vec2(UI_WIDTH_F32/2.0, UI_HEIGHT_F32/5.0)-vec2(240.0/2.0, 0.0);
+
+        let title_size = measure_text("Settings", None, 32, 1.0);
+        draw_text("Settings", cursor.x + (240.0-title_size.width)/2.0, cursor.y, 32.0, WHITE);
+        cursor.y += 24.0;
+
+        let settings = &mut game.settings;
+        let mut changed = false;
+        let mut window_changed = false;
+
+        // INFO: "-" and "+" around the value, the value itself toggles mute when the row has one.
+        let volume_row = |ui_state: &mut UIState, sprite_batch: &mut SpriteBatch, cursor: Vec2, key: &str, name: &str, volume: &mut u32, muted: Option<&mut bool>| {
+            let mut row_changed = false;
+            if ui_button(ui_state, sprite_batch, &["-##down_", key].concat(), cursor, 32.0, *volume == 0, None, &assets.ui_button) {
+                *volume = settings::step_volume(*volume, false);
+                row_changed = true;
+            }
+
+            match muted {
+            | Some(muted) => {
+                let label = match *muted {
+                | true  => format!("{}: Muted##mute_{}", name, key),
+                | false => format!("{}: {}%##mute_{}", name, volume, key),
+                };
+                if ui_button(ui_state, sprite_batch, &label, cursor+vec2(40.0, 0.0), 160.0, false, None, &assets.ui_button) {
+                    *muted = !*muted;
+                    row_changed = true;
+                }
+            },
+            | None => {
+                let label = format!("{}: {}%", name, volume);
+                let label_size = measure_text(&label, None, 16, 1.0);
+                let height = assets.ui_button[0][0].frames[0].h;
+                draw_text(&label, cursor.x + (240.0-label_size.width)/2.0, cursor.y + height/2.0, 16.0, WHITE);
+            },
+            }
+
+            if ui_button(ui_state, sprite_batch, &["+##up_", key].concat(), cursor+vec2(208.0, 0.0), 32.0, *volume == 100, None, &assets.ui_button) {
+                *volume = settings::step_volume(*volume, true);
+                row_changed = true;
+            }
+
+            row_changed
+        };
+
+        changed |= volume_row(&mut game.ui_state, sprite_batch, cursor, "master", "Master", &mut settings.master_volume, None);
+        cursor += vec2(0.0, 32.0);
+
+        for bus in Bus::VARIANTS {
+            let volume = &mut settings.volumes[bus as usize];
+            let muted = &mut settings.muted[bus as usize];
+            changed |= volume_row(&mut game.ui_state, sprite_batch, cursor, bus.key(), bus.name(), volume, Some(muted));
+            cursor += vec2(0.0, 32.0);
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let scale_label = format!("Window: {}x", settings.window_scale);
+            let disabled = settings.fullscreen;
+            if ui_button(&mut game.ui_state, sprite_batch, &[&scale_label, "##scale"].concat(), cursor, 240.0, disabled, None, &assets.ui_button) {
+                settings.window_scale = settings::next_window_scale(settings.window_scale);
+                window_changed = true;
+            }
+            cursor += vec2(0.0, 32.0);
+        }
+
+        let fullscreen_label = if settings.fullscreen { "Fullscreen: On##fullscreen" } else { "Fullscreen: Off##fullscreen" };
+        if ui_button(&mut game.ui_state, sprite_batch, fullscreen_label, cursor, 240.0, false, None, &assets.ui_button) {
+            settings.fullscreen = !settings.fullscreen;
+            window_changed = true;
+        }
+        cursor += vec2(0.0, 32.0);
+
+        let scaling_label = if settings.smooth_scaling { "Scaling: Smooth##scaling" } else { "Scaling: Pixel Perfect##scaling" };
+        if ui_button(&mut game.ui_state, sprite_batch, scaling_label, cursor, 240.0, false, None, &assets.ui_button) {
+            settings.smooth_scaling = !settings.smooth_scaling;
+            changed = true;
+        }
+        cursor += vec2(0.0, 32.0);
+
+        if changed || window_changed {
+            settings::apply_settings(settings, sound_player, window_changed);
+            settings::save_settings(settings);
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        let back = ui_button(&mut game.ui_state, sprite_batch, "Back##settings", cursor, 240.0, false, None, &assets.ui_button);
+        if back || game.ui_state.nav.back {
+            game.main_ui_state = match game.main_ui_state {
+            | MainUIState::PausedSettings => MainUIState::Paused,
+            | _ => MainUIState::MainMenu,
+            };
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        game.ui_state.mouse_div = prev_mouse_div;
+    }
+
+    if game.main_ui_state == MainUIState::MainMenuControls {
+        let prev_mouse_div = game.ui_state.mouse_div;
+        game.ui_state.mouse_div /= 4.0;
+
+        draw_rectangle(0.0, 0.0, UI_WIDTH_F32, UI_HEIGHT_F32, Color::from_rgba(0, 0, 0, 125));
+
+        let slot_width = 88.0;
+        let slot_spacing = 8.0;
+        let label_width = 120.0;
+        let row_width = label_width + (slot_width+slot_spacing)*3.0;
+
+        let mut cursor = vec2((UI_WIDTH_F32-row_width)/2.0, UI_HEIGHT_F32/6.0);
+
+        draw_text("Controls", cursor.x, cursor.y, 32.0, WHITE);
+        cursor.y += 16.0;
+        draw_text("Key", cursor.x+label_width, cursor.y, 16.0, Color::from_hex(0xc7cfcc));
+        draw_text("Key", cursor.x+label_width+slot_width+slot_spacing, cursor.y, 16.0, Color::from_hex(0xc7cfcc));
+        draw_text("Gamepad", cursor.x+label_width+(slot_width+slot_spacing)*2.0, cursor.y, 16.0, Color::from_hex(0xc7cfcc));
+        cursor.y += 8.0;
+
+        for action in InputAction::VARIANTS {
+            if action == InputAction::ToggleDevMode && !DEV_MODE { continue; }
+
+            let binding = *game.bindings.get(action);
+            draw_text(action.name(), cursor.x, cursor.y+12.0, 16.0, WHITE);
+
+            let slots = [
+                ( BindingSlot::Key(0), key_name(binding.keys[0]) ),
+                ( BindingSlot::Key(1), key_name(binding.keys[1]) ),
+                ( BindingSlot::Pad   , pad_name(binding.pad)     ),
+            ];
+
+            let mut slot_pos = cursor + vec2(label_width, 0.0);
+            for (slot, label) in slots {
+                let waiting = game.controls_rebinding == Some((action, slot));
+                let label = if waiting { "..." } else { &label };
+                let name = format!("{}##{:?}{:?}", label, action, slot);
+
+                if ui_button(&mut game.ui_state, sprite_batch, &name, slot_pos, slot_width, false, None, &assets.ui_button) {
+                    game.controls_rebinding = Some((action, slot));
+                    sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+                }
+                slot_pos.x += slot_width + slot_spacing;
+            }
+
+            cursor.y += 28.0;
+        }
+
+        cursor.y += 4.0;
+        let hint = match game.controls_rebinding {
+        | Some(_) => "Press a key or button, Backspace clears, Escape cancels.",
+        | None => "Pick a slot to rebind it.",
+        };
+        draw_text(hint, cursor.x, cursor.y, 16.0, Color::from_hex(0xc7cfcc));
+        cursor.y += 12.0;
+
+        let half_width = (row_width-slot_spacing)/2.0;
+        if ui_button(&mut game.ui_state, sprite_batch, "Reset Defaults", cursor, half_width, false, None, &assets.ui_button) {
+            game.bindings = Bindings::default();
+            game.controls_rebinding = None;
+            save_bindings(&game.bindings);
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
+        }
+
+        let back = ui_button(&mut game.ui_state, sprite_batch, "Back##controls", cursor+vec2(half_width+slot_spacing, 0.0), half_width, false, None, &assets.ui_button);
+        if back || game.ui_state.nav.back {
+            game.controls_rebinding = None;
             game.main_ui_state = MainUIState::MainMenu;
-            sound_player.play_sound(&assets.sfx_ui_positive, 0.1, false);
+            sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
         }
 
         game.ui_state.mouse_div = prev_mouse_div;
@@ -280,7 +736,7 @@ pub fn render(game: &mut Game) {
             // INFO: This is no-op, but it shows the intent, we need half of it, but scale is double.
             pos.x -= sprite.texture_frame.w/2.0*2.0;
         
-            draw_ui_rotated(pos, vec2(2.0, 2.0), -dir.to_angle(), &sprite);
+            sprite_batch.draw_ui_rotated(pos, vec2(2.0, 2.0), -dir.to_angle(), &sprite);
         }
     }
 
@@ -288,25 +744,26 @@ pub fn render(game: &mut Game) {
         let demolisher_pos = demolisher.trans.pos;
         if demolisher_pos.distance(player.trans.pos) >= 128.0 {
             let pos = vec2(UI_WIDTH_F32/2.0-128.0, 32.0);
-            draw_ui(pos, vec2(2.0, 2.0), &assets.ui_demolisher_arrow.derive_sprite());
+            sprite_batch.draw_ui(pos, vec2(2.0, 2.0), &assets.ui_demolisher_arrow.derive_sprite());
         }
     }
     
     let corner_padding = vec2(2.0, 2.0);
     {
         let coin_sprite = assets.coin.derive_sprite();
-        let coin_size = coin_sprite.texture.size();
+        let coin_size = coin_sprite.texture_frame.size();
 
         let mut cursor = vec2(UI_WIDTH_F32, 0.0);
     
         cursor.x -= coin_size.x*2.0 + corner_padding.x;
         cursor.y += corner_padding.y;
-        draw_ui(cursor, vec2(2.0, 2.0), &coin_sprite);
+        sprite_batch.draw_ui(cursor, vec2(2.0, 2.0), &coin_sprite);
 
         let text_size = measure_text(&game.money.to_string(), None, 16, 1.0);
         
         cursor.y += coin_size.y*2.0 - 8.0;
         cursor.x -= text_size.width*2.0;
+        sprite_batch.flush();
         draw_text(&game.money.to_string(), cursor.x, cursor.y, 32.0, WHITE);
 
         cursor.y = UI_HEIGHT_F32 - 16.0;
@@ -322,10 +779,11 @@ pub fn render(game: &mut Game) {
             let value = variant.value();
             let size = sprite.texture_frame.size();
             let coin_padding = 4.0;
-            draw_ui(cursor - vec2(0.0, size.y)*2.5, vec2(4.0, 4.0), &sprite);
-            draw_ui(cursor - vec2(coin_padding, coin_size.y/2.0*2.0), vec2(1.0, 1.0), &coin_sprite);
+            sprite_batch.draw_ui(cursor - vec2(0.0, size.y)*2.5, vec2(4.0, 4.0), &sprite);
+            sprite_batch.draw_ui(cursor - vec2(coin_padding, coin_size.y/2.0*2.0), vec2(1.0, 1.0), &coin_sprite);
             
             let text_size = measure_text(&value.to_string(), None, 16, 1.0);
+            sprite_batch.flush();
             draw_text(value.to_string(), cursor.x - text_size.width*2.0 - coin_padding, cursor.y, 32.0, WHITE);
             cursor.y -= 32.0;
         }
@@ -336,12 +794,15 @@ pub fn render(game: &mut Game) {
         game.ui_state.mouse_div /= 4.0;
 
         let mut cursor = vec2(4.0, UI_HEIGHT_F32);
-        let music_button = if sound_player.music_playing {
+        let music_muted = game.settings.muted[Bus::Music as usize];
+        let sound_muted = game.settings.muted[Bus::Sfx as usize];
+
+        let music_button = if !music_muted {
             &assets.ui_music_playing
         } else {
             &assets.ui_music_muted
         };
-        let sound_button = if sound_player.sound_playing {
+        let sound_button = if !sound_muted {
             &assets.ui_sound_playing
         } else {
             &assets.ui_sound_muted
@@ -367,16 +828,27 @@ pub fn render(game: &mut Game) {
             h: sound_button.texture_frame.h*2.0,
         };
 
-        if is_mouse_button_pressed(MouseButton::Left) && music_rect.contains(Vec2::from(mouse_position()) / game.ui_state.mouse_div) {
-            sound_player.music_playing = !sound_player.music_playing;
+        // INFO: Shortcuts for the mutes in Settings, the sound icon covers every bus but music.
+        let mut mutes_changed = false;
+        if is_mouse_button_pressed(MouseButton::Left) && music_rect.contains(ui_mouse_position(&game.ui_state)) {
+            game.settings.muted[Bus::Music as usize] = !music_muted;
+            mutes_changed = true;
         }
         
-        if is_mouse_button_pressed(MouseButton::Left) && sound_rect.contains(Vec2::from(mouse_position()) / game.ui_state.mouse_div) {
-            sound_player.sound_playing = !sound_player.sound_playing;
+        if is_mouse_button_pressed(MouseButton::Left) && sound_rect.contains(ui_mouse_position(&game.ui_state)) {
+            for bus in [Bus::Sfx, Bus::Ui, Bus::Ambience] {
+                game.settings.muted[bus as usize] = !sound_muted;
+            }
+            mutes_changed = true;
         }
 
-        draw_ui(music_pos, vec2(2.0, 2.0), music_button);
-        draw_ui(sound_pos, vec2(2.0, 2.0), sound_button);
+        if mutes_changed {
+            settings::apply_settings(&game.settings, sound_player, false);
+            settings::save_settings(&game.settings);
+        }
+
+        sprite_batch.draw_ui(music_pos, vec2(2.0, 2.0), music_button);
+        sprite_batch.draw_ui(sound_pos, vec2(2.0, 2.0), sound_button);
         
         game.ui_state.mouse_div = prev_mouse_div;
     }
@@ -386,16 +858,37 @@ pub fn render(game: &mut Game) {
         
         if derived.player_has_jetpack {
             let ratio = player.jetpack_fuel as f32 / derived.player_jetpack_fuel_capacity as f32;
-            draw_ui(cursor, vec2(2.0, 2.0), &assets.ui_fuel_bar_background.derive_sprite());
-            draw_ui_partial(cursor, vec2(2.0, 2.0), vec2(1.0, ratio), &assets.ui_fuel_bar_fill.derive_sprite());
-            draw_ui(cursor, vec2(2.0, 2.0), &ui_fuel_bar_frame.sprite);
+            sprite_batch.draw_ui(cursor, vec2(2.0, 2.0), &assets.ui_fuel_bar_background.derive_sprite());
+            sprite_batch.draw_ui_partial(cursor, vec2(2.0, 2.0), vec2(1.0, ratio), &assets.ui_fuel_bar_fill.derive_sprite());
+            sprite_batch.draw_ui(cursor, vec2(2.0, 2.0), &ui_fuel_bar_frame.sprite);
             cursor += vec2(ui_fuel_bar_frame.sprite.texture_frame.w*2.0, 0.0);
             cursor += vec2(8.0, 0.0);
         }
         let ratio = player.carrying.length as f32 / derived.player_bag_carry_capacity as f32;
-        draw_ui(cursor, vec2(2.0, 2.0), &assets.ui_inventory_bar_background.derive_sprite());
-        draw_ui_partial(cursor, vec2(2.0, 2.0), vec2(1.0, ratio), &assets.ui_inventory_bar_fill.derive_sprite());
-        draw_ui(cursor, vec2(2.0, 2.0), &ui_inventory_bar_frame.sprite);        
+        sprite_batch.draw_ui(cursor, vec2(2.0, 2.0), &assets.ui_inventory_bar_background.derive_sprite());
+        sprite_batch.draw_ui_partial(cursor, vec2(2.0, 2.0), vec2(1.0, ratio), &assets.ui_inventory_bar_fill.derive_sprite());
+        sprite_batch.draw_ui(cursor, vec2(2.0, 2.0), &ui_inventory_bar_frame.sprite);        
+    }
+
+    // map :::
+    sprite_batch.flush();
+    if !derived.ui_main_menu {
+        let mut landmarks = Vec::with_capacity_in(Landmark::LENGTH, &game.bump);
+        landmarks.push((Landmark::Statue, statue.trans.pos + statue.trans.size/2.0));
+        landmarks.push((Landmark::Minecart, minecart.trans.pos));
+        if game.elevator_spawned {
+            landmarks.push((Landmark::Elevator, elevator_platform.trans.pos + elevator_platform.trans.size/2.0));
+        }
+        if game.demolisher_spawned {
+            landmarks.push((Landmark::Demolisher, demolisher.trans.pos + demolisher.trans.size/2.0));
+        }
+
+        if game.ui_show_map {
+            minimap::draw_full_map(&mut game.minimap, world, &landmarks, player.trans.pos, game.total_time);
+        } else {
+            let pos = vec2(UI_WIDTH_F32 - minimap::minimap_size().x - corner_padding.x - 2.0, 48.0);
+            minimap::draw_minimap(&mut game.minimap, world, pos, &landmarks, player.trans.pos, game.total_time);
+        }
     }
     
     // INFO: Don't forget some textures are scaled 4x
@@ -405,14 +898,15 @@ pub fn render(game: &mut Game) {
         let prev_mouse_div = game.ui_state.mouse_div;
         game.ui_state.mouse_div /= 4.0;
         
-        let bg_size = assets.ui_bg.texture.size()*2.0;
+        let bg_size = assets.ui_bg.frames[0].size()*2.0;
         let bg_offset = vec2(UI_WIDTH_F32, UI_HEIGHT_F32) - bg_size;
 
         let bg_padding = vec2(20.0, 30.0);
         let button_spacing = vec2(8.0, 4.0);
 
         let mut cursor = vec2(bg_offset.x/2.0, bg_offset.y/2.0);
-        draw_ui(cursor, vec2(2.0, 2.0), &assets.ui_bg.derive_sprite());
+        sprite_batch.draw_ui(cursor, vec2(2.0, 2.0), &assets.ui_bg.derive_sprite());
+        sprite_batch.flush();
 
         cursor += bg_padding;
     
@@ -424,7 +918,7 @@ pub fn render(game: &mut Game) {
 
         rcursor.x += button_spacing.x + button_width;
 
-        let coin_size = assets.coin.texture.size();
+        let coin_size = assets.coin.frames[0].size();
 
         let mut ui_seq_upgrade_button = |position: Vec2, mut state: SeqUpgrade| {
             let mut disabled = state.reached_count;
@@ -442,6 +936,7 @@ pub fn render(game: &mut Game) {
             
             if ui_button(
                 &mut game.ui_state,
+                sprite_batch,
                 name,
                 position,
                 button_width,
@@ -452,9 +947,9 @@ pub fn render(game: &mut Game) {
                 if can_afford {
                     (state.upgrade)();
                     game.money -= state.cost;
-                    sound_player.play_sound(&assets.sfx_ui_positive, 0.1, false);
+                    sound_player.play_sound(Bus::Ui, &assets.sfx_ui_positive, false);
                 } else {
-                    sound_player.play_sound(&assets.sfx_ui_negative, 0.1, false);
+                    sound_player.play_sound(Bus::Ui, &assets.sfx_ui_negative, false);
                 }
             }
 
@@ -467,12 +962,13 @@ pub fn render(game: &mut Game) {
                     coin_pos.y += 1.0;
                 }
                 
-                draw_ui(coin_pos, vec2(1.0, 1.0), &assets.coin.derive_sprite());
+                sprite_batch.draw_ui(coin_pos, vec2(1.0, 1.0), &assets.coin.derive_sprite());
                 
                 let text_size = measure_text(&state.cost.to_string(), None, 16, 1.0);
                 coin_pos += vec2(-text_size.width-2.0, button_height/2.0);
 
                 let color = if can_afford { WHITE } else { RED };
+                sprite_batch.flush();
                 draw_text(&state.cost.to_string(), coin_pos.x, coin_pos.y, 16.0, color);
             }
         };
@@ -542,6 +1038,7 @@ pub fn render(game: &mut Game) {
         game.ui_state.mouse_div = prev_mouse_div;
     }
     
+    sprite_batch.flush();
     if game.demolisher_started && demolisher.stage == 5 {
         let transparency = f32::min(demolisher.stage_tick / 5.0 * 255.0, 255.0);
         draw_rectangle(0.0, 0.0, UI_WIDTH_F32, UI_HEIGHT_F32, Color::from_rgba(0, 0, 0, transparency as u8));
@@ -570,13 +1067,69 @@ pub fn render(game: &mut Game) {
         draw_center("Built with macroquad & bumpalo using Rust.", 16, 24.0);
     }
     
+    // main menu actions :::
+    // INFO: Handled after drawing because loading and saving need the whole game, not the borrows above.
+    if pressed_continue {
+        match save::load_game(game) {
+        | Ok(()) => {
+            game.main_ui_state = MainUIState::InGame;
+            game.sound_player.play_sound(Bus::Ui, &game.assets.sfx_ui_positive, false);
+        },
+        | Err(err) => {
+            warn!("Couldn't load save: {}", err);
+            game.has_save = false;
+            game.sound_player.play_sound(Bus::Ui, &game.assets.sfx_ui_negative, false);
+        },
+        }
+    }
+    if pressed_quit_to_menu {
+        // INFO: Leaves a fresh world of the same seed behind the menu, like on startup, so New Game never picks up
+        // the run that was just left. The run itself is in the save for Continue.
+        if !game.demolisher_started {
+            save::save_game(game);
+        }
+        game.sound_player.stop_loops();
+        game.sound_player.silence_loops(false);
+        reset_run(game, game.world.seed);
+        game.main_ui_state = MainUIState::MainMenu;
+        game.sound_player.play_sound(Bus::Ui, &game.assets.sfx_ui_positive, false);
+    }
+    if pressed_new_game {
+        // INFO: An empty seed keeps the pregenerated world's seed, anything that doesn't fit u64 is rejected.
+        let seed = match game.seed_input.as_str() {
+        | "" => Some(game.world.seed),
+        | input => input.parse::<u64>().ok(),
+        };
+
+        match seed {
+        | Some(seed) => {
+            let profile = &game.world_profiles[game.world_profile_i];
+            let (_, width, height) = WORLD_SIZES[game.world_size_i];
+            let layout = &game.world.layout;
+            if  seed != game.world.seed || profile.name != game.world.profile ||
+                width != layout.width || height != layout.height
+            {
+                reset_run(game, seed);
+            }
+            game.seed_input = seed.to_string();
+            game.ui_state.focused_text_input_hash = None;
+            game.main_ui_state = MainUIState::InGame;
+            save::save_game(game);
+            game.sound_player.play_sound(Bus::Ui, &game.assets.sfx_ui_positive, false);
+        },
+        | None => {
+            game.sound_player.play_sound(Bus::Ui, &game.assets.sfx_ui_negative, false);
+        },
+        }
+    }
+
     // HACK: This shouldn't be inside render, but whatever
     if is_mouse_button_released(MouseButton::Left) {
         game.ui_state.last_clicked_button_hash = None;
     }
     
     {    
-        set_camera(&Camera2D::from_display_rect(camera_origin));
+        set_camera(&Camera2D { viewport: screen_viewport, ..Camera2D::from_display_rect(camera_origin) });
     }
 
     // debug
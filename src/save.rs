@@ -0,0 +1,573 @@
+use crate::prelude::*;
+
+use crate::storage;
+use crate::init::{ place_entities };
+use crate::minimap::{ self, Minimap };
+use crate::tile::{ TileChunk };
+use crate::worldgen::{ DEFAULT_PROFILE };
+
+const SAVE_KEY: &str = "game";
+pub const SAVE_SLOTS: usize = 4;
+pub const MAX_SLOT_NAME: usize = 16;
+const MAGIC: [u8; 4] = *b"MINR";
+const VERSION: u16 = 6;
+// INFO: Version 1 saves predate seeded worlds, they load with seed 0. Version 2 saves predate world
+// profiles, they load as the default profile which had the same barrier. Version 3 saves predate world
+// sizes, every world was 64x64 chunks back then. Version 4 saves predate fog, they load with only the surface
+// discovered. Version 5 saves predate slots, they load unnamed and without a deepest depth.
+const MIN_VERSION: u16 = 1;
+const LEGACY_BARRIER_DEPTH: i32 = 13;
+const LEGACY_WORLD_SIDE: usize = 64;
+
+const AUTOSAVE_INTERVAL: f32 = 15.0;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Missing,
+    BadMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    Corrupt(&'static str),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+        | SaveError::Missing => write!(f, "no save found"),
+        | SaveError::BadMagic => write!(f, "not a save file"),
+        | SaveError::UnsupportedVersion(version) => write!(f, "unsupported save version {}", version),
+        | SaveError::Truncated => write!(f, "save file is truncated"),
+        | SaveError::Corrupt(reason) => write!(f, "save file is corrupt: {}", reason),
+        }
+    }
+}
+
+// INFO: Everything needed to continue a run. Entities that are mid-animation (coins in the air, minecart on
+// the rails, dropped items) are folded into money/carrying on capture, so loading always starts from rest.
+pub struct SaveData {
+    pub seed: u64,
+    pub profile: String,
+    pub barrier_depth: i32,
+    pub width: usize,
+    pub height: usize,
+    pub total_time: f32,
+    pub money: i32,
+    pub upgrade_tiers: [u8; Upgrades::COUNT],
+    pub unlocked_ores: [bool; ItemKind::LENGTH],
+    pub player_pos: Vec2,
+    pub player_carrying: Vec<ItemKind>,
+    pub player_jetpack_fuel: f32,
+    pub minecart_carrying: Vec<ItemKind>,
+    pub elevator_spawned: bool,
+    // INFO: Carries which tiles are discovered too.
+    pub chunks: Vec<TileChunk>,
+    // INFO: Empty until renamed on the Saves screen, see slot_label.
+    pub name: String,
+    // INFO: In tiles below the spawn room.
+    pub deepest_depth: i32,
+}
+
+impl SaveData {
+    pub fn capture(game: &Game) -> Self {
+        let coins_in_flight: i32 = game.coins.iter().map(|coin| coin.amount).sum();
+
+        Self {
+            seed: game.world.seed,
+            profile: game.world.profile.clone(),
+            barrier_depth: game.world.layout.barrier_depth,
+            width: game.world.layout.width,
+            height: game.world.layout.height,
+            total_time: game.total_time,
+            money: game.money + coins_in_flight,
+            upgrade_tiers: game.upgrades.tiers(),
+            unlocked_ores: game.unlocked_ores,
+            player_pos: game.player.trans.pos,
+            player_carrying: game.player.carrying.slice().to_vec(),
+            player_jetpack_fuel: game.player.jetpack_fuel,
+            minecart_carrying: game.minecart.carrying.slice().iter().map(|item| item.kind).collect(),
+            elevator_spawned: game.elevator_spawned,
+            chunks: game.world.chunks.clone(),
+            name: game.save_name.clone(),
+            deepest_depth: game.deepest_depth,
+        }
+    }
+
+    pub fn apply(self, game: &mut Game) {
+        // INFO: Selecting the profile and size so New Game keeps them, unless the profile is gone since the save
+        // was made.
+        if let Some(i) = game.world_profiles.iter().position(|profile| profile.name == self.profile) {
+            game.world_profile_i = i;
+        }
+        if let Some(i) = WORLD_SIZES.iter().position(|&(_, w, h)| (w, h) == (self.width, self.height)) {
+            game.world_size_i = i;
+        }
+
+        let layout = WorldLayout::new(self.width, self.height, self.barrier_depth);
+        let template_mesh = game.world.meshes[0].clone();
+
+        game.world.seed = self.seed;
+        game.world.profile = self.profile;
+        game.world.layout = layout;
+        game.world.chunks = self.chunks;
+        game.world.discover_surface();
+        game.world.meshes.resize(layout.size(), template_mesh);
+        game.world.dirty_chunks.clear();
+        game.world.recalculate_all_meshes = true;
+
+        place_entities(game);
+        game.minimap = Minimap::new(&game.world);
+
+        game.total_time = self.total_time;
+        game.money = self.money;
+        game.save_name = self.name;
+        game.deepest_depth = self.deepest_depth;
+        // INFO: Tiers are validated while decoding.
+        game.upgrades = Upgrades::from_tiers(self.upgrade_tiers).unwrap_or_default();
+        game.unlocked_ores = self.unlocked_ores;
+
+        game.player.trans.pos = self.player_pos;
+        game.player.last_positions.fill(self.player_pos);
+        game.camera.pos = self.player_pos;
+        game.player.carrying.clear();
+        for kind in self.player_carrying {
+            game.player.carrying.push(kind);
+        }
+        game.player.jetpack_fuel = self.player_jetpack_fuel;
+
+        game.minecart.carrying.clear();
+        for kind in self.minecart_carrying {
+            let trans = Transform {
+                pos: game.minecart.trans.pos,
+                size: vec2(0.0, 0.0),
+                offset: vec2(0.0, 0.0),
+            };
+            game.minecart.carrying.push(Item { trans, kind });
+        }
+
+        game.elevator_spawned = self.elevator_spawned;
+        game.seed_input = self.seed.to_string();
+
+        game.coins.clear();
+        game.dropped_items.clear();
+        game.tile_durability_map.clear();
+        game.tile_cant_dig_map.clear();
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = SaveWriter { bytes: Vec::with_capacity(64 * 1024) };
+
+        w.bytes.extend_from_slice(&MAGIC);
+        w.u16(VERSION);
+        w.u64(self.seed);
+        w.str(&self.profile);
+        w.i32(self.barrier_depth);
+        w.u16(self.width as u16);
+        w.u16(self.height as u16);
+
+        w.f32(self.total_time);
+        w.i32(self.money);
+        for tier in self.upgrade_tiers {
+            w.u8(tier);
+        }
+        for unlocked in self.unlocked_ores {
+            w.bool(unlocked);
+        }
+
+        w.vec2(self.player_pos);
+        w.item_kinds(&self.player_carrying);
+        w.f32(self.player_jetpack_fuel);
+        w.item_kinds(&self.minecart_carrying);
+        w.bool(self.elevator_spawned);
+
+        // INFO: Tiles are run-length encoded across chunk boundaries. Worlds are mostly long runs of stone,
+        // which keeps saves small enough for localStorage.
+        w.u32(self.chunks.len() as u32);
+        let mut tiles = self.chunks.iter().flat_map(|chunk| chunk.tiles.iter().copied());
+        if let Some(first) = tiles.next() {
+            let mut run_tile = first;
+            let mut run_length = 1u16;
+            for tile in tiles {
+                if tile == run_tile && run_length < u16::MAX {
+                    run_length += 1;
+                } else {
+                    w.u16(run_length);
+                    w.u8(run_tile as u8);
+                    run_tile = tile;
+                    run_length = 1;
+                }
+            }
+            w.u16(run_length);
+            w.u8(run_tile as u8);
+        }
+
+        // INFO: Discovered tiles as alternating runs, starting with an undiscovered one (which can be empty). Runs
+        // go row by row over the whole world instead of chunk by chunk, so fog follows the shape of the tunnels.
+        let mut discovered = false;
+        let mut run_length = 0u32;
+        for tile_discovered in discovered_tiles(&self.chunks, self.width, self.height) {
+            if tile_discovered != discovered {
+                w.u32(run_length);
+                discovered = tile_discovered;
+                run_length = 0;
+            }
+            run_length += 1;
+        }
+        w.u32(run_length);
+
+        w.str(&self.name);
+        w.i32(self.deepest_depth);
+
+        w.bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, SaveError> {
+        let mut r = SaveReader { bytes, cursor: 0 };
+
+        if r.take(4)? != MAGIC { return Err(SaveError::BadMagic); }
+
+        let version = r.u16()?;
+        if !(MIN_VERSION..=VERSION).contains(&version) { return Err(SaveError::UnsupportedVersion(version)); }
+
+        let seed = match version {
+        | 1 => 0,
+        | _ => r.u64()?,
+        };
+
+        let (profile, barrier_depth) = match version {
+        | 1 | 2 => (DEFAULT_PROFILE.to_string(), LEGACY_BARRIER_DEPTH),
+        | _ => (r.str()?, r.i32()?),
+        };
+        let (width, height) = match version {
+        | ..=3 => (LEGACY_WORLD_SIDE, LEGACY_WORLD_SIDE),
+        | _ => (r.u16()? as usize, r.u16()? as usize),
+        };
+        if !(WorldLayout::MIN_WIDTH..=WorldLayout::MAX_SIDE).contains(&width) ||
+           !(WorldLayout::MIN_HEIGHT..=WorldLayout::MAX_SIDE).contains(&height)
+        {
+            return Err(SaveError::Corrupt("world size out of range"));
+        }
+        if !(2..=height as i32-4).contains(&barrier_depth) {
+            return Err(SaveError::Corrupt("barrier depth out of range"));
+        }
+
+        let total_time = r.f32()?;
+        let money = r.i32()?;
+
+        let mut upgrade_tiers = [0; Upgrades::COUNT];
+        for tier in &mut upgrade_tiers {
+            *tier = r.u8()?;
+        }
+        if Upgrades::from_tiers(upgrade_tiers).is_none() {
+            return Err(SaveError::Corrupt("upgrade tier out of range"));
+        }
+
+        let mut unlocked_ores = [false; ItemKind::LENGTH];
+        for unlocked in &mut unlocked_ores {
+            *unlocked = r.bool()?;
+        }
+
+        let player_pos = r.vec2()?;
+        let player_carrying = r.item_kinds()?;
+        let player_jetpack_fuel = r.f32()?;
+        let minecart_carrying = r.item_kinds()?;
+        let elevator_spawned = r.bool()?;
+
+        let chunk_count = r.u32()? as usize;
+        if chunk_count != width * height {
+            return Err(SaveError::Corrupt("world size mismatch"));
+        }
+
+        let empty_chunk = TileChunk { tiles: [Tile::Empty; CHUNK_SIZE], discovered: [false; CHUNK_SIZE] };
+        let mut chunks = vec![empty_chunk; chunk_count];
+        let mut tile_i = 0;
+        while tile_i < chunk_count * CHUNK_SIZE {
+            let run_length = r.u16()? as usize;
+            let tile = *Tile::VARIANTS.get(r.u8()? as usize).ok_or(SaveError::Corrupt("unknown tile"))?;
+
+            if run_length == 0 || tile_i + run_length > chunk_count * CHUNK_SIZE {
+                return Err(SaveError::Corrupt("tile run out of bounds"));
+            }
+            for i in tile_i..tile_i+run_length {
+                chunks[i / CHUNK_SIZE].tiles[i % CHUNK_SIZE] = tile;
+            }
+            tile_i += run_length;
+        }
+
+        if version >= 5 {
+            let tile_width = width * CHUNK_SIDE;
+            let tile_count = chunk_count * CHUNK_SIZE;
+
+            let mut tile_i = 0;
+            let mut run_discovered = false;
+            while tile_i < tile_count {
+                let run_length = r.u32()? as usize;
+                if tile_i + run_length > tile_count {
+                    return Err(SaveError::Corrupt("discovered run out of bounds"));
+                }
+                for i in tile_i..tile_i+run_length {
+                    let tile_pos = ivec2((i % tile_width) as i32, (i / tile_width) as i32);
+                    chunks[chunk_index_at(tile_pos_to_chunk_pos(tile_pos), width)].discovered[tile_index_at(tile_pos)] = run_discovered;
+                }
+                tile_i += run_length;
+                run_discovered = !run_discovered;
+            }
+        }
+
+        let (name, deepest_depth) = match version {
+        | ..=5 => (String::new(), 0),
+        | _ => (r.str()?, r.i32()?),
+        };
+
+        Ok(Self {
+            seed,
+            profile,
+            barrier_depth,
+            width,
+            height,
+            total_time,
+            money,
+            upgrade_tiers,
+            unlocked_ores,
+            player_pos,
+            player_carrying,
+            player_jetpack_fuel,
+            minecart_carrying,
+            elevator_spawned,
+            chunks,
+            name,
+            deepest_depth,
+        })
+    }
+}
+
+fn discovered_tiles(chunks: &[TileChunk], width: usize, height: usize) -> impl Iterator<Item = bool> + '_ {
+    let tile_width = (width * CHUNK_SIDE) as i32;
+    let tile_height = (height * CHUNK_SIDE) as i32;
+
+    (0..tile_height).flat_map(move |y| (0..tile_width).map(move |x| {
+        let tile_pos = ivec2(x, y);
+        chunks[chunk_index_at(tile_pos_to_chunk_pos(tile_pos), width)].discovered[tile_index_at(tile_pos)]
+    }))
+}
+
+// INFO: The first slot keeps the key saves had before there were slots, so an old save shows up in it.
+fn slot_key(slot: usize) -> String {
+    match slot {
+    | 0 => SAVE_KEY.to_string(),
+    | _ => format!("{}{}", SAVE_KEY, slot + 1),
+    }
+}
+
+pub fn slot_label(slot: usize, name: &str) -> String {
+    match name {
+    | "" => format!("Slot {}", slot + 1),
+    | name => name.to_string(),
+    }
+}
+
+// INFO: What the Saves screen shows for a slot, read from the save itself.
+pub struct SlotInfo {
+    pub name: String,
+    pub money: i32,
+    pub total_time: f32,
+    pub deepest_depth: i32,
+    pub upgrades_owned: u32,
+    pub thumbnail: Image,
+    // INFO: Made from thumbnail on first draw.
+    pub texture: Option<Texture2D>,
+}
+
+impl SlotInfo {
+    pub fn from_save(save: &SaveData) -> SlotInfo {
+        SlotInfo {
+            name: save.name.clone(),
+            money: save.money,
+            total_time: save.total_time,
+            deepest_depth: save.deepest_depth,
+            upgrades_owned: save.upgrade_tiers.iter().map(|tier| *tier as u32).sum(),
+            thumbnail: minimap::thumbnail(&save.chunks, save.width, save.height),
+            texture: None,
+        }
+    }
+}
+
+pub fn read_slot(slot: usize) -> Result<SlotInfo, SaveError> {
+    let bytes = storage::read(&slot_key(slot)).ok_or(SaveError::Missing)?;
+    Ok(SlotInfo::from_save(&SaveData::decode(&bytes)?))
+}
+
+pub fn read_slots() -> Vec<Result<SlotInfo, SaveError>> {
+    (0..SAVE_SLOTS).map(read_slot).collect()
+}
+
+pub fn copy_slot(from: usize, to: usize) -> bool {
+    storage::read(&slot_key(from)).is_some_and(|bytes| storage::write(&slot_key(to), &bytes))
+}
+
+pub fn delete_slot(slot: usize) -> bool {
+    storage::remove(&slot_key(slot))
+}
+
+pub fn rename_slot(slot: usize, name: &str) -> Result<(), SaveError> {
+    let bytes = storage::read(&slot_key(slot)).ok_or(SaveError::Missing)?;
+    let mut save = SaveData::decode(&bytes)?;
+    save.name = name.trim().to_string();
+
+    match storage::write(&slot_key(slot), &save.encode()) {
+    | true  => Ok(()),
+    | false => Err(SaveError::Corrupt("couldn't write the renamed save")),
+    }
+}
+
+pub fn has_save(slot: usize) -> bool {
+    storage::exists(&slot_key(slot))
+}
+
+pub fn save_game(game: &mut Game) -> bool {
+    let saved = storage::write(&slot_key(game.settings.save_slot), &SaveData::capture(game).encode());
+    game.has_save |= saved;
+    saved
+}
+
+pub fn load_game(game: &mut Game) -> Result<(), SaveError> {
+    let bytes = storage::read(&slot_key(game.settings.save_slot)).ok_or(SaveError::Missing)?;
+    SaveData::decode(&bytes)?.apply(game);
+    Ok(())
+}
+
+pub fn tick_autosave(game: &mut Game, dt: f32) {
+    if game.main_ui_state != MainUIState::InGame { return; }
+    // INFO: The ending rewrites the world, saving it would leave a run that can't be continued.
+    if game.demolisher_started { return; }
+
+    game.autosave_tick += dt;
+    if game.autosave_tick >= AUTOSAVE_INTERVAL {
+        game.autosave_tick = 0.0;
+        save_game(game);
+    }
+}
+
+struct SaveWriter {
+    bytes: Vec<u8>,
+}
+
+impl SaveWriter {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn i32(&mut self, value: i32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+    fn vec2(&mut self, value: Vec2) {
+        self.f32(value.x);
+        self.f32(value.y);
+    }
+    fn str(&mut self, value: &str) {
+        self.u16(value.len() as u16);
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+    fn item_kinds(&mut self, kinds: &[ItemKind]) {
+        self.u16(kinds.len() as u16);
+        for &kind in kinds {
+            self.u8(kind as u8);
+        }
+    }
+}
+
+struct SaveReader<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> SaveReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], SaveError> {
+        let bytes = self.bytes.get(self.cursor..self.cursor+count).ok_or(SaveError::Truncated)?;
+        self.cursor += count;
+        Ok(bytes)
+    }
+    fn u8(&mut self) -> Result<u8, SaveError> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, SaveError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    fn u32(&mut self) -> Result<u32, SaveError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn u64(&mut self) -> Result<u64, SaveError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn i32(&mut self) -> Result<i32, SaveError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn f32(&mut self) -> Result<f32, SaveError> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn bool(&mut self) -> Result<bool, SaveError> {
+        match self.u8()? {
+        | 0 => Ok(false),
+        | 1 => Ok(true),
+        | _ => Err(SaveError::Corrupt("invalid bool")),
+        }
+    }
+    fn vec2(&mut self) -> Result<Vec2, SaveError> {
+        Ok(vec2(self.f32()?, self.f32()?))
+    }
+    fn str(&mut self) -> Result<String, SaveError> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| SaveError::Corrupt("invalid string"))
+    }
+    fn item_kinds(&mut self) -> Result<Vec<ItemKind>, SaveError> {
+        let count = self.u16()? as usize;
+        let mut kinds = Vec::with_capacity(count);
+        for _ in 0..count {
+            let kind = *ItemKind::VARIANTS.get(self.u8()? as usize).ok_or(SaveError::Corrupt("unknown item"))?;
+            kinds.push(kind);
+        }
+        Ok(kinds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::headless::{ headless_game };
+
+    #[test]
+    fn slot_info_is_read_from_the_save() {
+        let mut game = headless_game(1);
+        game.save_name = "Deep run".to_string();
+        game.deepest_depth = 42;
+        game.money = 120;
+        game.total_time = 75.0;
+
+        let save = SaveData::decode(&SaveData::capture(&game).encode()).unwrap();
+        let info = SlotInfo::from_save(&save);
+        assert_eq!(info.name, "Deep run");
+        assert_eq!(info.deepest_depth, 42);
+        assert_eq!(info.money, 120);
+        assert_eq!(info.total_time, 75.0);
+        assert_eq!(info.upgrades_owned, game.upgrades.tiers().iter().map(|tier| *tier as u32).sum());
+
+        assert_eq!(slot_label(0, &info.name), "Deep run");
+        assert_eq!(slot_label(2, ""), "Slot 3");
+        assert_eq!(slot_key(0), "game");
+        assert_eq!(slot_key(3), "game4");
+    }
+}
@@ -0,0 +1,178 @@
+// INFO: Player settings, kept under the "settings" storage key as a small text table (same format as data/)
+// so they survive restarts on native and web builds alike. Window scale is native only, the page decides the
+// canvas size on the web.
+
+use crate::prelude::*;
+
+use crate::data::{ self, DataError };
+use crate::save::{ SAVE_SLOTS };
+use crate::storage;
+
+const SETTINGS_KEY: &str = "settings";
+
+pub const VOLUME_STEP: u32 = 10;
+// INFO: Multiples of the game resolution, 8x is the 1280x960 window the game always opened with.
+pub const WINDOW_SCALES: [u32; 4] = [4, 6, 8, 10];
+pub const DEFAULT_WINDOW_SCALE: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    // INFO: Percent, 0..=100 in VOLUME_STEP steps.
+    pub master_volume: u32,
+    pub volumes: [u32; Bus::LENGTH],
+    pub muted: [bool; Bus::LENGTH],
+    pub window_scale: u32,
+    pub fullscreen: bool,
+    // INFO: Fill the window instead of scaling by whole numbers, see viewport.rs.
+    pub smooth_scaling: bool,
+    // INFO: Slot that Continue loads and New Game writes, picked on the Saves screen.
+    pub save_slot: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            master_volume: 100,
+            volumes: [100; Bus::LENGTH],
+            muted: [false; Bus::LENGTH],
+            window_scale: DEFAULT_WINDOW_SCALE,
+            fullscreen: false,
+            smooth_scaling: false,
+            save_slot: 0,
+        }
+    }
+}
+
+pub fn step_volume(volume: u32, up: bool) -> u32 {
+    match up {
+    | true  => u32::min(volume + VOLUME_STEP, 100),
+    | false => volume.saturating_sub(VOLUME_STEP),
+    }
+}
+
+pub fn next_window_scale(scale: u32) -> u32 {
+    let i = WINDOW_SCALES.iter().position(|other| *other == scale).unwrap_or(0);
+    WINDOW_SCALES[(i+1) % WINDOW_SCALES.len()]
+}
+
+fn bus_by_key(key: &str) -> Option<Bus> {
+    Bus::VARIANTS.into_iter().find(|bus| bus.key() == key)
+}
+
+// INFO: Unknown keys are errors, missing ones keep their defaults. Volumes are <bus>_volume and mutes
+// <bus>_muted, see Bus::key.
+pub fn parse_settings(source: &str) -> Result<Settings, DataError> {
+    let mut settings = Settings::default();
+
+    for record in data::records(source) {
+        record.expect_fields(2)?;
+        let key = record.str(0)?;
+
+        if key == "master_volume" {
+            settings.master_volume = u32::min(record.parse(1)?, 100);
+        } else if let Some(bus) = key.strip_suffix("_volume").and_then(bus_by_key) {
+            settings.volumes[bus as usize] = u32::min(record.parse(1)?, 100);
+        } else if let Some(bus) = key.strip_suffix("_muted").and_then(bus_by_key) {
+            settings.muted[bus as usize] = record.parse(1)?;
+        } else if key == "window_scale" {
+            let scale = record.parse(1)?;
+            if !WINDOW_SCALES.contains(&scale) { return Err(record.error(format!("unsupported window scale {}", scale))); }
+            settings.window_scale = scale;
+        } else if key == "fullscreen" {
+            settings.fullscreen = record.parse(1)?;
+        } else if key == "smooth_scaling" {
+            settings.smooth_scaling = record.parse(1)?;
+        } else if key == "save_slot" {
+            let slot = record.parse(1)?;
+            if slot >= SAVE_SLOTS { return Err(record.error(format!("save slot {} out of range", slot))); }
+            settings.save_slot = slot;
+        } else {
+            return Err(record.error(format!("unknown key \"{}\"", key)));
+        }
+    }
+
+    Ok(settings)
+}
+
+pub fn format_settings(settings: &Settings) -> String {
+    let mut text = format!("master_volume {}\n", settings.master_volume);
+    for bus in Bus::VARIANTS {
+        text.push_str(&format!("{}_volume {}\n", bus.key(), settings.volumes[bus as usize]));
+        text.push_str(&format!("{}_muted {}\n", bus.key(), settings.muted[bus as usize]));
+    }
+    text.push_str(&format!("window_scale {}\nfullscreen {}\n", settings.window_scale, settings.fullscreen));
+    text.push_str(&format!("smooth_scaling {}\n", settings.smooth_scaling));
+    text.push_str(&format!("save_slot {}\n", settings.save_slot));
+    text
+}
+
+// INFO: Broken stored settings fall back to the defaults instead of stopping the game from starting.
+pub fn load_settings() -> Settings {
+    let Some(bytes) = storage::read(SETTINGS_KEY) else { return Settings::default() };
+
+    match parse_settings(&String::from_utf8_lossy(&bytes)) {
+    | Ok(settings) => settings,
+    | Err(err) => {
+        warn!("Ignoring stored settings: {}", err);
+        Settings::default()
+    },
+    }
+}
+
+pub fn save_settings(settings: &Settings) {
+    if !storage::write(SETTINGS_KEY, format_settings(settings).as_bytes()) {
+        warn!("Couldn't save settings");
+    }
+}
+
+// INFO: Pushes settings out to the sound player and the window. Window changes only happen when asked for,
+// resizing on every call would fight with the player dragging the window around.
+pub fn apply_settings(settings: &Settings, sound_player: &mut SoundPlayer, window: bool) {
+    let mixer = &mut sound_player.mixer;
+    mixer.master = settings.master_volume as f32 / 100.0;
+    for bus in Bus::VARIANTS {
+        mixer.volumes[bus as usize] = settings.volumes[bus as usize] as f32 / 100.0;
+        mixer.muted[bus as usize] = settings.muted[bus as usize];
+    }
+    sound_player.update_loop_volumes();
+
+    if !window { return; }
+
+    set_fullscreen(settings.fullscreen);
+    #[cfg(not(target_family = "wasm"))]
+    if !settings.fullscreen {
+        let scale = settings.window_scale as f32;
+        request_new_screen_size(GAME_WIDTH_F32*scale, GAME_HEIGHT_F32*scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_through_text() {
+        let mut settings = Settings { master_volume: 70, window_scale: 6, fullscreen: true, smooth_scaling: true, save_slot: 2, ..Default::default() };
+        settings.volumes[Bus::Ambience as usize] = 30;
+        settings.muted[Bus::Music as usize] = true;
+        assert_eq!(parse_settings(&format_settings(&settings)).unwrap(), settings);
+        assert_eq!(parse_settings("").unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn bad_settings_are_rejected() {
+        assert!(parse_settings("window_scale 5\n").is_err());
+        assert!(parse_settings("volume 10\n").is_err());
+        assert!(parse_settings("bass_volume 10\n").is_err());
+        assert!(parse_settings("save_slot 9\n").is_err());
+        assert_eq!(parse_settings("music_volume 400\n").unwrap().volumes[Bus::Music as usize], 100);
+    }
+
+    #[test]
+    fn volume_steps_stay_in_range() {
+        assert_eq!(step_volume(100, true), 100);
+        assert_eq!(step_volume(0, false), 0);
+        assert_eq!(step_volume(50, false), 40);
+        assert_eq!(next_window_scale(10), 4);
+    }
+}
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use macroquad::prelude::*;
 
 use crate::{asset::AssetState, consts::NOT_SO_VERY_BIG_NUMBER};
+use crate::atlas::{ AtlasImage };
 
 #[derive(Debug)]
 pub struct Sprite {
@@ -12,12 +13,37 @@ pub struct Sprite {
     pub flip_x: bool,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoopMode {
+    Loop,
+    // INFO: Holds the last frame once it gets there.
+    Once,
+    // INFO: Plays forward and then back, without showing the first and last frames twice.
+    PingPong,
+}
+
+impl LoopMode {
+    pub const LENGTH: usize = std::mem::variant_count::<Self>();
+    pub const VARIANTS: [LoopMode; Self::LENGTH] = const {
+        let mut variants = [LoopMode::Loop; Self::LENGTH];
+
+        let mut i = 0;
+        while i < Self::LENGTH {
+            variants[i] = unsafe { std::mem::transmute::<u8, LoopMode>(i as u8) };
+            i += 1;
+        }
+
+        variants
+    };
+}
+
 #[derive(Debug)]
 pub struct Animation {
     pub asset_id: u64,
     pub frames: Arc<[Rect]>,
+    pub durations: Arc<[f32]>,
+    pub mode: LoopMode,
     pub index: usize,
-    pub frame_timer: f32,
     pub accumulated: f32,
     pub finished: bool,
     pub repeated: i32,
@@ -29,13 +55,11 @@ pub struct SpriteAsset {
     pub asset_id: u64,
     pub texture: Texture2D,
     pub frames: Arc<[Rect]>,
-    pub frame_timer: f32,
+    // INFO: In seconds, one per frame.
+    pub durations: Arc<[f32]>,
+    pub mode: LoopMode,
 }
 
-#[derive(Copy, Clone)] pub struct Offset(pub i32, pub i32);
-#[derive(Copy, Clone)] pub struct RowCol(pub i32, pub i32);
-#[derive(Copy, Clone)] pub struct Size(pub i32, pub i32);
-
 impl Sprite {
     pub fn local_offset_with_flip(&self, offset: Vec2) -> Vec2 {
         if !self.flip_x {
@@ -53,6 +77,20 @@ impl Animation {
     pub fn is_not(&self, sprite_asset: &SpriteAsset) -> bool {
         return self.asset_id != sprite_asset.asset_id;
     }
+    // INFO: Index runs through the frames in the order they are shown, ping pong shows most of them twice.
+    fn sequence_len(&self) -> usize {
+        match self.mode {
+        | LoopMode::PingPong => usize::max(self.frames.len()*2 - 2, 1),
+        | _ => self.frames.len(),
+        }
+    }
+    fn frame_index(&self) -> usize {
+        let index = usize::min(self.index, self.sequence_len()-1);
+        match index < self.frames.len() {
+        | true  => index,
+        | false => self.sequence_len() - index,
+        }
+    }
 }
 
 impl SpriteAsset {
@@ -65,284 +103,355 @@ impl SpriteAsset {
         }
     }
     pub fn derive_anim(&self) -> Animation {
-        Animation {
+        let mut anim = Animation {
             asset_id: self.asset_id,
             // TODO: use something else than arc, probs index
             frames: Arc::clone(&self.frames),
-            index: self.frames.len(),
-            frame_timer: self.frame_timer,
-            accumulated: self.frame_timer,
+            durations: Arc::clone(&self.durations),
+            mode: self.mode,
+            index: 0,
+            accumulated: 0.0,
             finished: false,
             repeated: -1,
             modifier: 1.0,
-        }
+        };
+        // INFO: Starts past the end, so the first tick wraps around to the first frame.
+        anim.index = anim.sequence_len();
+        anim.accumulated = anim.durations[anim.frame_index()];
+        anim
     }
 }
 
-pub fn load_sprite(
+pub fn new_sprite_asset(
     asset_state: &mut AssetState,
     texture: &Texture2D,
-    offset: Offset,
-    size: Size
+    frames: Vec<Rect>,
+    durations: Vec<f32>,
+    mode: LoopMode,
 ) -> SpriteAsset {
     let asset_id = asset_state.asset_id;
     asset_state.asset_id += 1;
 
-    let texture = texture.clone();
-    let Offset(offset_x, offset_y) = offset;
-    let Size(width, height) = size;
-
-    let offset_x = offset_x as f32;
-    let offset_y = offset_y as f32;
-
-    let texture_sizef = texture.size();
-    texture.set_filter(FilterMode::Nearest);
-    
-    let frame = Rect::new(offset_x, offset_y, width as f32, height as f32);
-
-    assert!(frame.x + frame.w <= texture_sizef.x);
-    assert!(frame.y + frame.h <= texture_sizef.y);
+    debug_assert!(!frames.is_empty() && frames.len() == durations.len());
+    asset_state.set_filter(texture, FilterMode::Nearest);
 
     SpriteAsset {
         asset_id,
-        texture,
-        frames: [frame].into(),
-        frame_timer: NOT_SO_VERY_BIG_NUMBER,
+        texture: texture.clone(),
+        frames: frames.into(),
+        durations: durations.into(),
+        mode,
     }
 }
 
-pub fn load_sheet_cell(
-    asset_state: &mut AssetState,
-    texture: &Texture2D,
-    row_col: RowCol,
-    size: Size,
-) -> SpriteAsset {
-    load_anim(asset_state, texture, row_col, 1, size, NOT_SO_VERY_BIG_NUMBER)
+// first 3 are unpressed, mid 3 are pressed, last 3 are disabled
+pub fn load_three_patch(asset_state: &mut AssetState, image: AtlasImage) -> [[SpriteAsset; 3]; 3] {
+    let size = (asset_state.atlas.size(image) - vec2(2.0 * 9.0, 0.0)) / vec2(9.0, 1.0);
+    let size = size.floor();
+
+    let mut cell = |i: usize| {
+        let frame = Rect::new(1.0 + i as f32 * (size.x + 2.0), 1.0, size.x, size.y);
+        let (texture, frames) = asset_state.atlas.place(image, &[frame]);
+        new_sprite_asset(asset_state, &texture, frames, vec![NOT_SO_VERY_BIG_NUMBER], LoopMode::Loop)
+    };
+
+    [
+        [ cell(0), cell(1), cell(2) ],
+        [ cell(3), cell(4), cell(5) ],
+        [ cell(6), cell(7), cell(8) ],
+    ]
 }
 
-pub fn load_sheet_cells(
-    asset_state: &mut AssetState,
-    texture: &Texture2D,
-    row_col: RowCol,
-    cell_count: u32,
-    size: Size,
-) -> Box<[SpriteAsset]> {
-    let mut cells = Vec::with_capacity(cell_count as usize);
-
-    for x in 0..cell_count {
-        cells.push(load_anim(
-            asset_state, texture, RowCol(row_col.0+x as i32, row_col.1), 1, size, NOT_SO_VERY_BIG_NUMBER
-        ));
+pub fn tick_animation(sprite: &mut Sprite, anim: &mut Animation, dt: f32) {
+    if anim.mode == LoopMode::Once && anim.repeated > 0 {
+        sprite.texture_frame = anim.frames[anim.frames.len()-1];
+        anim.finished = true;
+        return;
     }
 
-    cells.into_boxed_slice()
+    anim.accumulated += dt*f32::max(anim.modifier, 0.001);
+    let duration = anim.durations[anim.frame_index()];
+    if anim.accumulated > duration {
+        anim.accumulated = anim.accumulated - duration;
+        anim.index += 1;
+        if anim.index >= anim.sequence_len() {
+            anim.index = 0;
+            anim.repeated += 1;
+            anim.finished = true;
+        }
+    } else {
+        sprite.texture_frame = anim.frames[anim.frame_index()];
+        anim.finished = false;
+    }
 }
 
+// batch :::
+// INFO: World sprites aren't drawn right away, they are collected into buckets by layer and texture and drawn as
+// a few meshes when the batch is flushed, which is what keeps hundreds of coins after a big sale cheap. Layers
+// are drawn in order, within a layer quads keep the order they came in as long as they share a texture (with
+// the atlas they nearly always do). UI sprites go on the last layer. Text, rectangles and the minimap are still
+// drawn right away, so whatever should be under them has to be flushed first.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SpriteLayer {
+    // INFO: Cracks and marks on tiles, rails.
+    Ground,
+    // INFO: Minecart, statue and signs.
+    Props,
+    Coins,
+    // INFO: Elevator platform and demolisher.
+    Machines,
+    Items,
+    Particles,
+    Player,
+    // INFO: In front of the player, the elevator cage.
+    Front,
+    // INFO: Key hints, drawn over the presented world with their own flush.
+    Overlay,
+    // INFO: Menus and the HUD, with the UI camera that is y+ down.
+    Ui,
+}
 
-// first 3 are unpressed, mid 3 are pressed, last 3 are disabled
-pub fn load_three_patch(asset_state: &mut AssetState, texture: &Texture2D) -> [[SpriteAsset; 3]; 3] {
-    let size = (texture.size() - vec2(2.0 * 9.0, 0.0)) / vec2(9.0, 1.0);
-    let size = Size(size.x as i32, size.y as i32);
+// INFO: macroquad caps a draw call at 5000 indices by default, bigger meshes get clamped.
+const QUADS_PER_MESH: usize = 800;
 
-    [
-        [
-            load_sheet_cell(asset_state, texture, RowCol(0, 0), size),
-            load_sheet_cell(asset_state, texture, RowCol(1, 0), size),
-            load_sheet_cell(asset_state, texture, RowCol(2, 0), size),
-        ],
-        [
-            load_sheet_cell(asset_state, texture, RowCol(3, 0), size),
-            load_sheet_cell(asset_state, texture, RowCol(4, 0), size),
-            load_sheet_cell(asset_state, texture, RowCol(5, 0), size),
-        ],
-        [
-            load_sheet_cell(asset_state, texture, RowCol(6, 0), size),
-            load_sheet_cell(asset_state, texture, RowCol(7, 0), size),
-            load_sheet_cell(asset_state, texture, RowCol(8, 0), size),
-        ]
-    ]
+struct SpriteBucket {
+    layer: SpriteLayer,
+    // INFO: None for plain colored quads, macroquad fills in a white texture.
+    texture: Option<Texture2D>,
+    texture_id: Option<miniquad::TextureId>,
+    texture_size: Vec2,
+    vertices: Vec<Vertex>,
 }
 
+pub struct SpriteBatch {
+    buckets: Vec<SpriteBucket>,
+    // INFO: Refilled for every draw, so flushing doesn't allocate once the buffers have grown.
+    mesh: Mesh,
+}
 
-pub fn load_anim(
-    asset_state: &mut AssetState,
-    texture: &Texture2D,
-    row_col: RowCol,
-    frame_count: u32,
-    size: Size,
-    frame_timer_ms: f32,
-) -> SpriteAsset {
-    // 2.0 is padding due to both borders, while 1.0 is individual padding offset
-    let asset_id = asset_state.asset_id;
-    asset_state.asset_id += 1;
+// INFO: The same corners and UVs draw_texture_ex makes. Rotation is around the quad's center, sprites are
+// always flipped on y because the world is y+ up.
+pub fn sprite_quad(position: Vec2, size: Vec2, rotation: f32, flip_x: bool, frame: Rect, texture_size: Vec2) -> [Vertex; 4] {
+    quad(position, size, rotation, flip_x, true, frame, texture_size)
+}
 
-    let texture = texture.clone();
-    let RowCol(row, col) = row_col;
-    let Size(width, height) = size;
+fn quad(position: Vec2, size: Vec2, rotation: f32, flip_x: bool, flip_y: bool, frame: Rect, texture_size: Vec2) -> [Vertex; 4] {
+    let mut pos = position;
+    let mut size = size;
+    if flip_x {
+        pos.x += size.x;
+        size.x = -size.x;
+    }
+    if flip_y {
+        pos.y += size.y;
+        size.y = -size.y;
+    }
 
-    let widthf = width as f32;
-    let heightf = height as f32;
+    let center = pos + size / 2.0;
+    let rotation = Vec2::from_angle(rotation);
+    let begin = frame.point() / texture_size;
+    let end = (frame.point() + frame.size()) / texture_size;
+
+    let corners = [
+        (pos,                           begin),
+        (pos + vec2(size.x, 0.0),       vec2(end.x, begin.y)),
+        (pos + size,                    end),
+        (pos + vec2(0.0, size.y),       vec2(begin.x, end.y)),
+    ];
+    corners.map(|(corner, uv)| Vertex::new2((center + rotation.rotate(corner - center)).extend(0.0), uv, WHITE))
+}
 
-    let row_offset = row as f32 * (widthf + 2.0);
-    let col_offset = col as f32 * (heightf + 2.0);
+impl SpriteBatch {
+    pub fn new() -> Self {
+        SpriteBatch {
+            buckets: Vec::with_capacity(16),
+            mesh: Mesh { vertices: Vec::new(), indices: Vec::new(), texture: None },
+        }
+    }
 
-    let texture_sizef = texture.size();
-    let texture_rows = (texture_sizef.x / widthf) as i32;
-    let texture_cols = (texture_sizef.y / heightf) as i32;
-    texture.set_filter(FilterMode::Nearest);
+    fn bucket(&mut self, layer: SpriteLayer, texture: Option<&Texture2D>) -> &mut SpriteBucket {
+        let texture_id = texture.map(|texture| texture.raw_miniquad_id());
+        let i = match self.buckets.iter().position(|bucket| bucket.layer == layer && bucket.texture_id == texture_id) {
+        | Some(i) => i,
+        | None => {
+            self.buckets.push(SpriteBucket {
+                layer,
+                texture: texture.map(Texture2D::weak_clone),
+                texture_id,
+                texture_size: texture.map(Texture2D::size).unwrap_or(Vec2::ONE),
+                vertices: Vec::new(),
+            });
+            self.buckets.len() - 1
+        },
+        };
+        &mut self.buckets[i]
+    }
 
-    assert!(row_offset <= texture_sizef.x);
-    assert!(col_offset <= texture_sizef.y);
+    fn push_sprite(&mut self, layer: SpriteLayer, position: Vec2, size: Vec2, rotation: f32, sprite: &Sprite) {
+        let bucket = self.bucket(layer, Some(&sprite.texture));
+        let quad = sprite_quad(position, size, rotation, sprite.flip_x, sprite.texture_frame, bucket.texture_size);
+        bucket.vertices.extend_from_slice(&quad);
+    }
 
-    let mut frames = Vec::with_capacity(frame_count as usize);
+    pub fn draw_sprite(&mut self, layer: SpriteLayer, position: Vec2, sprite: &Sprite) {
+        self.push_sprite(layer, position, sprite.texture_frame.size(), 0.0, sprite);
+    }
 
-    let remaining_in_first_row = i32::min(texture_rows - row, frame_count as i32);
+    pub fn draw_sprite_rotated(&mut self, layer: SpriteLayer, position: Vec2, rotation: f32, sprite: &Sprite) {
+        self.push_sprite(layer, position, sprite.texture_frame.size(), rotation, sprite);
+    }
 
-    let mut x = row_offset;
-    let mut y = col_offset;
-    
-    for _ in 0..remaining_in_first_row {
-        frames.push(Rect::new(x+1.0, y+1.0, widthf, heightf));
-        x += widthf + 2.0;
+    pub fn draw_sprite_scaled(&mut self, layer: SpriteLayer, position: Vec2, scale: Vec2, sprite: &Sprite) {
+        self.push_sprite(layer, position, sprite.texture_frame.size()*scale, 0.0, sprite);
     }
 
-    let remaining_in_other_cols = frame_count as i32 - remaining_in_first_row;
-    let remaining_cols = remaining_in_other_cols / texture_rows;
-    let remaining_rows = remaining_in_other_cols % texture_rows;
+    pub fn draw_sprite_offset(&mut self, layer: SpriteLayer, position: Vec2, offset: Vec2, sprite: &Sprite) {
+        self.draw_sprite(layer, position + offset, sprite);
+    }
 
-    assert!(col + remaining_cols <= texture_cols);
+    pub fn draw_rect(&mut self, layer: SpriteLayer, rect: Rect, color: Color) {
+        let bucket = self.bucket(layer, None);
+        bucket.vertices.extend_from_slice(&[
+            Vertex::new(rect.x,          rect.y,          0.0, 0.0, 0.0, color),
+            Vertex::new(rect.right(),    rect.y,          0.0, 0.0, 0.0, color),
+            Vertex::new(rect.right(),    rect.bottom(),   0.0, 0.0, 0.0, color),
+            Vertex::new(rect.x,          rect.bottom(),   0.0, 0.0, 0.0, color),
+        ]);
+    }
 
-    for _ in 0..remaining_cols {
-        x = 0.0;
-        y += heightf;
-        for _ in 0..texture_rows {
-            frames.push(Rect::new(x+1.0, y+1.0, widthf, heightf));
-            x += widthf + 2.0;
+    // INFO: Draws everything with the camera that is set now.
+    pub fn flush(&mut self) {
+        // NOTE: Stable, buckets of a layer stay in the order their textures first showed up.
+        self.buckets.sort_by_key(|bucket| bucket.layer);
+
+        let mesh = &mut self.mesh;
+        for bucket in &mut self.buckets {
+            for quads in bucket.vertices.chunks(QUADS_PER_MESH*4) {
+                mesh.vertices.clear();
+                mesh.vertices.extend_from_slice(quads);
+                mesh.indices.clear();
+                for i in (0..quads.len() as u16).step_by(4) {
+                    mesh.indices.extend_from_slice(&[i, i+1, i+2, i, i+2, i+3]);
+                }
+                mesh.texture = bucket.texture.clone();
+                draw_mesh(mesh);
+            }
+            bucket.vertices.clear();
         }
+        mesh.texture = None;
     }
 
-    y += heightf + 2.0;
-    for _ in 0..remaining_rows {
-        frames.push(Rect::new(x+1.0, y+1.0, widthf, heightf));
-        x += widthf + 2.0;
+    fn push_ui(&mut self, texture: &Texture2D, position: Vec2, size: Vec2, rotation: f32, flip: (bool, bool), frame: Rect) {
+        let bucket = self.bucket(SpriteLayer::Ui, Some(texture));
+        let quad = quad(position, size, rotation, flip.0, flip.1, frame, bucket.texture_size);
+        bucket.vertices.extend_from_slice(&quad);
     }
-    
-    SpriteAsset {
-        asset_id,
-        texture,
-        frames: frames.into(),
-        frame_timer: frame_timer_ms / 1000.0,
+
+    pub fn draw_ui(&mut self, position: Vec2, scale: Vec2, sprite: &Sprite) {
+        self.push_ui(&sprite.texture, position, sprite.texture_frame.size()*scale, 0.0, (false, false), sprite.texture_frame);
     }
-}
 
-pub fn tick_animation(sprite: &mut Sprite, anim: &mut Animation, dt: f32) {
-    anim.accumulated += dt*f32::max(anim.modifier, 0.001);
-    if anim.accumulated > anim.frame_timer {
-        anim.accumulated = anim.accumulated - anim.frame_timer;
-        anim.index += 1;
-        if anim.index >= anim.frames.len() {
-            anim.index = 0;
-            anim.repeated += 1;
-            anim.finished = true;
-        }
-    } else {
-        sprite.texture_frame = anim.frames[anim.index];
-        anim.finished = false;
+    pub fn draw_ui_rotated(&mut self, position: Vec2, scale: Vec2, rotation: f32, sprite: &Sprite) {
+        self.push_ui(&sprite.texture, position, sprite.texture_frame.size()*scale, rotation, (false, false), sprite.texture_frame);
     }
-}
 
-pub fn draw_sprite(position: Vec2, sprite: &Sprite) {    
-    draw_texture_ex(&sprite.texture, position.x, position.y, WHITE, DrawTextureParams {
-        source: Some(sprite.texture_frame),
-        flip_x: sprite.flip_x,
-        flip_y: true, // because I enjoy y+
-        ..Default::default()
-    });
-}
+    #[allow(dead_code)]
+    pub fn draw_ui_rotated_flip(&mut self, position: Vec2, scale: Vec2, rotation: f32, flip_x: bool, flip_y: bool, sprite: &Sprite) {
+        self.push_ui(&sprite.texture, position, sprite.texture_frame.size()*scale, rotation, (flip_x, flip_y), sprite.texture_frame);
+    }
 
-pub fn draw_sprite_rotated(position: Vec2, rotation: f32, sprite: &Sprite) {    
-    draw_texture_ex(&sprite.texture, position.x, position.y, WHITE, DrawTextureParams {
-        source: Some(sprite.texture_frame),
-        flip_x: sprite.flip_x,
-        flip_y: true, // because I enjoy y+
-        rotation,
-        ..Default::default()
-    });
-}
+    pub fn draw_ui_partial(&mut self, position: Vec2, scale: Vec2, partial: Vec2, sprite: &Sprite) {
+        let mut source = sprite.texture_frame;
+        let offset_x = source.w * scale.x * (1.0-partial.x);
+        let offset_y = source.h * scale.y * (1.0-partial.y);
 
-pub fn draw_sprite_scaled(position: Vec2, scale: Vec2, sprite: &Sprite) {    
-    draw_texture_ex(&sprite.texture, position.x, position.y, WHITE, DrawTextureParams {
-        dest_size: Some(sprite.texture_frame.size()*scale),
-        source: Some(sprite.texture_frame),
-        flip_x: sprite.flip_x,
-        flip_y: true, // because I enjoy y+
-        ..Default::default()
-    });
-}
+        source.w *= partial.x;
+        source.h *= partial.y;
 
-pub fn draw_sprite_offset(position: Vec2, offset: Vec2, sprite: &Sprite) {
-    draw_sprite(position + offset, sprite);
-}
+        let size = sprite.texture_frame.size()*scale*partial;
+        self.push_ui(&sprite.texture, position + vec2(offset_x, offset_y), size, 0.0, (false, false), source);
+    }
 
-pub fn draw_ui(position: Vec2, scale: Vec2, sprite: &Sprite) {
-    draw_texture_ex(&sprite.texture, position.x, position.y, WHITE, DrawTextureParams {
-        dest_size: Some(sprite.texture_frame.size()*scale),
-        source: Some(sprite.texture_frame),
-        ..Default::default()
-    });
-}
+    pub fn draw_ui_three_patch(&mut self, position: Vec2, width: f32, sprites: &[SpriteAsset; 3]) {
+        // HACK: Well...
+        let texture = &sprites[0].texture;
+        let side_width = &sprites[0].frames[0].w;
+        let mut cursor = position;
 
-pub fn draw_ui_rotated(position: Vec2, scale: Vec2, rotation: f32, sprite: &Sprite) {
-    draw_texture_ex(&sprite.texture, position.x, position.y, WHITE, DrawTextureParams {
-        dest_size: Some(sprite.texture_frame.size()*scale),
-        source: Some(sprite.texture_frame),
-        rotation,
-        ..Default::default()
-    });
-}
+        let mut sprites = sprites.each_ref().map(|sprite| (sprite, sprite.frames[0].size().x));
+        sprites[1].1 = width - side_width*2.0;
 
-#[allow(dead_code)]
-pub fn draw_ui_rotated_flip(position: Vec2, scale: Vec2, rotation: f32, flip_x: bool, flip_y: bool, sprite: &Sprite) {
-    draw_texture_ex(&sprite.texture, position.x, position.y, WHITE, DrawTextureParams {
-        dest_size: Some(sprite.texture_frame.size()*scale),
-        source: Some(sprite.texture_frame),
-        flip_x,
-        flip_y,
-        rotation,
-        ..Default::default()
-    });
+        for (sprite, size) in sprites {
+            self.push_ui(texture, cursor, vec2(size, sprite.frames[0].h), 0.0, (false, false), sprite.frames[0]);
+            cursor += vec2(size, 0.0);
+        }
+    }
 }
 
-pub fn draw_ui_partial(position: Vec2, scale: Vec2, partial: Vec2, sprite: &Sprite) {
-    let mut source = sprite.texture_frame;
-    let offset_x = source.w * scale.x * (1.0-partial.x);
-    let offset_y = source.h * scale.y * (1.0-partial.y);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::asset::{ headless_texture };
+
+    fn shown_frames(mode: LoopMode, ticks: usize) -> Vec<f32> {
+        let frames: Vec<Rect> = (0..3).map(|i| Rect::new(i as f32, 0.0, 1.0, 1.0)).collect();
+        let asset = SpriteAsset {
+            asset_id: 0,
+            texture: headless_texture(),
+            frames: frames.into(),
+            durations: [1.0, 1.0, 1.0].into(),
+            mode,
+        };
+        let mut sprite = asset.derive_sprite();
+
+        // INFO: The first tick only wraps around to the first frame, after that ticks take turns moving on
+        // and showing.
+        let mut anim = asset.derive_anim();
+        tick_animation(&mut sprite, &mut anim, 0.5);
+
+        let mut shown = Vec::new();
+        for _ in 0..ticks {
+            tick_animation(&mut sprite, &mut anim, 0.5);
+            shown.push(sprite.texture_frame.x);
+        }
+        shown.dedup();
+        shown
+    }
 
-    source.w *= partial.x;
-    source.h *= partial.y;
+    #[test]
+    fn sprite_quads_are_flipped_for_y_up() {
+        let frame = Rect::new(16.0, 0.0, 16.0, 8.0);
+        let texture_size = vec2(64.0, 32.0);
+        let positions = |quad: [Vertex; 4]| quad.map(|vertex| vertex.position.truncate());
 
-    draw_texture_ex(&sprite.texture, offset_x+position.x, offset_y+position.y, WHITE, DrawTextureParams {
-        dest_size: Some(sprite.texture_frame.size()*scale*partial),
-        source: Some(source),
-        ..Default::default()
-    });
-}
+        let quad = sprite_quad(vec2(10.0, 20.0), frame.size(), 0.0, false, frame, texture_size);
+        assert_eq!(positions(quad), [vec2(10.0, 28.0), vec2(26.0, 28.0), vec2(26.0, 20.0), vec2(10.0, 20.0)]);
+        assert_eq!(quad[0].uv, vec2(0.25, 0.0));
+        assert_eq!(quad[2].uv, vec2(0.5, 0.25));
+
+        let flipped = sprite_quad(vec2(10.0, 20.0), frame.size(), 0.0, true, frame, texture_size);
+        assert_eq!(positions(flipped)[0], vec2(26.0, 28.0));
+
+        // INFO: A half turn around the center lands every corner on the opposite one.
+        let turned = sprite_quad(vec2(10.0, 20.0), frame.size(), std::f32::consts::PI, false, frame, texture_size);
+        assert!(positions(turned)[0].distance(positions(quad)[2]) < 0.001);
+    }
+
+    #[test]
+    fn ui_quads_keep_y_down() {
+        let frame = Rect::new(16.0, 0.0, 16.0, 8.0);
+        let ui = quad(vec2(10.0, 20.0), frame.size(), 0.0, false, false, frame, vec2(64.0, 32.0));
+        assert_eq!(ui[0].position.truncate(), vec2(10.0, 20.0));
+        assert_eq!(ui[0].uv, vec2(0.25, 0.0));
+        assert_eq!(ui[2].position.truncate(), vec2(26.0, 28.0));
+    }
 
-pub fn draw_ui_three_patch(position: Vec2, width: f32, sprites: &[SpriteAsset; 3]) {
-    // HACK: Well...
-    let texture = &sprites[0].texture;
-    let side_width = &sprites[0].frames[0].w;
-    let mut cursor = position;
-
-    let mut sprites = sprites.each_ref().map(|sprite| (sprite, sprite.frames[0].size().x));
-    sprites[1].1 = width - side_width*2.0;
-
-    for (sprite, size) in sprites {
-        draw_texture_ex(texture, cursor.x, cursor.y, WHITE, DrawTextureParams {
-            source: Some(sprite.frames[0]),
-            dest_size: Some(vec2(size, sprite.frames[0].h)),
-            ..Default::default()
-        });
-        cursor += vec2(size, 0.0);
+    #[test]
+    fn loop_modes_play_frames_in_order() {
+        assert_eq!(shown_frames(LoopMode::Loop, 16)[..6], [0.0, 1.0, 2.0, 0.0, 1.0, 2.0]);
+        assert_eq!(shown_frames(LoopMode::PingPong, 20)[..6], [0.0, 1.0, 2.0, 1.0, 0.0, 1.0]);
+        assert_eq!(shown_frames(LoopMode::Once, 20), [0.0, 1.0, 2.0]);
     }
 }
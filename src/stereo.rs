@@ -0,0 +1,127 @@
+// INFO: macroquad's audio backend can make a sound louder or quieter, but has no pan. Sounds that come from
+// somewhere in the world are decoded once at load time and loaded twice instead, as a copy that only has
+// the left channel and one that only has the right. Both copies are always played together, the pan is the
+// volume each of them gets, see mixer::side_gains. Sounds that are always centered, like music and the UI,
+// aren't split, split ones take twice the memory.
+
+use std::io::Cursor;
+
+use macroquad::audio;
+
+use crate::mixer::{ side_gains };
+
+pub enum LoadedSound {
+    Centered(audio::Sound),
+    // INFO: Left copy, then right copy.
+    Panned([audio::Sound; 2]),
+}
+
+impl LoadedSound {
+    pub fn play(&self, volume: f32, pan: f32, looped: bool) {
+        match self {
+        | LoadedSound::Centered(sound) => audio::play_sound(sound, audio::PlaySoundParams { looped, volume }),
+        | LoadedSound::Panned(sides) => {
+            for (sound, gain) in sides.iter().zip(side_gains(pan)) {
+                audio::play_sound(sound, audio::PlaySoundParams { looped, volume: volume * gain });
+            }
+        },
+        }
+    }
+    pub fn stop(&self) {
+        match self {
+        | LoadedSound::Centered(sound) => audio::stop_sound(sound),
+        | LoadedSound::Panned(sides) => sides.iter().for_each(audio::stop_sound),
+        }
+    }
+    pub fn set_volume(&self, volume: f32, pan: f32) {
+        match self {
+        | LoadedSound::Centered(sound) => audio::set_sound_volume(sound, volume),
+        | LoadedSound::Panned(sides) => {
+            for (sound, gain) in sides.iter().zip(side_gains(pan)) {
+                audio::set_sound_volume(sound, volume * gain);
+            }
+        },
+        }
+    }
+}
+
+pub async fn load_sound_from_bytes(bytes: &[u8], panned: bool) -> Result<LoadedSound, String> {
+    if !panned {
+        let sound = audio::load_sound_from_bytes(bytes).await.map_err(|err| err.to_string())?;
+        return Ok(LoadedSound::Centered(sound));
+    }
+
+    let mut reader = audrey::Reader::new(Cursor::new(bytes)).map_err(|err| err.to_string())?;
+    let description = reader.description();
+    let samples: Vec<i16> = reader.samples::<i16>().collect::<Result<_, _>>().map_err(|err| err.to_string())?;
+
+    let [left, right] = split_channels(&samples, description.channel_count() as usize, description.sample_rate());
+    let left = audio::load_sound_from_bytes(&left).await.map_err(|err| err.to_string())?;
+    let right = audio::load_sound_from_bytes(&right).await.map_err(|err| err.to_string())?;
+    Ok(LoadedSound::Panned([left, right]))
+}
+
+// INFO: Interleaved samples in, a stereo wav with only the left channel and one with only the right out. Mono
+// sounds go to both sides. Channels past the second are dropped.
+pub fn split_channels(samples: &[i16], channels: usize, sample_rate: u32) -> [Vec<u8>; 2] {
+    let channels = channels.max(1);
+    let frames = samples.len() / channels;
+
+    let mut sides = [wav_header(frames, sample_rate), wav_header(frames, sample_rate)];
+    for frame in samples.chunks_exact(channels) {
+        let left = frame[0];
+        let right = frame[1.min(channels - 1)];
+        sides[0].extend_from_slice(&left.to_le_bytes());
+        sides[0].extend_from_slice(&0i16.to_le_bytes());
+        sides[1].extend_from_slice(&0i16.to_le_bytes());
+        sides[1].extend_from_slice(&right.to_le_bytes());
+    }
+    sides
+}
+
+// INFO: 16 bit stereo PCM, both backends read that.
+fn wav_header(frames: usize, sample_rate: u32) -> Vec<u8> {
+    let data_len = (frames * 4) as u32;
+
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * 4).to_le_bytes());
+    bytes.extend_from_slice(&4u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(wav: &[u8]) -> Vec<[i16; 2]> {
+        wav[44..].chunks_exact(4)
+            .map(|frame| [i16::from_le_bytes([frame[0], frame[1]]), i16::from_le_bytes([frame[2], frame[3]])])
+            .collect()
+    }
+
+    #[test]
+    fn channels_are_split_into_two_stereo_wavs() {
+        let [left, right] = split_channels(&[1, -2, 3, -4], 2, 22050);
+        assert_eq!(&left[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(left[24..28].try_into().unwrap()), 22050);
+        assert_eq!(u32::from_le_bytes(left[40..44].try_into().unwrap()), 8);
+        assert_eq!(frames(&left), [[1, 0], [3, 0]]);
+        assert_eq!(frames(&right), [[0, -2], [0, -4]]);
+
+        // INFO: Mono goes to both sides.
+        let [left, right] = split_channels(&[5, 6], 1, 44100);
+        assert_eq!(frames(&left), [[5, 0], [6, 0]]);
+        assert_eq!(frames(&right), [[0, 5], [0, 6]]);
+    }
+}
@@ -0,0 +1,67 @@
+// INFO: Persistent blobs addressed by a short key. Native builds keep them as files inside save/ next
+// to the executable's working directory, web builds keep them in the browser's localStorage through the
+// miner_storage plugin at the end of patched_js/mq_js_bundle.js.
+
+#[cfg(not(target_family = "wasm"))]
+mod backend {
+    use std::path::PathBuf;
+
+    const SAVE_DIR: &str = "save";
+
+    fn path_of(key: &str) -> PathBuf {
+        PathBuf::from(SAVE_DIR).join([key, ".bin"].join(""))
+    }
+
+    pub fn read(key: &str) -> Option<Vec<u8>> {
+        std::fs::read(path_of(key)).ok()
+    }
+    pub fn write(key: &str, bytes: &[u8]) -> bool {
+        if std::fs::create_dir_all(SAVE_DIR).is_err() { return false; }
+
+        // INFO: Writing to a temporary file first so a crash mid-write never leaves a half written save.
+        let path = path_of(key);
+        let tmp_path = path.with_extension("tmp");
+
+        std::fs::write(&tmp_path, bytes).is_ok() && std::fs::rename(&tmp_path, &path).is_ok()
+    }
+    pub fn remove(key: &str) -> bool {
+        let path = path_of(key);
+        !path.exists() || std::fs::remove_file(path).is_ok()
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod backend {
+    unsafe extern "C" {
+        fn miner_storage_len(key: *const u8, key_len: usize) -> i32;
+        fn miner_storage_read(key: *const u8, key_len: usize, buf: *mut u8, buf_len: usize);
+        fn miner_storage_write(key: *const u8, key_len: usize, buf: *const u8, buf_len: usize) -> i32;
+        fn miner_storage_remove(key: *const u8, key_len: usize) -> i32;
+    }
+
+    #[unsafe(no_mangle)]
+    pub extern "C" fn miner_storage_crate_version() -> u32 {
+        2
+    }
+
+    pub fn read(key: &str) -> Option<Vec<u8>> {
+        let len = unsafe { miner_storage_len(key.as_ptr(), key.len()) };
+        if len < 0 { return None; }
+
+        let mut bytes = vec![0u8; len as usize];
+        unsafe { miner_storage_read(key.as_ptr(), key.len(), bytes.as_mut_ptr(), bytes.len()) };
+        Some(bytes)
+    }
+    pub fn write(key: &str, bytes: &[u8]) -> bool {
+        unsafe { miner_storage_write(key.as_ptr(), key.len(), bytes.as_ptr(), bytes.len()) != 0 }
+    }
+    pub fn remove(key: &str) -> bool {
+        unsafe { miner_storage_remove(key.as_ptr(), key.len()) != 0 }
+    }
+}
+
+pub use backend::{ read, write, remove };
+
+pub fn exists(key: &str) -> bool {
+    read(key).is_some()
+}
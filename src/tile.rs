@@ -1,6 +1,9 @@
 use crate::prelude::*;
 
+use std::sync::LazyLock;
+
 use crate::asset::{ AssetState };
+use crate::data::{ self, DataError };
 
 pub mod consts {
     pub const CHUNK_SIDE: usize = 16;
@@ -66,6 +69,17 @@ pub enum Tile {
 
 impl Tile {
     pub const COUNT: usize = std::mem::variant_count::<Self>();
+    pub const VARIANTS: [Tile; Self::COUNT] = const {
+        let mut variants = [Tile::Empty; Self::COUNT];
+
+        let mut i = 0;
+        while i < Self::COUNT {
+            variants[i] = unsafe { std::mem::transmute::<usize, Tile>(i) };
+            i += 1;
+        }
+
+        variants
+    };
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -77,12 +91,25 @@ pub struct TileBounds {
 pub struct TileSetAsset {
     pub asset_id: u64,
     pub texture: Texture2D,
+    pub tile_size: IVec2,
     pub bounds: [TileBounds; Tile::COUNT],
 }
 
 #[derive(Copy, Clone)]
 pub struct TileChunk {
     pub tiles: [Tile; CHUNK_SIZE],
+    // INFO: See fog.
+    pub discovered: [bool; CHUNK_SIZE],
+}
+
+impl TileChunk {
+    // INFO: What the mesh shows, undiscovered tiles all look like plain rock (bake_lighting darkens them).
+    pub fn drawn_tiles(&self) -> impl Iterator<Item = Tile> + '_ {
+        self.tiles.iter().zip(self.discovered).map(|(tile, discovered)| match discovered {
+        | true  => *tile,
+        | false => Tile::Stone,
+        })
+    }
 }
 
 pub fn load_tile_set(asset_state: &mut AssetState, texture: &Texture2D, tile_size: IVec2) -> TileSetAsset {    
@@ -90,383 +117,142 @@ pub fn load_tile_set(asset_state: &mut AssetState, texture: &Texture2D, tile_siz
     asset_state.asset_id += 1;
 
     let texture = texture.clone();
+    let texture_size = asset_state.texture_size(&texture);
+    asset_state.set_filter(&texture, FilterMode::Nearest);
+
+    TileSetAsset {
+        asset_id,
+        texture,
+        tile_size,
+        bounds: tile_bounds(texture_size, tile_size),
+    }
+}
+
+// INFO: Bounds are in UVs, so they have to be worked out again whenever the texture changes size.
+pub fn tile_bounds(texture_size: Vec2, tile_size: IVec2) -> [TileBounds; Tile::COUNT] {
     let width = tile_size.x as f32;
     let height = tile_size.y as f32;
-    
-    let texture_size = texture.size();
-    texture.set_filter(FilterMode::Nearest);
 
     let mut bounds = [TileBounds::default(); Tile::COUNT];
 
-    let rows_per_col = (texture_size.x / width) as i32;
-    let cols = Tile::COUNT as i32 / rows_per_col as i32;
-
     // Using 1.0 as starting point for x, y because of the padding.
     // Same is also the case for side + 2.0 calculations.
 
-    let mut i = 0;
-    let mut x = 1.0f32;
-    let mut y = 1.0f32;
-    
-    for _ in 0..i32::max(cols-1, 0) {
-        for _ in 0..rows_per_col {
-            bounds[i] = TileBounds { begin: vec2(x, y) / texture_size, end: vec2(x+width, y+height) / texture_size };
-            i += 1;
-            x += width + 2.0;
-        }
-        y += height + 2.0;
-        x = 1.0;
-    }
+    let per_row = usize::max((texture_size.x / (width + 2.0)) as usize, 1);
 
-    let remaining_rows = Tile::COUNT - i;
-    for _ in 0..remaining_rows {
-        bounds[i] = TileBounds { begin: vec2(x, y) / texture_size, end: vec2(x+width, y+height) / texture_size };
-        i += 1;
-        x += width + 2.0;
-    }
+    for tile in Tile::VARIANTS {
+        let Some(index) = tile.def().tileset_index else { continue };
 
-    bounds[0] = TileBounds::default();
-    
-    TileSetAsset {
-        asset_id,
-        texture,
-        bounds,
+        let x = 1.0 + (index % per_row) as f32 * (width + 2.0);
+        let y = 1.0 + (index / per_row) as f32 * (height + 2.0);
+        bounds[tile as usize] = TileBounds { begin: vec2(x, y) / texture_size, end: vec2(x+width, y+height) / texture_size };
     }
+    bounds
 }
 
-impl Tile {
-    pub fn can_walk_through(&self) -> bool {
-        match self {
-            Tile::Empty => true,
-            
-            Tile::ERR => false,
-            Tile::UP => false,
-            Tile::DOWN => false,
-            Tile::GREEN => false,
-            Tile::RED => false,
-            
-            Tile::BackgroundStone => true,
-            Tile::BackgroundStoneLadder => true,
-            Tile::BackgroundStoneLadderSupport => true,
-            Tile::BackgroundStoneElevatorLeft => false,
-            Tile::BackgroundStoneElevatorMiddle => false,
-            Tile::BackgroundStoneElevatorRight => false,
-            
-            Tile::Stone => false,
-            Tile::StoneDig => false,
-            Tile::StoneDontDig => false,
-            Tile::BackgroundStoneDontDig => false,
-            Tile::BackgroundStoneLadderDontDig => false,
-            Tile::HardStone => false,
-            Tile::Barrier => false,
-            Tile::WorldBoundary => false,
-            
-            Tile::StoneCopperOre => false,
-            Tile::StoneIronOre => false,
-            Tile::StoneGoldOre => false,
-            Tile::StoneEmerald => false,
-            Tile::StoneRuby => false,
-            Tile::StoneSapphire => false,
-            
-            Tile::StoneBoundaryBottomRight => false,
-            Tile::StoneBoundaryBottom => false,
-            Tile::StoneBoundaryBottomLeft => false,
-            Tile::StoneBoundaryLeft => false,
-            Tile::StoneBoundaryTopLeft => false,
-            Tile::StoneBoundaryTop => false,
-            Tile::StoneBoundaryTopRight => false,
-            Tile::StoneBoundaryRight => false,
-            Tile::StoneBoundaryTopLeftInverse => false,
-            Tile::StoneBoundaryTopRightInverse => false,
-            Tile::StoneBoundaryBottomRightInverse => false,
-            Tile::StoneBoundaryBottomLeftInverse => false,
-        }
+// INFO: Per tile properties live in data/tiles.txt, embedded so wasm builds get them too.
+pub struct TileDef {
+    pub walk_through: bool,
+    pub mine: bool,
+    pub climb: bool,
+    pub air: bool,
+    pub durability: f32,
+    pub item_drop: ItemKind,
+    pub mine_results_tile: Tile,
+    pub tileset_index: Option<usize>,
+}
+
+const TILE_DEFS_SOURCE: &str = include_str!("../data/tiles.txt");
+
+static TILE_DEFS: LazyLock<[TileDef; Tile::COUNT]> = LazyLock::new(|| {
+    match parse_tile_defs(TILE_DEFS_SOURCE) {
+    | Ok(defs) => defs,
+    | Err(err) => panic!("data/tiles.txt {}", err),
     }
+});
+
+pub fn parse_tile_defs(source: &str) -> Result<[TileDef; Tile::COUNT], DataError> {
+    let mut defs: [Option<TileDef>; Tile::COUNT] = [const { None }; Tile::COUNT];
+    let mut last_line = 0;
+
+    for record in data::records(source) {
+        record.expect_fields(6)?;
+        last_line = record.line;
 
-    pub fn can_mine(&self) -> bool {
-        match self {
-            Tile::Empty => false,
-            
-            Tile::ERR => false,
-            Tile::UP => false,
-            Tile::DOWN => false,
-            Tile::GREEN => false,
-            Tile::RED => false,
-            
-            Tile::BackgroundStone => false,
-            Tile::BackgroundStoneLadder => false,
-            Tile::BackgroundStoneLadderSupport => false,
-            Tile::BackgroundStoneElevatorLeft => false,
-            Tile::BackgroundStoneElevatorMiddle => false,
-            Tile::BackgroundStoneElevatorRight => false,
-            
-            Tile::Stone => true,
-            Tile::StoneDig => true,
-            Tile::StoneDontDig => false,
-            Tile::BackgroundStoneDontDig => false,
-            Tile::BackgroundStoneLadderDontDig => false,
-            Tile::HardStone => true,
-            Tile::Barrier => false,
-            Tile::WorldBoundary => false,
-            
-            Tile::StoneCopperOre => true,
-            Tile::StoneIronOre => true,
-            Tile::StoneGoldOre => true,
-            Tile::StoneEmerald => true,
-            Tile::StoneRuby => true,
-            Tile::StoneSapphire => true,
-            
-            Tile::StoneBoundaryBottomRight => false,
-            Tile::StoneBoundaryBottom => false,
-            Tile::StoneBoundaryBottomLeft => false,
-            Tile::StoneBoundaryLeft => false,
-            Tile::StoneBoundaryTopLeft => false,
-            Tile::StoneBoundaryTop => false,
-            Tile::StoneBoundaryTopRight => false,
-            Tile::StoneBoundaryRight => false,
-            Tile::StoneBoundaryTopLeftInverse => false,
-            Tile::StoneBoundaryTopRightInverse => false,
-            Tile::StoneBoundaryBottomRightInverse => false,
-            Tile::StoneBoundaryBottomLeftInverse => false,
+        let tile = record.variant(0, &Tile::VARIANTS)?;
+        let [walk_through, mine, climb, air] = record.flags(1, ["walk", "mine", "climb", "air"])?;
+
+        let def = TileDef {
+            walk_through, mine, climb, air,
+            durability:        record.optional(2)?.unwrap_or(NOT_SO_VERY_BIG_NUMBER),
+            item_drop:         record.variant(3, &ItemKind::VARIANTS)?,
+            mine_results_tile: record.variant(4, &Tile::VARIANTS)?,
+            tileset_index:     record.optional(5)?,
+        };
+
+        if defs[tile as usize].replace(def).is_some() {
+            return Err(record.error(format!("{:?} is defined twice", tile)));
         }
     }
-    pub fn can_climb(&self) -> bool {
-        match self {
-            Tile::Empty => false,
-            
-            Tile::ERR => false,
-            Tile::UP => false,
-            Tile::DOWN => false,
-            Tile::GREEN => false,
-            Tile::RED => false,
-            
-            Tile::BackgroundStone => false,
-            Tile::BackgroundStoneLadder => true,
-            Tile::BackgroundStoneLadderSupport => true,
-            Tile::BackgroundStoneElevatorLeft => false,
-            Tile::BackgroundStoneElevatorMiddle => false,
-            Tile::BackgroundStoneElevatorRight => false,
-            
-            
-            Tile::Stone => false,
-            Tile::StoneDig => false,
-            Tile::StoneDontDig => false,
-            Tile::BackgroundStoneDontDig => false,
-            Tile::BackgroundStoneLadderDontDig => false,
-            Tile::HardStone => false,
-            Tile::Barrier => false,
-            Tile::WorldBoundary => false,
-            
-            Tile::StoneCopperOre => false,
-            Tile::StoneIronOre => false,
-            Tile::StoneGoldOre => false,
-            Tile::StoneEmerald => false,
-            Tile::StoneRuby => false,
-            Tile::StoneSapphire => false,
-            
-            Tile::StoneBoundaryBottomRight => false,
-            Tile::StoneBoundaryBottom => false,
-            Tile::StoneBoundaryBottomLeft => false,
-            Tile::StoneBoundaryLeft => false,
-            Tile::StoneBoundaryTopLeft => false,
-            Tile::StoneBoundaryTop => false,
-            Tile::StoneBoundaryTopRight => false,
-            Tile::StoneBoundaryRight => false,
-            Tile::StoneBoundaryTopLeftInverse => false,
-            Tile::StoneBoundaryTopRightInverse => false,
-            Tile::StoneBoundaryBottomRightInverse => false,
-            Tile::StoneBoundaryBottomLeftInverse => false,
-        }
+
+    let mut missing = Tile::VARIANTS.iter().filter(|tile| defs[**tile as usize].is_none());
+    if let Some(tile) = missing.next() {
+        return Err(DataError { line: last_line, message: format!("{:?} is never defined", tile) });
     }
-    pub fn is_air(&self) -> bool {
-        match self {
-            Tile::Empty => true,
-            
-            Tile::ERR => false,
-            Tile::UP => false,
-            Tile::DOWN => false,
-            Tile::GREEN => false,
-            Tile::RED => false,
-            
-            Tile::BackgroundStone => true,
-            Tile::BackgroundStoneLadder => true,
-            Tile::BackgroundStoneLadderSupport => true,
-            Tile::BackgroundStoneElevatorLeft => false,
-            Tile::BackgroundStoneElevatorMiddle => false,
-            Tile::BackgroundStoneElevatorRight => false,
-            
-            Tile::Stone => false,
-            Tile::StoneDig => false,
-            Tile::StoneDontDig => false,
-            Tile::BackgroundStoneDontDig => false,
-            Tile::BackgroundStoneLadderDontDig => false,
-            Tile::HardStone => false,
-            Tile::Barrier => false,
-            Tile::WorldBoundary => false,
-            
-            Tile::StoneCopperOre => false,
-            Tile::StoneIronOre => false,
-            Tile::StoneGoldOre => false,
-            Tile::StoneEmerald => false,
-            Tile::StoneRuby => false,
-            Tile::StoneSapphire => false,
-            
-            Tile::StoneBoundaryBottomRight => false,
-            Tile::StoneBoundaryBottom => false,
-            Tile::StoneBoundaryBottomLeft => false,
-            Tile::StoneBoundaryLeft => false,
-            Tile::StoneBoundaryTopLeft => false,
-            Tile::StoneBoundaryTop => false,
-            Tile::StoneBoundaryTopRight => false,
-            Tile::StoneBoundaryRight => false,
-            Tile::StoneBoundaryTopLeftInverse => false,
-            Tile::StoneBoundaryTopRightInverse => false,
-            Tile::StoneBoundaryBottomRightInverse => false,
-            Tile::StoneBoundaryBottomLeftInverse => false,
-        }
+
+    Ok(defs.map(|def| def.unwrap()))
+}
+
+impl Tile {
+    pub fn def(&self) -> &'static TileDef {
+        &TILE_DEFS[*self as usize]
     }
 
-    pub fn mine_results_tile(&self) -> Tile {
-        match self {
-            Tile::Empty => Tile::ERR,
-            
-            Tile::ERR => Tile::ERR,
-            Tile::UP => Tile::ERR,
-            Tile::DOWN => Tile::ERR,
-            Tile::GREEN => Tile::ERR,
-            Tile::RED => Tile::ERR,
-            
-            Tile::BackgroundStone => Tile::ERR,
-            Tile::BackgroundStoneLadder => Tile::ERR,
-            Tile::BackgroundStoneLadderSupport => Tile::ERR,
-            Tile::BackgroundStoneElevatorLeft => Tile::ERR,
-            Tile::BackgroundStoneElevatorMiddle => Tile::ERR,
-            Tile::BackgroundStoneElevatorRight => Tile::ERR,
-            
-            Tile::Stone => Tile::BackgroundStone,
-            Tile::StoneDig => Tile::BackgroundStone,
-            Tile::StoneDontDig => Tile::ERR,
-            Tile::BackgroundStoneDontDig => Tile::ERR,
-            Tile::BackgroundStoneLadderDontDig => Tile::ERR,
-            Tile::HardStone => Tile::BackgroundStone,
-            Tile::Barrier => Tile::ERR,
-            Tile::WorldBoundary => Tile::ERR,
-            
-            Tile::StoneCopperOre => Tile::BackgroundStone,
-            Tile::StoneIronOre => Tile::BackgroundStone,
-            Tile::StoneGoldOre => Tile::BackgroundStone,
-            Tile::StoneEmerald => Tile::BackgroundStone,
-            Tile::StoneRuby => Tile::BackgroundStone,
-            Tile::StoneSapphire => Tile::BackgroundStone,
-            
-            Tile::StoneBoundaryBottomRight => Tile::ERR,
-            Tile::StoneBoundaryBottom => Tile::ERR,
-            Tile::StoneBoundaryBottomLeft => Tile::ERR,
-            Tile::StoneBoundaryLeft => Tile::ERR,
-            Tile::StoneBoundaryTopLeft => Tile::ERR,
-            Tile::StoneBoundaryTop => Tile::ERR,
-            Tile::StoneBoundaryTopRight => Tile::ERR,
-            Tile::StoneBoundaryRight => Tile::ERR,
-            Tile::StoneBoundaryTopLeftInverse => Tile::ERR,
-            Tile::StoneBoundaryTopRightInverse => Tile::ERR,
-            Tile::StoneBoundaryBottomRightInverse => Tile::ERR,
-            Tile::StoneBoundaryBottomLeftInverse => Tile::ERR,
-        }
+    pub fn can_walk_through(&self) -> bool { self.def().walk_through }
+    pub fn can_mine(&self) -> bool { self.def().mine }
+    pub fn can_climb(&self) -> bool { self.def().climb }
+    pub fn is_air(&self) -> bool { self.def().air }
+    pub fn mine_results_tile(&self) -> Tile { self.def().mine_results_tile }
+    pub fn item_drop(&self) -> ItemKind { self.def().item_drop }
+    pub fn durability(&self) -> f32 { self.def().durability }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_registry_defines_every_tile() {
+        let defs = parse_tile_defs(TILE_DEFS_SOURCE).unwrap_or_else(|err| panic!("{}", err));
+
+        assert!(defs[Tile::Empty as usize].tileset_index.is_none());
+        assert!(Tile::BackgroundStoneLadder.can_climb() && Tile::BackgroundStoneLadder.can_walk_through());
+        assert_eq!(Tile::StoneGoldOre.item_drop(), ItemKind::GoldOre);
+        assert_eq!(Tile::Stone.mine_results_tile(), Tile::BackgroundStone);
+        assert_eq!(Tile::WorldBoundary.durability(), NOT_SO_VERY_BIG_NUMBER);
     }
 
-    pub fn item_drop(&self) -> ItemKind {
-        match self {
-            Tile::Empty => ItemKind::Air,
-            
-            Tile::ERR => ItemKind::Air,
-            Tile::UP => ItemKind::Air,
-            Tile::DOWN => ItemKind::Air,
-            Tile::GREEN => ItemKind::Air,
-            Tile::RED => ItemKind::Air,
-            
-            Tile::BackgroundStone => ItemKind::Air,
-            Tile::BackgroundStoneLadder => ItemKind::Air,
-            Tile::BackgroundStoneLadderSupport => ItemKind::Air,
-            Tile::BackgroundStoneElevatorLeft => ItemKind::Air,
-            Tile::BackgroundStoneElevatorMiddle => ItemKind::Air,
-            Tile::BackgroundStoneElevatorRight => ItemKind::Air,
-            
-            Tile::Stone => ItemKind::Air,
-            Tile::StoneDig => ItemKind::Air,
-            Tile::StoneDontDig => ItemKind::Air,
-            Tile::BackgroundStoneDontDig => ItemKind::Air,
-            Tile::BackgroundStoneLadderDontDig => ItemKind::Air,
-            Tile::HardStone => ItemKind::Air,
-            Tile::Barrier => ItemKind::Air,
-            Tile::WorldBoundary => ItemKind::Air,
-
-            Tile::StoneCopperOre => ItemKind::CopperOre,
-            Tile::StoneIronOre => ItemKind::IronOre,
-            Tile::StoneGoldOre => ItemKind::GoldOre,
-            Tile::StoneEmerald => ItemKind::RawEmerald,
-            Tile::StoneRuby => ItemKind::RawRuby,
-            Tile::StoneSapphire => ItemKind::RawSapphire,
-            
-            Tile::StoneBoundaryBottomRight => ItemKind::Air,
-            Tile::StoneBoundaryBottom => ItemKind::Air,
-            Tile::StoneBoundaryBottomLeft => ItemKind::Air,
-            Tile::StoneBoundaryLeft => ItemKind::Air,
-            Tile::StoneBoundaryTopLeft => ItemKind::Air,
-            Tile::StoneBoundaryTop => ItemKind::Air,
-            Tile::StoneBoundaryTopRight => ItemKind::Air,
-            Tile::StoneBoundaryRight => ItemKind::Air,
-            Tile::StoneBoundaryTopLeftInverse => ItemKind::Air,
-            Tile::StoneBoundaryTopRightInverse => ItemKind::Air,
-            Tile::StoneBoundaryBottomRightInverse => ItemKind::Air,
-            Tile::StoneBoundaryBottomLeftInverse => ItemKind::Air,
-        }
+    #[test]
+    fn registry_rejects_duplicate_and_missing_tiles() {
+        let duplicate = format!("{}\nStone mine 1.0 Air BackgroundStone 12\n", TILE_DEFS_SOURCE);
+        let err = parse_tile_defs(&duplicate).err().expect("duplicate should fail");
+        assert!(err.message.contains("Stone is defined twice"), "{}", err);
+
+        let missing: String = TILE_DEFS_SOURCE.lines()
+            .filter(|line| !line.trim_start().starts_with("HardStone "))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let err = parse_tile_defs(&missing).err().expect("missing should fail");
+        assert!(err.message.contains("HardStone is never defined"), "{}", err);
     }
 
-    pub fn durability(&self) -> f32 {
-        match self {
-            Tile::Empty => NOT_SO_VERY_BIG_NUMBER,
-            
-            Tile::ERR => NOT_SO_VERY_BIG_NUMBER,
-            Tile::UP => NOT_SO_VERY_BIG_NUMBER,
-            Tile::DOWN => NOT_SO_VERY_BIG_NUMBER,
-            Tile::GREEN => NOT_SO_VERY_BIG_NUMBER,
-            Tile::RED => NOT_SO_VERY_BIG_NUMBER,
-            
-            Tile::BackgroundStone => NOT_SO_VERY_BIG_NUMBER,
-            Tile::BackgroundStoneLadder => NOT_SO_VERY_BIG_NUMBER,
-            Tile::BackgroundStoneLadderSupport => NOT_SO_VERY_BIG_NUMBER,
-            Tile::BackgroundStoneElevatorLeft => NOT_SO_VERY_BIG_NUMBER,
-            Tile::BackgroundStoneElevatorMiddle => NOT_SO_VERY_BIG_NUMBER,
-            Tile::BackgroundStoneElevatorRight => NOT_SO_VERY_BIG_NUMBER,
-            
-            Tile::Stone => 1.2,
-            Tile::StoneDig => 0.5,
-            Tile::StoneDontDig => NOT_SO_VERY_BIG_NUMBER,
-            Tile::BackgroundStoneDontDig => NOT_SO_VERY_BIG_NUMBER,
-            Tile::BackgroundStoneLadderDontDig => NOT_SO_VERY_BIG_NUMBER,
-            Tile::HardStone => 60.0,
-            Tile::Barrier => NOT_SO_VERY_BIG_NUMBER,
-            Tile::WorldBoundary => NOT_SO_VERY_BIG_NUMBER,
-
-            Tile::StoneCopperOre => 1.4,
-            Tile::StoneIronOre => 1.8,
-            Tile::StoneGoldOre => 2.5,
-            Tile::StoneEmerald => 3.5,
-            Tile::StoneRuby => 3.5,
-            Tile::StoneSapphire => 3.5,
-            
-            Tile::StoneBoundaryBottomRight => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryBottom => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryBottomLeft => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryLeft => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryTopLeft => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryTop => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryTopRight => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryRight => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryTopLeftInverse => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryTopRightInverse => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryBottomRightInverse => NOT_SO_VERY_BIG_NUMBER,
-            Tile::StoneBoundaryBottomLeftInverse => NOT_SO_VERY_BIG_NUMBER,
-        }
+    #[test]
+    fn registry_reports_bad_fields_with_line() {
+        let err = parse_tile_defs("\n# comment\nStone mine,fly 1.2 Air BackgroundStone 12\n").err().unwrap();
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("fly"));
+
+        let err = parse_tile_defs("Stone mine 1.2 Air Bedrock 12").err().unwrap();
+        assert!(err.message.contains("Bedrock"));
     }
 }
@@ -2,15 +2,117 @@ use std::hash::{DefaultHasher, Hash, Hasher};
 
 use macroquad::prelude::*;
 
-use crate::sprite::{draw_ui_three_patch, SpriteAsset};
+use crate::sprite::{SpriteAsset, SpriteBatch};
+use crate::viewport::{ Viewport };
 
 pub struct UIState {
+    // INFO: Copy of Game::viewport for mapping the mouse. mouse_div is game pixels per UI unit, screens drawn at
+    // UI scale divide it by 4 while they are drawn.
+    pub viewport: Viewport,
     pub mouse_div: Vec2,
     pub last_clicked_button_hash: Option<u64>,
+    pub focused_text_input_hash: Option<u64>,
+
+    // INFO: Focus is for playing without a mouse. Buttons register themselves while drawn, focus moves between
+    // the ones drawn last frame. Moving the mouse drops focus so there aren't two highlighted buttons.
+    pub nav: UINav,
+    pub focused_button_hash: Option<u64>,
+    pub buttons: Vec<(u64, Rect)>,
+    pub prev_buttons: Vec<(u64, Rect)>,
+    pub last_mouse_position: Vec2,
+}
+
+impl Default for UIState {
+    fn default() -> UIState {
+        UIState {
+            viewport: Viewport::default(),
+            mouse_div: vec2(1.0, 1.0),
+            last_clicked_button_hash: None,
+            focused_text_input_hash: None,
+            nav: UINav::default(),
+            focused_button_hash: None,
+            buttons: Vec::with_capacity(32),
+            prev_buttons: Vec::with_capacity(32),
+            last_mouse_position: vec2(0.0, 0.0),
+        }
+    }
+}
+
+// INFO: Menu navigation for this frame, every field is true only on the frame it is pressed.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct UINav {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+}
+
+pub fn ui_mouse_position(state: &UIState) -> Vec2 {
+    state.viewport.screen_to_game(Vec2::from(mouse_position())) / state.mouse_div
+}
+
+pub fn ui_begin_frame(state: &mut UIState, nav: UINav, mouse_position: Vec2) {
+    std::mem::swap(&mut state.buttons, &mut state.prev_buttons);
+    state.buttons.clear();
+    state.nav = nav;
+
+    if mouse_position != state.last_mouse_position {
+        state.last_mouse_position = mouse_position;
+        state.focused_button_hash = None;
+    }
+
+    let direction;
+    if nav.up {
+        direction = vec2(0.0, -1.0);
+    } else if nav.down {
+        direction = vec2(0.0, 1.0);
+    } else if nav.left {
+        direction = vec2(-1.0, 0.0);
+    } else if nav.right {
+        direction = vec2(1.0, 0.0);
+    } else {
+        return;
+    }
+
+    state.focused_button_hash = next_focus(&state.prev_buttons, state.focused_button_hash, direction);
 }
 
+// INFO: Nothing focused (or the focused button is gone) picks the first button drawn. Otherwise the closest
+// button in that direction, being off to the side costs twice as much as being far away, so grids move
+// along rows and columns. Nothing in that direction keeps focus where it is.
+fn next_focus(buttons: &[(u64, Rect)], focused: Option<u64>, direction: Vec2) -> Option<u64> {
+    let Some(from) = focused.and_then(|hash| buttons.iter().find(|(other, _)| *other == hash)) else {
+        return buttons.first().map(|(hash, _)| *hash);
+    };
+    let from_center = from.1.center();
+
+    let mut best = None;
+    let mut best_score = f32::MAX;
+
+    for (hash, rect) in buttons {
+        let delta = rect.center() - from_center;
+        let along = delta.dot(direction);
+        if along <= 0.0 { continue; }
+
+        let across = (delta - direction*along).length();
+        let score = along + across*2.0;
+        if score < best_score {
+            best_score = score;
+            best = Some(*hash);
+        }
+    }
+
+    best.or(focused)
+}
+
+// INFO: "label##id" shows only the label and identifies the button by id alone, so buttons with the same
+// label can coexist and a button keeps focus when its label changes.
+#[allow(clippy::too_many_arguments)]
 pub fn ui_button(
     state: &mut UIState,
+    sprite_batch: &mut SpriteBatch,
     name: &str,
     position: Vec2,
     width: f32,
@@ -18,19 +120,27 @@ pub fn ui_button(
     out_pressing: Option<&mut bool>,
     sprites: &[[SpriteAsset; 3]; 3],
 ) -> bool {
+    let (label, id) = name.split_once("##").unwrap_or((name, name));
+
     let mut hasher = DefaultHasher::new();
-    name.hash(&mut hasher);
+    id.hash(&mut hasher);
     let hash = hasher.finish();
 
     // HACK: again...
     let height = sprites[0][0].frames[0].h;
     let rect = Rect { x: position.x, y: position.y, w: width, h: height };
+
+    // INFO: Disabled buttons aren't registered, so focus skips them.
+    let focused = !disabled && state.focused_button_hash == Some(hash);
+    if !disabled {
+        state.buttons.push((hash, rect));
+    }
     
     #[allow(unused_assignments)]
     let mut pressed = false;
     let mut pressing = false;
     
-    if !disabled && rect.contains(Vec2::from(mouse_position()) / state.mouse_div) {
+    if !disabled && rect.contains(ui_mouse_position(state)) {
         if is_mouse_button_pressed(MouseButton::Left) {
             if state.last_clicked_button_hash.is_none() {
                 state.last_clicked_button_hash = Some(hash);
@@ -39,9 +149,9 @@ pub fn ui_button(
 
         if is_mouse_button_down(MouseButton::Left) && state.last_clicked_button_hash == Some(hash) {
             pressing = true;
-            draw_ui_three_patch(position, width, &sprites[1]);
+            sprite_batch.draw_ui_three_patch(position, width, &sprites[1]);
         } else {
-            draw_ui_three_patch(position, width, &sprites[0]);
+            sprite_batch.draw_ui_three_patch(position, width, &sprites[0]);
         }
 
         if is_mouse_button_released(MouseButton::Left) {
@@ -52,14 +162,20 @@ pub fn ui_button(
             pressed = false;
         }
     } else if disabled {
-        draw_ui_three_patch(position, width, &sprites[2]);
+        sprite_batch.draw_ui_three_patch(position, width, &sprites[2]);
         pressed = false;
     } else {
-        draw_ui_three_patch(position, width, &sprites[0]);
+        sprite_batch.draw_ui_three_patch(position, width, &sprites[0]);
         pressed = false;
     }
+    sprite_batch.flush();
+
+    if focused {
+        draw_rectangle_lines(rect.x-1.0, rect.y-1.0, rect.w+2.0, rect.h+2.0, 2.0, Color::from_hex(0xc7cfcc));
+        pressed |= state.nav.confirm;
+    }
     
-    let text_size = measure_text(name, None, 16, 1.0);
+    let text_size = measure_text(label, None, 16, 1.0);
     let text_size = vec2(text_size.width, text_size.height);
 
     let x = rect.x + (rect.w-text_size.x)/2.0;
@@ -73,7 +189,136 @@ pub fn ui_button(
         *out_pressing = pressing;
     }
     
-    draw_text(name, x, y, 16.0, WHITE);
+    draw_text(label, x, y, 16.0, WHITE);
 
     pressed
 }
+
+#[allow(clippy::too_many_arguments)]
+pub fn ui_digit_input(
+    state: &mut UIState,
+    sprite_batch: &mut SpriteBatch,
+    name: &str,
+    text: &mut String,
+    max_length: usize,
+    position: Vec2,
+    width: f32,
+    sprites: &[[SpriteAsset; 3]; 3],
+) -> bool {
+    ui_text_input(state, sprite_batch, name, text, max_length, |char| char.is_ascii_digit(), position, width, sprites)
+}
+
+fn text_input_hash(name: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish()
+}
+
+// INFO: For inputs that should take typing as soon as they show up, e.g. renaming.
+pub fn ui_focus_text_input(state: &mut UIState, name: &str) {
+    state.focused_text_input_hash = Some(text_input_hash(name));
+}
+
+pub fn ui_is_text_input_focused(state: &UIState, name: &str) -> bool {
+    state.focused_text_input_hash == Some(text_input_hash(name))
+}
+
+// INFO: Single line input that only accepts characters passing accept. Clicking it focuses it, clicking
+// anywhere else or pressing enter releases focus. Returns true on the frame text changes.
+#[allow(clippy::too_many_arguments)]
+pub fn ui_text_input(
+    state: &mut UIState,
+    sprite_batch: &mut SpriteBatch,
+    name: &str,
+    text: &mut String,
+    max_length: usize,
+    accept: fn(char) -> bool,
+    position: Vec2,
+    width: f32,
+    sprites: &[[SpriteAsset; 3]; 3],
+) -> bool {
+    let hash = text_input_hash(name);
+
+    // HACK: again...
+    let height = sprites[0][0].frames[0].h;
+    let rect = Rect { x: position.x, y: position.y, w: width, h: height };
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        if rect.contains(ui_mouse_position(state)) {
+            state.focused_text_input_hash = Some(hash);
+        } else if state.focused_text_input_hash == Some(hash) {
+            state.focused_text_input_hash = None;
+        }
+    }
+
+    let focused = state.focused_text_input_hash == Some(hash);
+    let mut changed = false;
+
+    if focused {
+        while let Some(char) = get_char_pressed() {
+            if accept(char) && text.len() < max_length {
+                text.push(char);
+                changed = true;
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) && text.pop().is_some() {
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            state.focused_text_input_hash = None;
+        }
+    }
+
+    if focused {
+        sprite_batch.draw_ui_three_patch(position, width, &sprites[1]);
+    } else {
+        sprite_batch.draw_ui_three_patch(position, width, &sprites[0]);
+    }
+
+    sprite_batch.flush();
+
+    let label = match focused {
+    | true  => [name, ": ", text, "_"].concat(),
+    | false => [name, ": ", text].concat(),
+    };
+
+    let text_size = measure_text(&label, None, 16, 1.0);
+
+    let x = rect.x + (rect.w-text_size.width)/2.0;
+    let y = rect.y + height/2.0;
+
+    draw_text(&label, x, y, 16.0, WHITE);
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Vec<(u64, Rect)> {
+        vec![
+            (1, Rect::new(  0.0,  0.0, 100.0, 20.0)), (2, Rect::new(110.0,  0.0, 100.0, 20.0)),
+            (3, Rect::new(  0.0, 30.0, 100.0, 20.0)), (4, Rect::new(110.0, 30.0, 100.0, 20.0)),
+            (5, Rect::new(  0.0, 60.0, 210.0, 20.0)),
+        ]
+    }
+
+    #[test]
+    fn focus_starts_at_first_button() {
+        assert_eq!(next_focus(&grid(), None, vec2(0.0, 1.0)), Some(1));
+        assert_eq!(next_focus(&grid(), Some(99), vec2(0.0, 1.0)), Some(1));
+        assert_eq!(next_focus(&[], None, vec2(0.0, 1.0)), None);
+    }
+
+    #[test]
+    fn focus_moves_along_rows_and_columns() {
+        let buttons = grid();
+        assert_eq!(next_focus(&buttons, Some(1), vec2( 1.0, 0.0)), Some(2));
+        assert_eq!(next_focus(&buttons, Some(2), vec2( 0.0, 1.0)), Some(4));
+        assert_eq!(next_focus(&buttons, Some(4), vec2(-1.0, 0.0)), Some(3));
+        assert_eq!(next_focus(&buttons, Some(3), vec2( 0.0, 1.0)), Some(5));
+        assert_eq!(next_focus(&buttons, Some(5), vec2( 0.0, 1.0)), Some(5));
+        assert_eq!(next_focus(&buttons, Some(1), vec2( 0.0,-1.0)), Some(1));
+    }
+}
@@ -1,29 +1,80 @@
 use crate::prelude::*;
 
-const DEV_MODE: bool = false;
+use crate::bindings::{ self, Capture };
+use crate::fog;
+use crate::input;
+use crate::lighting;
+use crate::music;
+use crate::particles;
+use crate::ui::{ ui_begin_frame, UINav };
+use crate::viewport;
+
+pub const DEV_MODE: bool = false;
 
 pub fn update(game: &mut Game) {
+    poll_window_and_input(game);
+    simulate(game, get_frame_time());
+}
+
+// INFO: Everything update needs from the window lives here, simulate only reads game.input_actions. That is
+// what lets tests step a Game with scripted input and without a window.
+pub fn poll_window_and_input(game: &mut Game) {
     // pre update :::
-    game.window_to_draw_size = vec2(screen_width(), screen_height()) / vec2(GAME_WIDTH_F32, GAME_HEIGHT_F32);
-    game.ui_state.mouse_div = game.window_to_draw_size;
+    game.viewport = viewport::fit_viewport(vec2(screen_width(), screen_height()), game.settings.smooth_scaling);
+    game.ui_state.viewport = game.viewport;
+    game.ui_state.mouse_div = Vec2::ONE;
+
+    // input map :::
+    let ui_active = game.main_ui_state != MainUIState::InGame || game.ui_show_statue;
+    let input = &mut game.input;
+    let bindings = &game.bindings;
+    input::poll_gamepads(input);
+
+    let pad_actions = input::map_pad_actions(bindings, &input.pad, &input.prev_pad, ui_active);
+    game.input_actions = input::map_key_actions(bindings).or(pad_actions);
+    game.input_actions.toggle_dev_mode &= DEV_MODE;
+
+    let text_input_focused = game.ui_state.focused_text_input_hash.is_some();
+    let pad_nav = input::map_pad_nav(&input.pad, &input.prev_pad, ui_active);
+    let mut nav = input::map_key_nav(text_input_focused).or(pad_nav);
+
+    // rebinding :::
+    // INFO: Whatever is pressed while a slot waits belongs to it, neither the menu nor the game sees it.
+    if let Some((action, slot)) = game.controls_rebinding {
+        match bindings::capture_binding(slot, &game.input) {
+        | Capture::Waiting => {},
+        | Capture::Cancel => game.controls_rebinding = None,
+        | Capture::Bind(value) => {
+            game.bindings.set(action, slot, value);
+            bindings::save_bindings(&game.bindings);
+            game.controls_rebinding = None;
+        },
+        }
+        nav = UINav::default();
+        game.input_actions = InputActions::default();
+    }
+
+    ui_begin_frame(&mut game.ui_state, nav, mouse_position().into());
+}
+
+pub fn simulate(game: &mut Game, frame_time: f32) {
+    // pause :::
+    // INFO: Nothing moves while paused, Escape (or Start/East) from the pause menu resumes. Settings has its
+    // own Back, that is handled with the menu in render.
+    if game.main_ui_state.is_paused() {
+        if game.main_ui_state == MainUIState::Paused && game.input_actions.escape {
+            game.main_ui_state = MainUIState::InGame;
+            game.sound_player.silence_loops(false);
+        }
+        game.sound_player.tick(f32::clamp(frame_time, 1.0/1000.0, 1.0/5.0));
+        return;
+    }
 
     // reset per-frame :::
     game.derived = DerivedState::default();
-    
-    // input map :::
-    game.input_actions = InputActions {
-        move_left      : is_key_down(KeyCode::A) || is_key_down(KeyCode::Left),
-        move_right     : is_key_down(KeyCode::D) || is_key_down(KeyCode::Right),
-        move_up        : is_key_down(KeyCode::W) || is_key_down(KeyCode::Up),
-        move_down      : is_key_down(KeyCode::S) || is_key_down(KeyCode::Down),
-
-        interact       : is_key_pressed(KeyCode::E),
-        escape         : is_key_pressed(KeyCode::Escape),
-        toggle_dev_mode: is_key_pressed(KeyCode::Tab) && DEV_MODE,
-    };
 
     // frame borrows :::
-    let dt = f32::clamp(get_frame_time(), 1.0/1000.0, 1.0/5.0);
+    let dt = f32::clamp(frame_time, 1.0/1000.0, 1.0/5.0);
     let assets = &game.assets;
 
     let player = &mut game.player;
@@ -36,13 +87,15 @@ pub fn update(game: &mut Game) {
     let demolisher = &mut game.demolisher;
     
     let world = &mut game.world;
+    let layout = world.layout;
     let visible_chunks = &mut game.visible_chunks;
     let derived = &mut game.derived;
     let input_actions = &game.input_actions;
     let upgrades = &mut game.upgrades;
     let sound_player = &mut game.sound_player;
 
-    sound_player.tick_music();
+    sound_player.listener.pos = player.trans.pos;
+    sound_player.tick(dt);
     
     // reset late derived:::
     let late_derived = &game.late_derived;
@@ -73,7 +126,8 @@ pub fn update(game: &mut Game) {
         
 
     // frame start derived :::
-    derived.player_at_overworld = player.trans.pos.y >= WORLD_SPAWN_F32.y*TILE_SIDE_F32-0.5;
+    derived.player_at_overworld = player.trans.pos.y >= layout.spawn_f32.y*TILE_SIDE_F32-0.5;
+    derived.player_below_barrier = player.trans.pos.y < layout.barrier_pos.y as f32*TILE_SIDE_F32;
 
     derived.player_mining_speed = match upgrades.mining.kind {
         MiningUpgradeKind::DefaultPickaxe => 1.0,
@@ -136,13 +190,20 @@ pub fn update(game: &mut Game) {
 
     derived.player_can_place_ladder = !derived.player_has_jetpack;
     derived.player_can_use_jetpack = derived.player_has_jetpack &&
-        player.trans.pos.y <= ELEVATOR_PLATFORM_END.y;
+        player.trans.pos.y <= layout.elevator_platform_end.y;
 
     derived.ui_main_menu = match game.main_ui_state {
         MainUIState::MainMenu => true,
         MainUIState::MainMenuCredits => true,
+        MainUIState::MainMenuControls => true,
+        MainUIState::MainMenuSettings => true,
+        MainUIState::MainMenuSaves => true,
         MainUIState::InGame => false,
+        MainUIState::Paused => false,
+        MainUIState::PausedSettings => false,
     };
+
+    sound_player.music.target = music::music_layer(derived, game.demolisher_started);
     
     game.total_time += dt;
     
@@ -376,8 +437,16 @@ pub fn update(game: &mut Game) {
         *durability += dt * 3.0 * derived.player_mining_speed * derived.player_hit_str;
 
         derived.player_mining = true;        
+        derived.player_mining_pos = tile.world_pos();
 
         if *durability > tile.kind.durability() {
+            game.camera.add_trauma(0.15);
+
+            let tile_rect = Rect::new(tile.world_pos().x, tile.world_pos().y, TILE_SIDE_F32, TILE_SIDE_F32);
+            game.particles.burst_in(&particles::DEBRIS, tile_rect, 10, None);
+            if let Some(glow) = lighting::gem_glow(tile.kind) {
+                game.particles.burst_in(&particles::DEBRIS, tile_rect, 4, Some(Color::new(glow.x, glow.y, glow.z, 1.0)));
+            }
             if tile.kind.item_drop() != ItemKind::Air {
                 player_added_to_bags.push(tile.kind.item_drop());
             }
@@ -419,7 +488,7 @@ pub fn update(game: &mut Game) {
         let tile_one_down = tile.down(1);
         let tile_two_down = tile.down(2);
 
-        if tile.pos.y >= WORLD_SPAWN_I32.y { break 'lay_ladder; }
+        if tile.pos.y >= layout.spawn_i32.y { break 'lay_ladder; }
 
         if  player_movement.y > 0 && tile.kind.is_air() && tile_one_up.kind.is_air() &&
             player.trans.pos.y - tile.world_pos().y < 1.0
@@ -446,10 +515,11 @@ pub fn update(game: &mut Game) {
     
     // sound effects :::   
     // NOTE: I hate this.
+    let pickaxe_pos = if derived.player_mining { derived.player_mining_pos } else { player.trans.pos };
             
     if !derived.player_can_use_jetpack && player.anim.is( &assets.player_hit ) && game.sfx_pickaxe < player.anim.repeated {
         game.sfx_pickaxe = player.anim.repeated;
-        sound_player.play_sound(&assets.sfx_pickaxe, 0.2, false);
+        sound_player.play_sound_at(Bus::Sfx, &assets.sfx_pickaxe, pickaxe_pos, false);
     }
     
     if !derived.player_can_use_jetpack && player.anim.is_not( &assets.player_hit ) && game.sfx_pickaxe > 0 {
@@ -458,7 +528,7 @@ pub fn update(game: &mut Game) {
     
     if derived.player_can_use_jetpack && player.anim.is( &assets.player_jetpack_hit ) && game.sfx_pickaxe < player.anim.repeated {
         game.sfx_pickaxe = player.anim.repeated;
-        sound_player.play_sound(&assets.sfx_pickaxe, 0.2, false);
+        sound_player.play_sound_at(Bus::Sfx, &assets.sfx_pickaxe, pickaxe_pos, false);
     }
     
     if derived.player_can_use_jetpack && player.anim.is_not( &assets.player_jetpack_hit ) && game.sfx_pickaxe > 0 {
@@ -470,7 +540,7 @@ pub fn update(game: &mut Game) {
         if player.anim.is( &assets.player_idle ) {
             player.anim = assets.player_walk.derive_anim();
         } else if player.anim.is( &assets.player_jetpack_idle ) {
-            sound_player.play_sound(&assets.sfx_jetpack, 0.1, true);
+            sound_player.play_sound(Bus::Sfx, &assets.sfx_jetpack, true);
             player.anim = assets.player_jetpack_move.derive_anim();
         }
     }
@@ -600,22 +670,22 @@ pub fn update(game: &mut Game) {
         let direction = if elevator_platform.down_or_up { -520.0 } else { 520.0 };
         elevator_platform.trans.pos.y -= direction*dt;
                 
-        if elevator_platform.trans.pos.y <= ELEVATOR_PLATFORM_END_STOP.y {
-            elevator_platform.trans.pos.y = ELEVATOR_PLATFORM_END_STOP.y;
+        if elevator_platform.trans.pos.y <= layout.elevator_platform_end_stop.y {
+            elevator_platform.trans.pos.y = layout.elevator_platform_end_stop.y;
             elevator_platform.player_inside_for = 0.0;
             elevator_platform.anim = assets.elevator_platform_idle.derive_anim();
             elevator_platform.down_or_up = !elevator_platform.down_or_up;
         }
         
-        if elevator_platform.trans.pos.y >= ELEVATOR_PLATFORM_START.y {
-            elevator_platform.trans.pos.y = ELEVATOR_PLATFORM_START.y;
+        if elevator_platform.trans.pos.y >= layout.elevator_platform_start.y {
+            elevator_platform.trans.pos.y = layout.elevator_platform_start.y;
             elevator_platform.player_inside_for = 0.0;
             elevator_platform.anim = assets.elevator_platform_idle.derive_anim();
             elevator_platform.down_or_up = !elevator_platform.down_or_up;
         }
 
         if elevator_platform.anim.is_not(&assets.elevator_platform_moving) && elevator_platform.player_inside_for > 0.0 {
-            sound_player.play_sound(&assets.sfx_elevator, 0.2, false);
+            sound_player.play_sound_at(Bus::Ambience, &assets.sfx_elevator, elevator_platform.trans.pos, false);
             elevator_platform.anim = assets.elevator_platform_moving.derive_anim();
         }
         
@@ -661,7 +731,7 @@ pub fn update(game: &mut Game) {
                     offset: vec2(0.0, 0.0),
                 };
                 minecart.carrying.push(Item { trans, kind });
-                sound_player.play_sound(&assets.sfx_minecart_transfer, 0.2, false);
+                sound_player.play_sound_at(Bus::Sfx, &assets.sfx_minecart_transfer, minecart.trans.pos, false);
                 minecart.cooldown = 2.0;
             }
         }
@@ -671,7 +741,7 @@ pub fn update(game: &mut Game) {
     if minecart.movement == MinecartMovement::Idle && minecart.cooldown <= 0.1 && minecart.carrying.length > 0 {
         minecart.movement = MinecartMovement::Forwards;
         minecart.anim = assets.minecart_moving.derive_anim();
-        sound_player.play_sound(&assets.sfx_minecart_moving, 0.2, true);
+        sound_player.play_sound_at(Bus::Ambience, &assets.sfx_minecart_moving, minecart.trans.pos, true);
     }
 
     minecart.cooldown = f32::max(minecart.cooldown-dt, 0.0);
@@ -681,8 +751,8 @@ pub fn update(game: &mut Game) {
         let mut new_pos = minecart.trans.pos + vec2(minecart.speed, 0.0) * dt;
         let mut new_rotation = 0.0;
 
-        if new_pos.x >= MINECART_STRAIGHT_END.x {
-            new_pos = minecart.trans.pos + (MINECART_DIAGONAL_END-MINECART_STRAIGHT_END).normalize()
+        if new_pos.x >= layout.minecart_straight_end.x {
+            new_pos = minecart.trans.pos + (layout.minecart_diagonal_end-layout.minecart_straight_end).normalize()
                 * minecart.speed * dt;
             new_rotation = 32.0f32.to_radians();
         }
@@ -690,20 +760,20 @@ pub fn update(game: &mut Game) {
         minecart.rotation = new_rotation;
         minecart.speed = f32::min(minecart.speed + dt*55.0, 180.0);
         
-        if new_pos.x >= MINECART_DIAGONAL_END.x {
+        if new_pos.x >= layout.minecart_diagonal_end.x {
             minecart.movement = MinecartMovement::Backwards;
         }
     }
     
     // minecart moving backwards :::
     if minecart.movement == MinecartMovement::Backwards {
-        let mut new_pos = minecart.trans.pos + (MINECART_STRAIGHT_END-MINECART_DIAGONAL_END).normalize()
+        let mut new_pos = minecart.trans.pos + (layout.minecart_straight_end-layout.minecart_diagonal_end).normalize()
             * minecart.speed * dt;
         let mut new_rotation = 22.0f32.to_radians();
         
-        if new_pos.x <= MINECART_STRAIGHT_END.x {
+        if new_pos.x <= layout.minecart_straight_end.x {
             new_pos = minecart.trans.pos - vec2(minecart.speed, 0.0) * dt;
-            new_pos.y = MINECART_STRAIGHT_END.y;
+            new_pos.y = layout.minecart_straight_end.y;
             new_rotation = 0.0;
         }
         minecart.trans.pos = new_pos;
@@ -711,10 +781,11 @@ pub fn update(game: &mut Game) {
         
         minecart.speed = f32::min(minecart.speed + dt*55.0, 250.0);
 
-        if new_pos.x <= MINECART_START.x {
+        if new_pos.x <= layout.minecart_start.x {
             sound_player.stop_sound(&assets.sfx_minecart_moving);
-            sound_player.play_sound(&assets.sfx_minecart_throw, 0.2, false);
-            minecart.trans.pos.x = MINECART_START.x;
+            sound_player.play_sound_at(Bus::Sfx, &assets.sfx_minecart_throw, minecart.trans.pos, false);
+            game.camera.add_trauma(0.35 * sound_player.listener.attenuation(minecart.trans.pos));
+            minecart.trans.pos.x = layout.minecart_start.x;
             minecart.movement = MinecartMovement::Idle;
             minecart.anim = assets.minecart_idle.derive_anim();
             minecart.speed = 50.0;
@@ -792,23 +863,30 @@ pub fn update(game: &mut Game) {
             }
         }
     }
+    sound_player.move_sound(&assets.sfx_minecart_moving, minecart.trans.pos);
     
     // statue interact :::
     if player.trans.collider().intersects(statue.trans.collider()) {
         derived.ui_show_statue_key = true;
 
-        if input_actions.interact {
+        if input_actions.interact && !game.ui_show_map {
             game.ui_show_statue = !game.ui_show_statue;
         }
     }
 
+    // toggle map :::
+    if input_actions.toggle_map && !derived.ui_main_menu && !game.ui_show_statue && !game.demolisher_started {
+        game.ui_show_map = !game.ui_show_map;
+    }
+
     // demolisher interact :::
     if !game.demolisher_started && game.demolisher_spawned && player.trans.collider().intersects(demolisher.trans.collider()) {
         derived.ui_show_demolisher_key = true;
 
         if input_actions.interact {
             game.demolisher_started = true;
-            sound_player.play_sound(&assets.sfx_demolisher, 0.2, false);
+            sound_player.play_sound_at(Bus::Sfx, &assets.sfx_demolisher, demolisher.trans.pos, false);
+            game.camera.add_trauma(0.3);
             demolisher.anim = assets.demolisher_working_0.derive_anim();
         }
     }
@@ -816,7 +894,7 @@ pub fn update(game: &mut Game) {
     
     // sell excess ores if player has(bought) jetpack :::
     if derived.player_has_jetpack && player.carrying.length > derived.player_bag_carry_capacity {
-        sound_player.play_sound(&assets.sfx_minecart_transfer, 0.2, false);
+        sound_player.play_sound(Bus::Sfx, &assets.sfx_minecart_transfer, false);
         minecart.cooldown = 1.0;
 
         for _ in 0..(player.carrying.length-derived.player_bag_carry_capacity) {
@@ -832,6 +910,7 @@ pub fn update(game: &mut Game) {
     
     // demolisher heat :::
     if game.demolisher_started && demolisher.stage < 5 {
+        game.camera.hold_trauma(0.08 * demolisher.stage as f32);
         demolisher.stage_tick += dt;
         demolisher.anim.modifier += dt/4.0;
         if demolisher.stage_tick >= 2.0 {
@@ -861,6 +940,8 @@ pub fn update(game: &mut Game) {
         world_commands.set_tile_area(tile_start.pos, tile_end.pos-tile_start.pos+ivec2(1, 1), Tile::BackgroundStone);
         demolisher.prev_pos = demolisher.trans.pos;
         player.trans.pos.x = demolisher.trans.pos.x;
+        game.camera.hold_trauma(0.7);
+        demolisher.dust.emit(&mut game.particles, demolisher.trans.pos + vec2(demolisher.trans.size.x, 2.0), dt);
         
         demolisher.stage_tick += dt;
         if demolisher.stage_tick >= 5.0 {
@@ -872,6 +953,15 @@ pub fn update(game: &mut Game) {
         sound_player.stop_sound(&assets.sfx_demolisher);
     }
 
+    // particles :::
+    if derived.player_can_use_jetpack && player.jetpack_fuel > 0.0 {
+        let side = if player.sprite.flip_x { 3.0 } else { -3.0 };
+        player.exhaust.emit(&mut game.particles, player.trans.pos + vec2(side, 5.0), dt);
+    }
+
+    // camera :::
+    game.camera.tick(player.trans.pos, player_movement_f32, &layout, dt);
+
     // collect coins :::
     let mut coins_to_remove = Vec::new_in(&game.bump);
 
@@ -880,8 +970,8 @@ pub fn update(game: &mut Game) {
             game.money += coin.amount;
             coins_to_remove.push(i);
         }
-        if coin.trans.pos.y <= WORLD_SPAWN_F32.y * TILE_SIDE as f32 {
-            coin.trans.pos.y = WORLD_SPAWN_F32.y * TILE_SIDE as f32;
+        if coin.trans.pos.y <= layout.spawn_f32.y * TILE_SIDE as f32 {
+            coin.trans.pos.y = layout.spawn_f32.y * TILE_SIDE as f32;
             coin.velocity = vec2(0.0, 0.0);
         } else {
             coin.trans.pos += coin.velocity * dt;
@@ -891,7 +981,7 @@ pub fn update(game: &mut Game) {
     for i in coins_to_remove {
         // INFO: not using swap_remove because draw order changes and it looks glitchy
         game.coins.remove(i);
-        sound_player.play_sound(&assets.sfx_coin, 0.15, false);
+        sound_player.play_sound(Bus::Sfx, &assets.sfx_coin, false);
     }
 
     // tick dropped items :::
@@ -930,7 +1020,7 @@ pub fn update(game: &mut Game) {
     // update visible chunks :::
     {
         let player_chunk = world_pos_to_chunk_pos(player.trans.pos);
-        *visible_chunks = World::query_chunks_around_chunk_pos(&game.bump, player_chunk, 1).to_vec();
+        *visible_chunks = World::query_chunks_around_chunk_pos(&game.bump, &layout, player_chunk, 1).to_vec();
     }
     
     // update tile durability :::
@@ -974,6 +1064,15 @@ pub fn update(game: &mut Game) {
                 game.ui_show_statue = false;
                 break 'escape;
             }
+            if game.ui_show_map {
+                game.ui_show_map = false;
+                break 'escape;
+            }
+            if game.main_ui_state == MainUIState::InGame {
+                game.main_ui_state = MainUIState::Paused;
+                sound_player.silence_loops(true);
+                break 'escape;
+            }
         }
     }
     
@@ -981,24 +1080,24 @@ pub fn update(game: &mut Game) {
     if derived.player_has_jetpack && !game.elevator_spawned {
         game.elevator_spawned = true;   
         world_commands.set_tile_area(
-            world_pos_to_tile_pos(ELEVATOR_PLATFORM_END)+ivec2(0, 1),
-            world_pos_to_tile_pos(ELEVATOR_PLATFORM_START-ELEVATOR_PLATFORM_END)+ivec2(1, 0),
+            world_pos_to_tile_pos(layout.elevator_platform_end)+ivec2(0, 1),
+            world_pos_to_tile_pos(layout.elevator_platform_start-layout.elevator_platform_end)+ivec2(1, 0),
             Tile::BackgroundStoneElevatorLeft,
         );
         world_commands.set_tile_area(
-            world_pos_to_tile_pos(ELEVATOR_PLATFORM_END)+ivec2(1, 1),
-            world_pos_to_tile_pos(ELEVATOR_PLATFORM_START-ELEVATOR_PLATFORM_END)+ivec2(1, 0),
+            world_pos_to_tile_pos(layout.elevator_platform_end)+ivec2(1, 1),
+            world_pos_to_tile_pos(layout.elevator_platform_start-layout.elevator_platform_end)+ivec2(1, 0),
             Tile::BackgroundStoneElevatorMiddle,
         );
         world_commands.set_tile_area(
-            world_pos_to_tile_pos(ELEVATOR_PLATFORM_END)+ivec2(2, 1),
-            world_pos_to_tile_pos(ELEVATOR_PLATFORM_START-ELEVATOR_PLATFORM_END)+ivec2(1, 0),
+            world_pos_to_tile_pos(layout.elevator_platform_end)+ivec2(2, 1),
+            world_pos_to_tile_pos(layout.elevator_platform_start-layout.elevator_platform_end)+ivec2(1, 0),
             Tile::BackgroundStoneElevatorRight,
         );
         
-        let room_x = ROOM_START_I32.x;
-        let room_y = ROOM_START_I32.y;
-        let room_w = ROOM_END_I32.x - ROOM_START_I32.x;
+        let room_x = layout.room_start_i32.x;
+        let room_y = layout.room_start_i32.y;
+        let room_w = layout.room_end_i32.x - layout.room_start_i32.x;
 
         let dig_area_x = room_x + room_w/2 - 4;
         for i in 0..8 {
@@ -1022,11 +1121,23 @@ pub fn update(game: &mut Game) {
     }
 
     // apply commands & updates :::
+    let player_eye = world_pos_to_tile_pos(player.trans.pos + vec2(0.0, player.trans.size.y/2.0));
     world.apply_commands(world_commands);
+    fog::discover_around(world, &game.bump, player_eye);
     world.apply_updates(&assets.tile_set);
+    lighting::find_exposed_gems(world, visible_chunks, &mut game.exposed_gems);
+    for (tile_pos, glow) in &game.exposed_gems {
+        if !game.particles.chance(0.8, dt) { continue; }
+        let world_pos = tile_pos_to_world_pos(*tile_pos);
+        let tile_rect = Rect::new(world_pos.x, world_pos.y, TILE_SIDE_F32, TILE_SIDE_F32);
+        game.particles.burst_in(&particles::SPARKLE, tile_rect, 1, Some(Color::new(glow.x, glow.y, glow.z, 1.0)));
+    }
+    particles::tick_particles(&mut game.particles, &world.tiles(), &layout, dt);
+    game.minimap.repaint_around(world, player_eye);
+    game.deepest_depth = i32::max(game.deepest_depth, layout.spawn_i32.y - world_pos_to_tile_pos(player.trans.pos).y);
     
     // move late derived :::
-    next_late_derived.ui_is_active = game.ui_show_statue;
+    next_late_derived.ui_is_active = game.ui_show_statue || game.ui_show_map;
     game.late_derived = next_late_derived;
 
     // post update :::
@@ -14,6 +14,41 @@ pub struct Upgrades {
     pub demolisher: DemolisherUpgrade,
 }
 
+impl Upgrades {
+    pub const COUNT: usize = 9;
+
+    pub fn tiers(&self) -> [u8; Self::COUNT] {
+        [
+            self.mining.kind as u8,
+            self.ladder.kind as u8,
+            self.bag.kind as u8,
+            self.climb_momentum.kind as u8,
+            self.jetpack.kind as u8,
+            self.jetpack_boost.kind as u8,
+            self.jetpack_fuel.kind as u8,
+            self.jetpack_storage.kind as u8,
+            self.demolisher.kind as u8,
+        ]
+    }
+    // INFO: Returns None when any of the tiers is out of range, leaving the upgrades untouched.
+    pub fn from_tiers(tiers: [u8; Self::COUNT]) -> Option<Self> {
+        let [mining, ladder, bag, climb_momentum, jetpack, jetpack_boost, jetpack_fuel, jetpack_storage, demolisher] = tiers;
+
+        let mut upgrades = Self::default();
+        upgrades.mining.kind = MiningUpgradeKind::from_u8(mining)?;
+        upgrades.ladder.kind = LadderUpgradeKind::from_u8(ladder)?;
+        upgrades.bag.kind = BagUpgradeKind::from_u8(bag)?;
+        upgrades.climb_momentum.kind = ClimbMomentumUpgradeKind::from_u8(climb_momentum)?;
+        upgrades.jetpack.kind = JetpackUpgradeKind::from_u8(jetpack)?;
+        upgrades.jetpack_boost.kind = JetpackBoostUpgradeKind::from_u8(jetpack_boost)?;
+        upgrades.jetpack_fuel.kind = JetpackFuelUpgradeKind::from_u8(jetpack_fuel)?;
+        upgrades.jetpack_storage.kind = JetpackStorageUpgradeKind::from_u8(jetpack_storage)?;
+        upgrades.demolisher.kind = DemolisherUpgradeKind::from_u8(demolisher)?;
+
+        Some(upgrades)
+    }
+}
+
 pub struct SeqUpgrade<'a> {
     pub name: &'static str,
     pub tier: u8,
@@ -72,7 +107,14 @@ macro_rules! create_seq {
                     // WARN: This line is inside unsafe because it affects the result. This ensures next
                     // never passes max possible value for variant
                     let next = u8::min(cur+1, std::mem::variant_count::<Self>() as u8-1);
-                    std::mem::transmute(next)
+                    std::mem::transmute::<u8, $kind>(next)
+                }
+            }
+            pub fn from_u8(value: u8) -> Option<Self> {
+                if value < std::mem::variant_count::<Self>() as u8 {
+                    Some(unsafe { std::mem::transmute::<u8, $kind>(value) })
+                } else {
+                    None
                 }
             }
             pub fn upgrade(&mut self) {
@@ -140,3 +182,65 @@ create_seq! {struct DemolisherUpgrade; enum DemolisherUpgradeKind {
     NoDemolisher => ("No Demolisher", 0),
     Demolisher => ("Demolisher", 32000),
 }}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrade_walks_tiers_and_stops_at_last() {
+        let mut kind = MiningUpgradeKind::default();
+        assert_eq!(kind as u8, MiningUpgradeKind::DefaultPickaxe as u8);
+
+        let expected = [
+            MiningUpgradeKind::IronPickaxe,
+            MiningUpgradeKind::HardenedPickaxe,
+            MiningUpgradeKind::AlloyPickaxe,
+            MiningUpgradeKind::AlloyPickaxe,
+        ];
+        for next in expected {
+            kind.upgrade();
+            assert_eq!(kind as u8, next as u8);
+        }
+    }
+
+    #[test]
+    fn seq_shows_next_tier_until_last() {
+        let mut bag = BagUpgrade::default();
+
+        {
+            let seq = bag.to_seq();
+            assert_eq!((seq.tier, seq.count, seq.name, seq.cost), (0, 4, "Small Pouch", 150));
+            assert!(!seq.reached_count);
+        }
+
+        bag.kind = BagUpgradeKind::Backpack;
+        let seq = bag.to_seq();
+        assert_eq!((seq.tier, seq.name), (3, "Backpack"));
+        assert!(seq.reached_count);
+    }
+
+    #[test]
+    fn seq_upgrade_applies_to_container() {
+        let mut ladder = LadderUpgrade::default();
+        (ladder.to_seq().upgrade)();
+
+        assert!(ladder.reached(LadderUpgradeKind::FastClimb));
+        assert!(ladder.reached(LadderUpgradeKind::DefaultClimb));
+    }
+
+    #[test]
+    fn tiers_round_trip_and_reject_out_of_range() {
+        let mut upgrades = Upgrades::default();
+        upgrades.mining.kind = MiningUpgradeKind::HardenedPickaxe;
+        upgrades.jetpack_fuel.kind = JetpackFuelUpgradeKind::LongHaulTanks;
+
+        let tiers = upgrades.tiers();
+        assert_eq!(Upgrades::from_tiers(tiers).unwrap().tiers(), tiers);
+
+        let mut bad_tiers = tiers;
+        bad_tiers[1] = 2;
+        assert!(Upgrades::from_tiers(bad_tiers).is_none());
+        assert!(JetpackUpgradeKind::from_u8(2).is_none());
+    }
+}
@@ -0,0 +1,77 @@
+// INFO: Where the 160x120 game lands in the window. The world is drawn into a render target of exactly that
+// size and scaled up by a whole number so every pixel stays the same size, whatever is left over becomes black
+// bars. Smooth scaling fills as much as the aspect ratio allows instead, with filtering hiding the uneven
+// pixels. UI is drawn straight to the window through the same rect so text stays sharp.
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    // INFO: Window pixels, y down.
+    pub rect: Rect,
+    // INFO: Window pixels per game pixel.
+    pub scale: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Viewport {
+        fit_viewport(vec2(GAME_WIDTH_F32, GAME_HEIGHT_F32), false)
+    }
+}
+
+// INFO: Windows smaller than the game fall back to shrinking, there is no whole number under 1.
+pub fn fit_viewport(screen: Vec2, smooth: bool) -> Viewport {
+    let fit = f32::min(screen.x / GAME_WIDTH_F32, screen.y / GAME_HEIGHT_F32);
+    let scale = if smooth || fit < 1.0 { fit } else { fit.floor() };
+
+    let size = vec2(GAME_WIDTH_F32, GAME_HEIGHT_F32) * scale;
+    let origin = ((screen - size) / 2.0).floor();
+
+    Viewport { rect: Rect::new(origin.x, origin.y, size.x, size.y), scale }
+}
+
+impl Viewport {
+    // INFO: For Camera2D::viewport, which counts y from the bottom of the window like GL does.
+    pub fn gl_viewport(&self, screen_height: f32) -> (i32, i32, i32, i32) {
+        (
+            self.rect.x as i32,
+            (screen_height - self.rect.y - self.rect.h) as i32,
+            self.rect.w as i32,
+            self.rect.h as i32,
+        )
+    }
+
+    pub fn screen_to_game(&self, screen_pos: Vec2) -> Vec2 {
+        (screen_pos - self.rect.point()) / self.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_scale_letterboxes_and_smooth_fills() {
+        let viewport = fit_viewport(vec2(1920.0, 1080.0), false);
+        assert_eq!(viewport.scale, 9.0);
+        assert_eq!(viewport.rect, Rect::new(240.0, 0.0, 1440.0, 1080.0));
+
+        let viewport = fit_viewport(vec2(1000.0, 1000.0), false);
+        assert_eq!(viewport.scale, 6.0);
+        assert_eq!(viewport.rect, Rect::new(20.0, 140.0, 960.0, 720.0));
+
+        let viewport = fit_viewport(vec2(1000.0, 1000.0), true);
+        assert_eq!(viewport.scale, 6.25);
+        assert_eq!(viewport.rect.w, 1000.0);
+
+        assert_eq!(fit_viewport(vec2(80.0, 60.0), false).scale, 0.5);
+    }
+
+    #[test]
+    fn screen_positions_map_into_the_game() {
+        let viewport = fit_viewport(vec2(1000.0, 1000.0), false);
+        assert_eq!(viewport.screen_to_game(vec2(20.0, 140.0)), vec2(0.0, 0.0));
+        assert_eq!(viewport.screen_to_game(vec2(980.0, 860.0)), vec2(GAME_WIDTH_F32, GAME_HEIGHT_F32));
+        assert_eq!(viewport.gl_viewport(1000.0), (20, 140, 960, 720));
+    }
+}
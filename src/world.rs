@@ -3,82 +3,155 @@ use std::collections::{BTreeMap, HashSet};
 use crate::prelude::*;
 
 use crate::tile::{ TileSetAsset, TileChunk };
+use crate::worldgen::{ WorldGenProfile };
 
 pub mod consts {
-    use super::*;
-
-    pub const WORLD_WIDTH: usize = 64;
-    pub const WORLD_HEIGHT: usize = 64;
-    pub const WORLD_SIZE: usize = WORLD_WIDTH * WORLD_HEIGHT;
     pub const TILE_SIDE: usize = 16;
-
-    pub const WORLD_WIDTH_I32: i32 = WORLD_WIDTH as i32;
-    pub const WORLD_HEIGHT_I32: i32 = WORLD_HEIGHT as i32;
-    pub const WORLD_SIZE_I32: i32 = WORLD_SIZE as i32;
     pub const TILE_SIDE_I32: i32 = TILE_SIDE as i32;
-
-    pub const WORLD_WIDTH_F32: f32 = WORLD_WIDTH as f32;
-    pub const WORLD_HEIGHT_F32: f32 = WORLD_HEIGHT as f32;
-    pub const WORLD_SIZE_F32: f32 = WORLD_SIZE as f32;
     pub const TILE_SIDE_F32: f32 = TILE_SIDE as f32;
 
-    pub const WORLD_SPAWN_I32: IVec2 = ivec2(
-        ( WORLD_WIDTH * CHUNK_SIDE / 2 ) as i32 + 1,
-        ( (WORLD_HEIGHT - 2) * CHUNK_SIDE ) as i32  + 1,
-    );
-    pub const WORLD_SPAWN_F32: Vec2 = vec2(WORLD_SPAWN_I32.x as f32, WORLD_SPAWN_I32.y as f32);    
-
     pub const MINE_AREA_WIDTH_I32: i32 = 8;
-        
-    pub const ROOM_START_I32: IVec2 = ivec2(WORLD_SPAWN_I32.x - CHUNK_SIDE_I32*2, WORLD_SPAWN_I32.y);
-    pub const ROOM_END_I32: IVec2 = ROOM_START_I32.wrapping_add(ivec2(CHUNK_SIDE_I32*4, 8));
-    
-    pub const STATUE: IVec2 = ivec2(WORLD_SPAWN_I32.x-MINE_AREA_WIDTH_I32/2-2, WORLD_SPAWN_I32.y);
 
-    pub const RAIL_START: IVec2 = ivec2(WORLD_SPAWN_I32.x+MINE_AREA_WIDTH_I32/2+1, WORLD_SPAWN_I32.y);
-    pub const RAIL_STRAIGHT_END: IVec2 = ivec2(RAIL_START.x+6, WORLD_SPAWN_I32.y);
-    pub const RAIL_DIAGONAL_END: IVec2 = ivec2(RAIL_START.x+26, ROOM_END_I32.y+1);
-    
-    pub const BARRIER_HEIGHT: i32 = 13;
-    pub const BARRIER_POS: IVec2 = ivec2(0, (WORLD_HEIGHT_I32-BARRIER_HEIGHT)*CHUNK_SIDE_I32);
-    
-    pub const MINECART_START: Vec2 = vec2(
-        RAIL_START.x as f32*TILE_SIDE_F32 + 3.0,
-        RAIL_START.y as f32*TILE_SIDE_F32,
-    );
-    pub const MINECART_STRAIGHT_END: Vec2 = vec2(
-        RAIL_STRAIGHT_END.x as f32*TILE_SIDE_F32 + TILE_SIDE_F32/2.0,
-        RAIL_STRAIGHT_END.y as f32*TILE_SIDE_F32,
-    );
-    pub const MINECART_DIAGONAL_END: Vec2 = vec2(
-        RAIL_DIAGONAL_END.x as f32*TILE_SIDE_F32-24.0,
-        RAIL_DIAGONAL_END.y as f32*TILE_SIDE_F32-1.0,
-    );
-    pub const ELEVATOR_CAGE: IVec2 = ivec2(
-        STATUE.x-6,
-        STATUE.y
-    );
-    pub const ELEVATOR_PLATFORM_START: Vec2 = vec2(
-        ELEVATOR_CAGE.x as f32*TILE_SIDE_F32,
-        ELEVATOR_CAGE.y as f32*TILE_SIDE_F32 - 5.0,
-    );
-    pub const ELEVATOR_PLATFORM_END: Vec2 = vec2(
-        ELEVATOR_PLATFORM_START.x,
-        (BARRIER_POS.y - 2) as f32*TILE_SIDE_F32,
-    );
-    pub const ELEVATOR_PLATFORM_END_STOP: Vec2 = vec2(
-        ELEVATOR_PLATFORM_START.x,
-        (BARRIER_POS.y - 2) as f32*TILE_SIDE_F32-21.0,
-    );
-    pub const DEMOLISHER: Vec2 = vec2(
-        ROOM_START_I32.x as f32 * TILE_SIDE_F32,
-        ROOM_START_I32.y as f32 * TILE_SIDE_F32,
-    );
+    // INFO: Sizes offered on the main menu, in chunks. Every chunk carries its own mesh, which is what keeps
+    // the biggest one from going wider.
+    pub const WORLD_SIZES: [(&str, usize, usize); 3] = [
+        ( "Small" , 32, 32  ),
+        ( "Normal", 64, 64  ),
+        ( "Huge"  , 64, 128 ),
+    ];
+    pub const DEFAULT_WORLD_SIZE_I: usize = 1;
 }
 
 use consts::*;
 
+// INFO: World dimensions (in chunks) and every landmark that follows from them. Landmarks keep the units of the
+// consts they replaced, IVec2 ones are tiles (STATUE and ELEVATOR_CAGE are multiplied by CHUNK_SIDE for
+// their entity position, as they always were), Vec2 ones are world units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WorldLayout {
+    pub width: usize,
+    pub height: usize,
+    pub barrier_depth: i32,
+
+    pub spawn_i32: IVec2,
+    pub spawn_f32: Vec2,
+    pub room_start_i32: IVec2,
+    pub room_end_i32: IVec2,
+    pub statue: IVec2,
+    pub rail_start: IVec2,
+    pub rail_straight_end: IVec2,
+    pub rail_diagonal_end: IVec2,
+    pub barrier_pos: IVec2,
+    pub minecart_start: Vec2,
+    pub minecart_straight_end: Vec2,
+    pub minecart_diagonal_end: Vec2,
+    pub elevator_cage: IVec2,
+    pub elevator_platform_start: Vec2,
+    pub elevator_platform_end: Vec2,
+    pub elevator_platform_end_stop: Vec2,
+    pub demolisher: Vec2,
+}
+
+impl WorldLayout {
+    // INFO: The surface (room, rails, elevator) needs 5 chunks of width. Height has to fit the room, a barrier
+    // and the scatter pass under it.
+    pub const MIN_WIDTH: usize = 6;
+    pub const MIN_HEIGHT: usize = 24;
+    pub const MAX_SIDE: usize = 256;
+
+    pub fn new(width: usize, height: usize, barrier_depth: i32) -> Self {
+        assert!((Self::MIN_WIDTH..=Self::MAX_SIDE).contains(&width), "world width {} out of range", width);
+        assert!((Self::MIN_HEIGHT..=Self::MAX_SIDE).contains(&height), "world height {} out of range", height);
+
+        // INFO: Profiles are written without knowing the world size, deep barriers get pulled up on short maps.
+        let barrier_depth = barrier_depth.clamp(2, height as i32 - 4);
+
+        let spawn_i32 = ivec2(
+            ( width * CHUNK_SIDE / 2 ) as i32 + 1,
+            ( (height - 2) * CHUNK_SIDE ) as i32  + 1,
+        );
+        let spawn_f32 = spawn_i32.as_vec2();
+
+        let room_start_i32 = ivec2(spawn_i32.x - CHUNK_SIDE_I32*2, spawn_i32.y);
+        let room_end_i32 = room_start_i32 + ivec2(CHUNK_SIDE_I32*4, 8);
+
+        let statue = ivec2(spawn_i32.x-MINE_AREA_WIDTH_I32/2-2, spawn_i32.y);
+
+        let rail_start = ivec2(spawn_i32.x+MINE_AREA_WIDTH_I32/2+1, spawn_i32.y);
+        let rail_straight_end = ivec2(rail_start.x+6, spawn_i32.y);
+        let rail_diagonal_end = ivec2(rail_start.x+26, room_end_i32.y+1);
+
+        let barrier_pos = ivec2(0, (height as i32-barrier_depth)*CHUNK_SIDE_I32);
+
+        let elevator_cage = ivec2(statue.x-6, statue.y);
+        let elevator_platform_start = vec2(
+            elevator_cage.x as f32*TILE_SIDE_F32,
+            elevator_cage.y as f32*TILE_SIDE_F32 - 5.0,
+        );
+        let elevator_platform_end = vec2(
+            elevator_platform_start.x,
+            (barrier_pos.y - 2) as f32*TILE_SIDE_F32,
+        );
+
+        Self {
+            width,
+            height,
+            barrier_depth,
+
+            spawn_i32,
+            spawn_f32,
+            room_start_i32,
+            room_end_i32,
+            statue,
+            rail_start,
+            rail_straight_end,
+            rail_diagonal_end,
+            barrier_pos,
+            minecart_start: vec2(
+                rail_start.x as f32*TILE_SIDE_F32 + 3.0,
+                rail_start.y as f32*TILE_SIDE_F32,
+            ),
+            minecart_straight_end: vec2(
+                rail_straight_end.x as f32*TILE_SIDE_F32 + TILE_SIDE_F32/2.0,
+                rail_straight_end.y as f32*TILE_SIDE_F32,
+            ),
+            minecart_diagonal_end: vec2(
+                rail_diagonal_end.x as f32*TILE_SIDE_F32-24.0,
+                rail_diagonal_end.y as f32*TILE_SIDE_F32-1.0,
+            ),
+            elevator_cage,
+            elevator_platform_start,
+            elevator_platform_end,
+            elevator_platform_end_stop: elevator_platform_end - vec2(0.0, 21.0),
+            demolisher: vec2(
+                room_start_i32.x as f32 * TILE_SIDE_F32,
+                room_start_i32.y as f32 * TILE_SIDE_F32,
+            ),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.width * self.height
+    }
+    pub fn width_i32(&self) -> i32 {
+        self.width as i32
+    }
+    pub fn height_i32(&self) -> i32 {
+        self.height as i32
+    }
+    // INFO: In tiles.
+    pub fn tile_width_i32(&self) -> i32 {
+        self.width_i32() * CHUNK_SIDE_I32
+    }
+    pub fn tile_height_i32(&self) -> i32 {
+        self.height_i32() * CHUNK_SIDE_I32
+    }
+}
+
 pub struct World {
+    pub seed: u64,
+    pub profile: String,
+    pub layout: WorldLayout,
     pub chunks: Vec<TileChunk>,
     pub meshes: Vec<GameMesh>,
     pub dirty_chunks: HashSet<IVec2>,
@@ -88,6 +161,7 @@ pub struct World {
 #[derive(Clone, Copy)]
 pub struct WorldTiles<'w> {
     pub chunks: &'w Vec<TileChunk>,
+    pub width: usize,
 }
 
 impl<'w> WorldTiles<'w> {
@@ -97,10 +171,10 @@ impl<'w> WorldTiles<'w> {
     }
     pub fn at_tile_pos(&self, tile_pos: IVec2) -> WorldTile<'w> {
         let chunk_pos = tile_pos_to_chunk_pos(tile_pos);
-        let chunk = &self.chunks[chunk_index_at(chunk_pos)];
+        let chunk = &self.chunks[chunk_index_at(chunk_pos, self.width)];
         
         WorldTile {
-            world_tiles: WorldTiles { chunks: self.chunks },
+            world_tiles: *self,
             pos: tile_pos,
             kind: chunk.tiles[tile_index_at(tile_pos)],
         }
@@ -161,8 +235,8 @@ pub mod conversions {
     pub fn local_tile_index_at(local_tile_pos: IVec2) -> usize {
         local_tile_pos.y as usize * CHUNK_SIDE + local_tile_pos.x as usize
     }
-    pub fn chunk_index_at(chunk_pos: IVec2) -> usize {
-        chunk_pos.y as usize * WORLD_WIDTH + chunk_pos.x as usize
+    pub fn chunk_index_at(chunk_pos: IVec2, world_width: usize) -> usize {
+        chunk_pos.y as usize * world_width + chunk_pos.x as usize
     }
     
     // chunk_pos ->
@@ -242,9 +316,15 @@ enum WorldCommand<'b> {
 }
 
 impl World {
-    pub fn new(tile_set: &TileSetAsset, bump: &Bump) -> Self {
+    pub fn new(tile_set: &TileSetAsset, bump: &Bump, seed: u64, profile: &WorldGenProfile, width: usize, height: usize) -> Self {
+        // INFO: World generation owns its own generator, so the same seed always results in the same world
+        // no matter how much the global one was used before.
+        let rng = rand::RandGenerator::new();
+        rng.srand(seed);
+
         let tile_chunk = TileChunk {
             tiles: [Tile::BackgroundStone; CHUNK_SIZE],
+            discovered: [false; CHUNK_SIZE],
         };
 
         let mut tile_mesh = Mesh {
@@ -296,15 +376,26 @@ impl World {
             }
         }        
         
-        let room_x = ROOM_START_I32.x;
-        let room_y = ROOM_START_I32.y;
-        let room_w = ROOM_END_I32.x - ROOM_START_I32.x;
-        let room_h = ROOM_END_I32.y - ROOM_START_I32.y;
+        let layout = WorldLayout::new(width, height, profile.barrier_depth);
+
+        let room_x = layout.room_start_i32.x;
+        let room_y = layout.room_start_i32.y;
+        let room_w = layout.room_end_i32.x - layout.room_start_i32.x;
+        let room_h = layout.room_end_i32.y - layout.room_start_i32.y;
+
+        let barrier_depth = layout.barrier_depth;
+        let barrier_pos = layout.barrier_pos;
+
+        let width_i32 = layout.width_i32();
+        let height_i32 = layout.height_i32();
 
         let mut world = World {
-            chunks: vec![tile_chunk; WORLD_SIZE],
-            meshes: vec![GameMesh(tile_mesh); WORLD_SIZE],
-            dirty_chunks: HashSet::with_capacity(WORLD_SIZE),
+            seed,
+            profile: profile.name.clone(),
+            layout,
+            chunks: vec![tile_chunk; layout.size()],
+            meshes: vec![GameMesh(tile_mesh); layout.size()],
+            dirty_chunks: HashSet::with_capacity(layout.size()),
             recalculate_all_meshes: false,
         };
 
@@ -313,13 +404,8 @@ impl World {
         commands.push_commands(&[
             WorldCommand::SetTileArea {
                 tile: Tile::Stone,
-                x: BARRIER_POS.x, y: BARRIER_POS.y,
-                // BUG: When presented with x=0 and w=Y, it should be able to query tiles *between* [0 and Y).
-                // But due to the chunk_pos calculation resulting chunk at the end is not a valid chunk. While
-                // SetTileArea is guarded from that with min/max checks, chunk index calculation is not, hence
-                // for that reason this is doing -1 in both dimensions, painting 1 tile less. It isn't noticeable
-                // by player unless they are at world boundary, but must be fixed at some point.
-                width: WORLD_WIDTH_I32*CHUNK_SIDE_I32-1, height: BARRIER_HEIGHT*CHUNK_SIDE_I32-1,
+                x: barrier_pos.x, y: barrier_pos.y,
+                width: width_i32*CHUNK_SIDE_I32, height: barrier_depth*CHUNK_SIDE_I32,
             },
             WorldCommand::SetTileArea {
                 tile: Tile::BackgroundStone,
@@ -366,63 +452,31 @@ impl World {
 
         // INFO: Min >= 1 always spawns, Min < 1 it spawns with Max/abs(diff). So, if Min(0) Max(1), it is 1/2,
         // Min(-1) Max(1), it is 1/3 etc.
-        struct Min(i32);
-        struct Max(u32);
-
         let mut ores_map = BTreeMap::new();
-
-        let ores = [
-            ( 2 , Tile::StoneCopperOre, Min(10), Max(16) ),
-            ( 2 , Tile::StoneIronOre  , Min(2 ), Max(6 ) ),
-            
-            ( 3 , Tile::StoneCopperOre, Min(6 ), Max(10) ),
-            ( 3 , Tile::StoneIronOre  , Min(12), Max(16) ),
-            
-            ( 4 , Tile::StoneCopperOre, Min(4 ), Max(8) ),
-            ( 4 , Tile::StoneIronOre  , Min(12), Max(20) ),
-            ( 4 , Tile::StoneGoldOre  , Min(1 ), Max(2 ) ),
-            
-            ( 5 , Tile::StoneGoldOre  , Min(4 ), Max(8 ) ),
-            ( 5 , Tile::StoneCopperOre, Min(8 ), Max(12) ),
-            ( 5 , Tile::StoneIronOre  , Min(14), Max(14) ),
-            
-            ( 6 , Tile::StoneGoldOre  , Min(6 ), Max(10) ),
-
-            ( 7 , Tile::StoneGoldOre  , Min(8 ), Max(12 ) ),
-            
-            ( 8 , Tile::StoneCopperOre, Min(5 ), Max(8 ) ),
-            ( 8 , Tile::StoneIronOre  , Min(18), Max(18) ),
-            ( 8 , Tile::StoneGoldOre  , Min(10), Max(14) ),
-            ( 8 , Tile::StoneEmerald  , Min(-7), Max(1 ) ),
-            
-            ( 9 , Tile::StoneEmerald  , Min(-4), Max(1 ) ),
-            ( 10, Tile::StoneEmerald  , Min(0 ), Max(1 ) ),
-            ( 11, Tile::StoneEmerald  , Min(0 ), Max(2 ) ),
-            ( 12, Tile::StoneEmerald  , Min(1 ), Max(3 ) ),
-        ];
+        let ores = &profile.ores[..];
 
         let mut ores_i = 0;
         
-        for chunk_y in 0..BARRIER_HEIGHT {
+        for chunk_y in 0..barrier_depth {
             while ores_i < ores.len() {
-                let (wanted_chunk_y, tile, Min(min), Max(max)) = ores[ores_i];
+                let rule = ores[ores_i];
                 
-                if wanted_chunk_y > chunk_y { break; }
+                if rule.depth > chunk_y { break; }
 
                 ores_i += 1;
 
-                ores_map.insert(tile, (Min(min), Max(max)));
+                ores_map.insert(rule.tile, (rule.min, rule.max));
             }
             
-            for chunk_x in 0..WORLD_WIDTH_I32 {
-                let chunk_pos = ivec2(chunk_x, WORLD_HEIGHT_I32-chunk_y-1);
+            for chunk_x in 0..width_i32 {
+                let chunk_pos = ivec2(chunk_x, height_i32-chunk_y-1);
 
-                for (&tile, &(Min(min), Max(max))) in ores_map.iter() {
-                    let gen_count = i32::max(rand::gen_range(min, max as i32 + 1), 0);
+                for (&tile, &(min, max)) in ores_map.iter() {
+                    let gen_count = i32::max(rng.gen_range(min, max + 1), 0);
                     let mut local_tile_poses = Vec::with_capacity_in(gen_count as usize, bump);
                     for _ in 0..gen_count {
-                        let x = rand::gen_range(0, CHUNK_SIDE_I32);
-                        let y = rand::gen_range(0, CHUNK_SIDE_I32);
+                        let x = rng.gen_range(0, CHUNK_SIDE_I32);
+                        let y = rng.gen_range(0, CHUNK_SIDE_I32);
                         local_tile_poses.push(ivec2(x, y));
                     }
                     commands.push_commands(&[ WorldCommand::SetTilesInChunk { chunk_pos, local_tile_poses, tile } ]);
@@ -431,28 +485,19 @@ impl World {
             }
         }
 
-        struct HMin(usize);
-        struct HMax(usize);
-        struct Width(usize);
-
-        let hard_stone_pass = [
-            (5 , HMin(12), HMax(24), Width(3 )),
-            (6 , HMin(16), HMax(24), Width(4 )),
-            (7 , HMin(24), HMax(24), Width(4 )),
-            (10, HMin(24), HMax(24), Width(6 )),
-            (12, HMin(24), HMax(24), Width(6 )),
-        ];
+        // INFO: Bands the barrier got pulled up over are dropped.
+        for band in profile.hard_stone_bands.iter().filter(|band| band.depth < barrier_depth) {
+            let (min, max, width) = (band.min_stride, band.max_stride, band.width);
+            let chunk_y = band.depth + 1;
+            let mut tile_y = (height_i32 - chunk_y) * CHUNK_SIDE_I32 + 8;
+            let mut guide_points = Vec::with_capacity_in(layout.width * CHUNK_SIDE, bump);
 
-        for (chunk_y, HMin(min), HMax(max), Width(width)) in hard_stone_pass {
-            let mut tile_y = (WORLD_HEIGHT_I32 - chunk_y) * CHUNK_SIDE_I32 + 8;
-            let mut guide_points = Vec::with_capacity_in(WORLD_WIDTH * CHUNK_SIDE, bump);
-
-            let chunk_tile_begin_y = chunk_pos_to_tile_pos(ivec2(0, WORLD_HEIGHT_I32 - chunk_y)).y;
-            let chunk_tile_end_y = chunk_pos_to_tile_pos(ivec2(0, WORLD_HEIGHT_I32 - chunk_y+1)).y - 1;
+            let chunk_tile_begin_y = chunk_pos_to_tile_pos(ivec2(0, height_i32 - chunk_y)).y;
+            let chunk_tile_end_y = chunk_pos_to_tile_pos(ivec2(0, height_i32 - chunk_y+1)).y - 1;
             
-            for tile_x in 0..WORLD_WIDTH_I32 * CHUNK_SIDE_I32 {
+            for tile_x in 0..width_i32 * CHUNK_SIDE_I32 {
                 const MAX: i32 = 5;
-                let direction_rand = rand::gen_range(0, MAX);
+                let direction_rand = rng.gen_range(0, MAX);
 
                 let direction = if direction_rand < 1 {
                     -1
@@ -472,21 +517,21 @@ impl World {
                 guide_points.push(ivec2(tile_x, tile_y));
             }
 
-            let guide_points = &guide_points[..WORLD_WIDTH*CHUNK_SIDE];
+            let guide_points = &guide_points[..layout.width*CHUNK_SIDE];
 
             let mut i = 0;
-            while i < WORLD_WIDTH*CHUNK_SIDE {
-                let stride = rand::gen_range(min, max);
-                let space = rand::gen_range(1, 6);
+            while i < layout.width*CHUNK_SIDE {
+                let stride = rng.gen_range(min, max);
+                let space = rng.gen_range(1, 6);
 
-                let random_x = rand::gen_range(-8, 9);
-                let random_y = rand::gen_range(-4, 5);
+                let random_x = rng.gen_range(-8, 9);
+                let random_y = rng.gen_range(-4, 5);
 
                 let offset = ivec2(random_x, random_y);
 
-                for stride_i in i..usize::min(i+stride, WORLD_WIDTH*CHUNK_SIDE) {
+                for stride_i in i..usize::min(i+stride, layout.width*CHUNK_SIDE) {
                     let guide_point = guide_points[stride_i];
-                    let width = rand::gen_range(width-1, width+1) as i32;
+                    let width = rng.gen_range(width-1, width+1) as i32;
                     for width_i in 0..width {
                         commands.set_tile(offset+guide_point+ivec2(0, width_i), Tile::HardStone);
                     }
@@ -497,12 +542,12 @@ impl World {
 
         let mut stepping_stone_i = 0;
 
-        while stepping_stone_i < WORLD_WIDTH_I32*CHUNK_SIDE_I32 {
-            let x = rand::gen_range(4, 10);
-            stepping_stone_i = i32::min(stepping_stone_i+x, WORLD_WIDTH_I32*CHUNK_SIDE_I32);
+        while stepping_stone_i < width_i32*CHUNK_SIDE_I32 {
+            let x = rng.gen_range(4, 10);
+            stepping_stone_i = i32::min(stepping_stone_i+x, width_i32*CHUNK_SIDE_I32);
 
-            let height = rand::gen_range(2, 5);
-            let smallest_width = rand::gen_range(2, 4);
+            let height = rng.gen_range(2, 5);
+            let smallest_width = rng.gen_range(2, 4);
             let mut x_offsets = Vec::with_capacity_in(height as usize, &bump);
             let mut widths = Vec::with_capacity_in(height as usize, &bump);
 
@@ -524,11 +569,11 @@ impl World {
                 next_x_offset += 1;
             }
 
-            let barrier_point = (WORLD_HEIGHT_I32-BARRIER_HEIGHT)*CHUNK_SIDE_I32 - 16;
-            let local_y_offset = rand::gen_range(-4, 4);
+            let barrier_point = barrier_pos.y - 16;
+            let local_y_offset = rng.gen_range(-4, 4);
 
             for (i, y) in (barrier_point..barrier_point+height).enumerate() {
-                let local_x_offset = rand::gen_range(-2, 2);
+                let local_x_offset = rng.gen_range(-2, 2);
                 let width = widths[i];
                 let x = local_x_offset + x_offsets[i] + stepping_stone_i;
                 let y = local_y_offset + y;
@@ -538,21 +583,23 @@ impl World {
         }
 
         let mut scatter_i = 0;
-        let mut smallest_scatter_y = WORLD_HEIGHT_I32*CHUNK_SIDE_I32;
+        let mut smallest_scatter_y = height_i32*CHUNK_SIDE_I32;
 
-        while scatter_i < WORLD_WIDTH_I32*CHUNK_SIDE_I32 {
-            scatter_i += rand::gen_range(2, 8);
+        while scatter_i < width_i32*CHUNK_SIDE_I32 {
+            scatter_i += rng.gen_range(2, 8);
 
-            let mut y = (WORLD_HEIGHT_I32-BARRIER_HEIGHT)*CHUNK_SIDE_I32 - 16;
+            let mut y = barrier_pos.y - 16;
             for _ in 0..32 {
-                y -= rand::gen_range(8, 24);
-                let offset_x = rand::gen_range(-4, 4);
-                let offset_y = rand::gen_range(-4, 4);
+                // INFO: Deep barriers leave less room below, the hard stone floor still has to fit under it.
+                if y < 32+24+CHUNK_SIDE_I32 { break; }
+                y -= rng.gen_range(8, 24);
+                let offset_x = rng.gen_range(-4, 4);
+                let offset_y = rng.gen_range(-4, 4);
                 
                 let center_top = ivec2(offset_x+scatter_i, offset_y+y);
 
-                let half_width = rand::gen_range(1, 4);
-                let height = rand::gen_range(2, 5);
+                let half_width = rng.gen_range(1, 4);
+                let height = rng.gen_range(2, 5);
 
                 let height = i32::min(height, half_width);
 
@@ -574,26 +621,25 @@ impl World {
         let mut commands = world.commands(&bump);
         let tiles = world.tiles();
                 
-        for y in smallest_scatter_y..(WORLD_HEIGHT_I32-BARRIER_HEIGHT)*CHUNK_SIDE_I32 {
-            for x in 0..WORLD_WIDTH_I32*CHUNK_SIDE_I32 {
+        for y in smallest_scatter_y..barrier_pos.y {
+            for x in 0..width_i32*CHUNK_SIDE_I32 {
                 if tiles.at_tile_pos(ivec2(x, y)).kind == Tile::Stone {
-                    let chance = rand::gen_range(0, 300);
-                    if chance <= 4 {
-                        commands.set_tile(ivec2(x, y), Tile::StoneSapphire);
-                    } else if chance <= 6 {
-                        commands.set_tile(ivec2(x, y), Tile::StoneRuby);
-                    } else if chance <= 10 {
-                        commands.set_tile(ivec2(x, y), Tile::StoneEmerald);
-                    } else if chance <= 40 {
-                        commands.set_tile(ivec2(x, y), Tile::StoneGoldOre);
+                    let chance = rng.gen_range(0, profile.gem_roll);
+                    let mut odds_below = 0;
+                    for gem in &profile.gems {
+                        odds_below += gem.odds;
+                        if chance < odds_below {
+                            commands.set_tile(ivec2(x, y), gem.tile);
+                            break;
+                        }
                     }
                 }
             }
         }
         
         commands.set_tile_area(
-            ivec2(0, (WORLD_HEIGHT_I32-BARRIER_HEIGHT) * CHUNK_SIDE_I32 - 1),
-            ivec2(WORLD_WIDTH_I32*CHUNK_SIDE_I32, 1),
+            ivec2(0, barrier_pos.y - 1),
+            ivec2(width_i32*CHUNK_SIDE_I32, 1),
             Tile::Barrier,
         );
         
@@ -601,43 +647,72 @@ impl World {
         // there, so it helps with providing landmarkds.
         commands.set_tile_area(
             ivec2(0, smallest_scatter_y),
-            ivec2(WORLD_WIDTH_I32*CHUNK_SIDE_I32, 32),
+            ivec2(width_i32*CHUNK_SIDE_I32, 32),
             Tile::HardStone,
         );
         commands.set_tile_area(
             ivec2(0, smallest_scatter_y+32),
-            ivec2(WORLD_WIDTH_I32*CHUNK_SIDE_I32, 1),
+            ivec2(width_i32*CHUNK_SIDE_I32, 1),
             Tile::Barrier,
         );
 
         commands.push_commands(&[
             WorldCommand::SetTileArea {
                 tile: Tile::WorldBoundary,
-                x: 0, y: 0, width: WORLD_WIDTH_I32*CHUNK_SIDE_I32-1, height: 1,
+                x: 0, y: 0, width: width_i32*CHUNK_SIDE_I32, height: 1,
             },
             WorldCommand::SetTileArea {
                 tile: Tile::WorldBoundary,
-                x: 0, y: WORLD_HEIGHT_I32*CHUNK_SIDE_I32-2, width: WORLD_WIDTH_I32*CHUNK_SIDE_I32-1, height: 1,
+                x: 0, y: height_i32*CHUNK_SIDE_I32-1, width: width_i32*CHUNK_SIDE_I32, height: 1,
             },
             WorldCommand::SetTileArea {
                 tile: Tile::WorldBoundary,
-                x: 0, y: 0, width: 1, height: WORLD_HEIGHT_I32*CHUNK_SIDE_I32-1,
+                x: 0, y: 0, width: 1, height: height_i32*CHUNK_SIDE_I32,
             },
             WorldCommand::SetTileArea {
                 tile: Tile::WorldBoundary,
-                x: WORLD_WIDTH_I32*CHUNK_SIDE_I32-2, y: 0, width: 1, height: WORLD_HEIGHT_I32*CHUNK_SIDE_I32-1,
+                x: width_i32*CHUNK_SIDE_I32-1, y: 0, width: 1, height: height_i32*CHUNK_SIDE_I32,
             },
         ]);
         
         commands.push_commands(&[ WorldCommand::RecalculateAllMeshes ]);
         world.apply_commands(commands);
+        world.discover_surface();
 
         world
     }
 
+    // INFO: The spawn room, rails and everything over them are known from the start, fog only covers what's
+    // under the room's floor.
+    pub fn discover_surface(&mut self) {
+        for y in self.layout.spawn_i32.y-1..self.layout.tile_height_i32() {
+            for x in 0..self.layout.tile_width_i32() {
+                self.discover(ivec2(x, y));
+            }
+        }
+    }
+
+    pub fn is_discovered(&self, tile_pos: IVec2) -> bool {
+        let layout = self.layout;
+        let inside = tile_pos.x >= 0 && tile_pos.y >= 0 && tile_pos.x < layout.tile_width_i32() && tile_pos.y < layout.tile_height_i32();
+        inside && self.chunk_at(tile_pos_to_chunk_pos(tile_pos)).discovered[tile_index_at(tile_pos)]
+    }
+
+    // INFO: Expects tile_pos inside the world. Returns whether it was undiscovered until now.
+    pub fn discover(&mut self, tile_pos: IVec2) -> bool {
+        let chunk_pos = tile_pos_to_chunk_pos(tile_pos);
+        let discovered = &mut self.chunk_mut_at(chunk_pos).discovered[tile_index_at(tile_pos)];
+        if *discovered { return false; }
+
+        *discovered = true;
+        self.dirty_chunks.insert(chunk_pos);
+        true
+    }
+
     pub fn tiles<'w>(&'w self) -> WorldTiles<'w> {
         WorldTiles {
             chunks: &self.chunks,
+            width: self.layout.width,
         }
     }
     pub fn commands<'b>(&self, bump: &'b Bump) -> WorldCommands<'b> {
@@ -654,7 +729,7 @@ impl World {
                 let chunk_pos = tile_pos_to_chunk_pos(ivec2(x, y));
                 let tile_index = tile_index_at(ivec2(x, y));
                 
-                let chunk = &mut self.chunks[chunk_index_at(chunk_pos)];
+                let chunk = &mut self.chunks[chunk_index_at(chunk_pos, self.layout.width)];
                 chunk.tiles[tile_index] = tile;
                 self.dirty_chunks.insert(chunk_pos);
             },
@@ -664,13 +739,13 @@ impl World {
                     let chunk_pos = tile_pos_to_chunk_pos(tile_pos);
                     let tile_index = tile_index_at(ivec2(tile_pos.x, tile_pos.y));
                     
-                    let chunk = &mut chunks[chunk_index_at(chunk_pos)];
+                    let chunk = &mut chunks[chunk_index_at(chunk_pos, self.layout.width)];
                     chunk.tiles[tile_index] = tile;
                     self.dirty_chunks.insert(chunk_pos);
                 }
             },
             | WorldCommand::SetTilesInChunk { chunk_pos, local_tile_poses, tile } => {
-                let chunk = &mut self.chunks[chunk_index_at(chunk_pos)];
+                let chunk = &mut self.chunks[chunk_index_at(chunk_pos, self.layout.width)];
                 for tile_pos in local_tile_poses {
                     let tile_index = local_tile_index_at(ivec2(tile_pos.x, tile_pos.y));
                     
@@ -681,17 +756,21 @@ impl World {
             | WorldCommand::SetTileArea { x, y, width, height, tile } => {
                 let chunks = &mut self.chunks[..];
 
-                let chunk_positions = World::query_chunks_between_two_tiles(
-                    world_commands.bump, ivec2(x, y), ivec2(x+width, y+height)
-                );
+                // INFO: Generation is allowed to hang over every edge of the world, the area is clipped to it.
+                // Chunks are clamped too, but tiles past the world's edge would still index into a neighbour.
+                let world_end = ivec2(self.layout.tile_width_i32(), self.layout.tile_height_i32());
+                let begin = ivec2(x, y).max(IVec2::ZERO);
+                let end = ivec2(x+width, y+height).min(world_end);
+
+                let chunk_positions = World::query_chunks_between_two_tiles(world_commands.bump, &self.layout, begin, end);
                 for chunk_pos in chunk_positions {
-                    let begin_x = i32::max(chunk_pos.x * CHUNK_SIDE_I32, x);
-                    let begin_y = i32::max(chunk_pos.y * CHUNK_SIDE_I32, y);
+                    let begin_x = i32::max(chunk_pos.x * CHUNK_SIDE_I32, begin.x);
+                    let begin_y = i32::max(chunk_pos.y * CHUNK_SIDE_I32, begin.y);
                     
-                    let end_x = i32::min((chunk_pos.x+1) * CHUNK_SIDE_I32, x+width);
-                    let end_y = i32::min((chunk_pos.y+1) * CHUNK_SIDE_I32, y+height);
+                    let end_x = i32::min((chunk_pos.x+1) * CHUNK_SIDE_I32, end.x);
+                    let end_y = i32::min((chunk_pos.y+1) * CHUNK_SIDE_I32, end.y);
 
-                    let chunk = &mut chunks[chunk_index_at(chunk_pos)];
+                    let chunk = &mut chunks[chunk_index_at(chunk_pos, self.layout.width)];
                     self.dirty_chunks.insert(chunk_pos);
                     let tiles = &mut chunk.tiles[..];
 
@@ -707,25 +786,25 @@ impl World {
     }
     
     pub fn chunk_at(&self, chunk_pos: IVec2) -> &TileChunk {
-        &self.chunks[chunk_index_at(chunk_pos)]
+        &self.chunks[chunk_index_at(chunk_pos, self.layout.width)]
     }
     pub fn chunk_mut_at(&mut self, chunk_pos: IVec2) -> &mut TileChunk {
-        &mut self.chunks[chunk_index_at(chunk_pos)]
+        &mut self.chunks[chunk_index_at(chunk_pos, self.layout.width)]
     }
     pub fn mesh_at(&self, chunk_pos: IVec2) -> &GameMesh {
-        &self.meshes[chunk_index_at(chunk_pos)]
+        &self.meshes[chunk_index_at(chunk_pos, self.layout.width)]
     }
-    pub fn query_chunks_around_chunk_pos(bump: &Bump, origin: IVec2, half_distance: u32) -> Vec<IVec2, &Bump> {
+    pub fn query_chunks_around_chunk_pos<'b>(bump: &'b Bump, layout: &WorldLayout, origin: IVec2, half_distance: u32) -> Vec<IVec2, &'b Bump> {
         let half_distance = half_distance as i32;
         let distance = half_distance * 2 + 1;
         
         let mut chunks = Vec::with_capacity_in((distance * distance) as usize, bump);
         
         let x_begin = i32::max(origin.x - half_distance, 0);
-        let x_end = i32::min(origin.x + half_distance, WORLD_WIDTH as i32 - 1);
+        let x_end = i32::min(origin.x + half_distance, layout.width_i32() - 1);
 
         let y_begin = i32::max(origin.y - half_distance, 0);
-        let y_end = i32::min(origin.y + half_distance, WORLD_HEIGHT as i32 - 1);
+        let y_end = i32::min(origin.y + half_distance, layout.height_i32() - 1);
         
         for y in y_begin..=y_end {
             for x in x_begin..=x_end {
@@ -735,11 +814,18 @@ impl World {
 
         chunks
     }
-    pub fn query_chunks_between_two_tiles(bump: &Bump, tile_begin: IVec2, tile_end: IVec2) -> Vec<IVec2, &Bump> {
-        let chunk_begin = tile_begin / IVec2::splat(CHUNK_SIDE as i32);
-        let chunk_end = tile_end / IVec2::splat(CHUNK_SIDE as i32);
-        let chunks_height = chunk_end.y - chunk_begin.y;
-        let chunks_width = chunk_end.x - chunk_begin.x;
+    // INFO: tile_end is exclusive, so an area ending right at a chunk (or world) edge doesn't reach into the
+    // next chunk. An empty area results in no chunks. Chunks outside the world are left out, chunk_index_at
+    // would wrap them onto chunks on the other side.
+    pub fn query_chunks_between_two_tiles<'b>(bump: &'b Bump, layout: &WorldLayout, tile_begin: IVec2, tile_end: IVec2) -> Vec<IVec2, &'b Bump> {
+        if tile_end.cmple(tile_begin).any() { return Vec::new_in(bump); }
+
+        let last_chunk = ivec2(layout.width_i32() - 1, layout.height_i32() - 1);
+        let chunk_begin = tile_begin.div_euclid(IVec2::splat(CHUNK_SIDE as i32)).max(IVec2::ZERO);
+        let chunk_end = (tile_end - IVec2::ONE).div_euclid(IVec2::splat(CHUNK_SIDE as i32)).min(last_chunk);
+        if chunk_end.cmplt(chunk_begin).any() { return Vec::new_in(bump); }
+        let chunks_height = chunk_end.y - chunk_begin.y + 1;
+        let chunks_width = chunk_end.x - chunk_begin.x + 1;
 
         let mut chunks = Vec::with_capacity_in((chunks_height * chunks_width) as usize, bump);
 
@@ -812,8 +898,8 @@ impl World {
                 let vertices = &mut mesh.0.vertices[..];
 
                 let mut i = 0;
-                for tile in &chunk.tiles {
-                    let bounds = tile_set.bounds[*tile as usize];
+                for tile in chunk.drawn_tiles() {
+                    let bounds = tile_set.bounds[tile as usize];
                     
                     vertices[i+0].uv = vec2(bounds.begin.x, bounds.end.y);
                     vertices[i+1].uv = vec2(bounds.end.x  , bounds.end.y);
@@ -828,16 +914,16 @@ impl World {
         }
 
         for chunk_pos in self.dirty_chunks.drain() {
-            let chunk = &mut self.chunks[chunk_index_at(chunk_pos)];
-            let mesh = &mut self.meshes[chunk_index_at(chunk_pos)];
+            let chunk = &mut self.chunks[chunk_index_at(chunk_pos, self.layout.width)];
+            let mesh = &mut self.meshes[chunk_index_at(chunk_pos, self.layout.width)];
             
             if mesh.0.texture.is_none() { continue; };
 
             let vertices = &mut mesh.0.vertices[..];
 
             let mut i = 0;
-            for tile in &chunk.tiles {
-                let bounds = tile_set.bounds[*tile as usize];
+            for tile in chunk.drawn_tiles() {
+                let bounds = tile_set.bounds[tile as usize];
                 
                 vertices[i+0].uv = vec2(bounds.begin.x, bounds.end.y);
                 vertices[i+1].uv = vec2(bounds.end.x  , bounds.end.y);
@@ -849,3 +935,203 @@ impl World {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::headless::headless_game;
+
+    fn count_tiles(world: &World, tile: Tile) -> usize {
+        world.chunks.iter().flat_map(|chunk| chunk.tiles.iter()).filter(|&&kind| kind == tile).count()
+    }
+
+    #[test]
+    fn conversions_agree_with_each_other() {
+        let tile_pos = ivec2(37, 70);
+
+        assert_eq!(tile_pos_to_chunk_pos(tile_pos), ivec2(2, 4));
+        assert_eq!(tile_pos_to_world_pos(tile_pos), vec2(592.0, 1120.0));
+        assert_eq!(world_pos_to_tile_pos(vec2(592.0, 1120.0) + 15.9), tile_pos);
+        assert_eq!(world_pos_to_chunk_pos(tile_pos_to_world_pos(tile_pos)), ivec2(2, 4));
+        assert_eq!(chunk_pos_to_tile_pos(ivec2(2, 4)), ivec2(32, 64));
+        assert_eq!(chunk_pos_to_world_pos(ivec2(2, 4)), tile_pos_to_world_pos(ivec2(32, 64)));
+
+        assert_eq!(tile_index_at(tile_pos), local_tile_index_at(ivec2(5, 6)));
+        assert_eq!(local_tile_index_at(ivec2(CHUNK_SIDE_I32-1, CHUNK_SIDE_I32-1)), CHUNK_SIZE-1);
+        assert_eq!(chunk_index_at(ivec2(5, 6), 32), 6*32 + 5);
+    }
+
+    #[test]
+    fn chunks_between_tiles_excludes_end() {
+        let bump = Bump::new();
+        let layout = WorldLayout::new(32, 32, 13);
+        let side = CHUNK_SIDE_I32;
+
+        let chunks = World::query_chunks_between_two_tiles(&bump, &layout, ivec2(0, 0), ivec2(side, side));
+        assert_eq!(chunks.as_slice(), &[ivec2(0, 0)]);
+
+        let chunks = World::query_chunks_between_two_tiles(&bump, &layout, ivec2(side-1, 0), ivec2(side+1, 1));
+        assert_eq!(chunks.as_slice(), &[ivec2(0, 0), ivec2(1, 0)]);
+
+        let last_chunk = ivec2(layout.width_i32(), layout.height_i32()) - 1;
+        let world_end = (last_chunk + 1) * side;
+        let chunks = World::query_chunks_between_two_tiles(&bump, &layout, world_end - 1, world_end);
+        assert_eq!(chunks.as_slice(), &[last_chunk]);
+
+        assert!(World::query_chunks_between_two_tiles(&bump, &layout, ivec2(4, 4), ivec2(4, 4)).is_empty());
+    }
+
+    #[test]
+    fn chunks_between_tiles_stay_inside_the_world() {
+        let bump = Bump::new();
+        let layout = WorldLayout::new(32, 32, 13);
+        let side = CHUNK_SIDE_I32;
+        let world_end = ivec2(layout.width_i32(), layout.height_i32()) * side;
+
+        let chunks = World::query_chunks_between_two_tiles(&bump, &layout, ivec2(-5, -5), ivec2(3, 3));
+        assert_eq!(chunks.as_slice(), &[ivec2(0, 0)]);
+
+        let chunks = World::query_chunks_between_two_tiles(&bump, &layout, world_end - 2, world_end + 20);
+        assert_eq!(chunks.as_slice(), &[world_end / side - 1]);
+
+        assert!(World::query_chunks_between_two_tiles(&bump, &layout, ivec2(-40, 0), ivec2(-20, 4)).is_empty());
+        assert!(World::query_chunks_between_two_tiles(&bump, &layout, world_end, world_end + 4).is_empty());
+    }
+
+    #[test]
+    fn set_tile_area_paints_exactly_the_area_across_chunks() {
+        let mut world = headless_game(1).world;
+        let bump = Bump::new();
+        assert_eq!(count_tiles(&world, Tile::RED), 0);
+
+        world.dirty_chunks.clear();
+        let mut commands = world.commands(&bump);
+        commands.set_tile_area(ivec2(10, 20), ivec2(22, 12), Tile::RED);
+        world.apply_commands(commands);
+
+        assert_eq!(count_tiles(&world, Tile::RED), 22 * 12);
+        let tiles = world.tiles();
+        assert_eq!(tiles.at_tile_pos(ivec2(10, 20)).kind, Tile::RED);
+        assert_eq!(tiles.at_tile_pos(ivec2(31, 31)).kind, Tile::RED);
+        assert_ne!(tiles.at_tile_pos(ivec2(32, 31)).kind, Tile::RED);
+        assert_ne!(tiles.at_tile_pos(ivec2(31, 32)).kind, Tile::RED);
+
+        let mut dirty: Vec<IVec2> = world.dirty_chunks.iter().copied().collect();
+        dirty.sort_by_key(|pos| (pos.y, pos.x));
+        assert_eq!(dirty, [ivec2(0, 1), ivec2(1, 1)]);
+    }
+
+    #[test]
+    fn set_tile_area_reaches_world_edge() {
+        let mut world = headless_game(1).world;
+        let bump = Bump::new();
+
+        let width = world.layout.tile_width_i32();
+        let height = world.layout.tile_height_i32();
+
+        let mut commands = world.commands(&bump);
+        commands.set_tile_area(ivec2(width-3, height-2), ivec2(3, 2), Tile::RED);
+        world.apply_commands(commands);
+
+        assert_eq!(count_tiles(&world, Tile::RED), 6);
+        assert_eq!(world.tiles().at_tile_pos(ivec2(width-1, height-1)).kind, Tile::RED);
+    }
+
+    #[test]
+    fn set_tile_area_is_clipped_to_the_world() {
+        let mut world = headless_game(1).world;
+        let bump = Bump::new();
+
+        let width = world.layout.tile_width_i32();
+        let height = world.layout.tile_height_i32();
+
+        let mut commands = world.commands(&bump);
+        commands.set_tile_area(ivec2(-2, -3), ivec2(5, 5), Tile::RED);
+        commands.set_tile_area(ivec2(width-2, 20), ivec2(6, 3), Tile::RED);
+        commands.set_tile_area(ivec2(40, height-1), ivec2(2, 9), Tile::RED);
+        world.apply_commands(commands);
+
+        assert_eq!(count_tiles(&world, Tile::RED), 3*2 + 2*3 + 2);
+        assert_eq!(world.tiles().at_tile_pos(ivec2(width-1, 22)).kind, Tile::RED);
+    }
+
+    #[test]
+    fn set_tile_and_set_tiles_in_chunk_agree() {
+        let mut world = headless_game(1).world;
+        let bump = Bump::new();
+
+        let mut local_tile_poses = Vec::new_in(&bump);
+        local_tile_poses.push(ivec2(3, 4));
+
+        let mut commands = world.commands(&bump);
+        commands.set_tile(ivec2(16*5+1, 16*6+2), Tile::RED);
+        commands.set_tiles_in_chunk(ivec2(5, 6), local_tile_poses, Tile::GREEN);
+        world.apply_commands(commands);
+
+        let tiles = world.tiles();
+        assert_eq!(tiles.at_tile_pos(ivec2(16*5+1, 16*6+2)).kind, Tile::RED);
+        assert_eq!(tiles.at_tile_pos(ivec2(16*5+3, 16*6+4)).kind, Tile::GREEN);
+        assert!(world.dirty_chunks.contains(&ivec2(5, 6)));
+    }
+
+    fn assert_fenced_by_boundary(world: &World) {
+        let tiles = world.tiles();
+
+        let last_x = world.layout.tile_width_i32() - 1;
+        let last_y = world.layout.tile_height_i32() - 1;
+
+        for x in 0..=last_x {
+            assert_eq!(tiles.at_tile_pos(ivec2(x, 0)).kind, Tile::WorldBoundary);
+            assert_eq!(tiles.at_tile_pos(ivec2(x, last_y)).kind, Tile::WorldBoundary);
+        }
+        for y in 0..=last_y {
+            assert_eq!(tiles.at_tile_pos(ivec2(0, y)).kind, Tile::WorldBoundary);
+            assert_eq!(tiles.at_tile_pos(ivec2(last_x, y)).kind, Tile::WorldBoundary);
+        }
+    }
+
+    #[test]
+    fn generated_world_is_fenced_by_boundary() {
+        assert_fenced_by_boundary(&headless_game(1).world);
+    }
+
+    #[test]
+    fn every_world_size_generates_with_landmarks_inside() {
+        let mut game = headless_game(1);
+        let profile = game.world_profiles[0].clone();
+
+        for (name, width, height) in WORLD_SIZES {
+            game.bump.reset();
+            let world = World::new(&game.assets.tile_set, &game.bump, 3, &profile, width, height);
+            let layout = world.layout;
+
+            assert_eq!(world.chunks.len(), width * height, "{}", name);
+            assert_fenced_by_boundary(&world);
+
+            let tiles = world.tiles();
+            assert!(tiles.at_tile_pos(layout.spawn_i32).kind.can_walk_through(), "{}", name);
+            assert!(layout.rail_diagonal_end.x < layout.tile_width_i32() - 1, "{}", name);
+            assert!(layout.elevator_cage.x > 0, "{}", name);
+            assert!(layout.elevator_platform_end_stop.y > 0.0, "{}", name);
+        }
+    }
+
+    #[test]
+    fn deep_barrier_is_pulled_up_on_short_worlds() {
+        let layout = WorldLayout::new(32, 32, 60);
+        assert_eq!(layout.barrier_depth, 28);
+        assert_eq!(WorldLayout::new(64, 64, 13).barrier_pos, ivec2(0, 51*16));
+    }
+
+    #[test]
+    fn same_seed_generates_same_world() {
+        let a = headless_game(7).world;
+        let b = headless_game(7).world;
+        let c = headless_game(8).world;
+
+        let same = |x: &World, y: &World| x.chunks.iter().zip(&y.chunks).all(|(l, r)| l.tiles == r.tiles);
+        assert!(same(&a, &b));
+        assert!(!same(&a, &c));
+    }
+}
@@ -0,0 +1,215 @@
+// INFO: World generation profiles, see data/worldgen/default.txt for the format. The embedded profiles are
+// always there, native builds also read data/worldgen/ at startup so profiles can be tuned without
+// recompiling. A file with the same name as an embedded profile replaces it.
+
+use crate::prelude::*;
+
+use crate::data::{ self, DataError };
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+// INFO: Default goes first, it's what a fresh game starts with.
+const EMBEDDED_PROFILES: [(&str, &str); 3] = [
+    ( DEFAULT_PROFILE, include_str!("../data/worldgen/default.txt") ),
+    ( "rich"         , include_str!("../data/worldgen/rich.txt"   ) ),
+    ( "sparse"       , include_str!("../data/worldgen/sparse.txt" ) ),
+];
+
+#[cfg(not(target_family = "wasm"))]
+const PROFILE_DIR: &str = "data/worldgen";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OreRule {
+    pub depth: i32,
+    pub tile: Tile,
+    pub min: i32,
+    pub max: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HardStoneBand {
+    pub depth: i32,
+    pub min_stride: usize,
+    pub max_stride: usize,
+    pub width: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GemOdds {
+    pub tile: Tile,
+    pub odds: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldGenProfile {
+    pub name: String,
+    pub barrier_depth: i32,
+    // INFO: Sorted by depth.
+    pub ores: Vec<OreRule>,
+    pub hard_stone_bands: Vec<HardStoneBand>,
+    pub gem_roll: i32,
+    pub gems: Vec<GemOdds>,
+}
+
+pub fn parse_profile(name: &str, source: &str) -> Result<WorldGenProfile, DataError> {
+    let mut barrier_depth = None;
+    let mut gem_roll = None;
+    let mut ores = Vec::new();
+    let mut hard_stone_bands = Vec::new();
+    let mut gems = Vec::new();
+
+    for record in data::records(source) {
+        match record.str(0)? {
+        | "barrier_depth" => {
+            record.expect_fields(2)?;
+            let depth = record.parse(1)?;
+            // INFO: Room sits at depth 1. Anything too deep for the chosen world size is pulled up by WorldLayout.
+            if depth < 2 { return Err(record.error("barrier_depth has to be at least 2")); }
+            barrier_depth = Some(depth);
+        },
+        | "ore" => {
+            record.expect_fields(5)?;
+            let rule = OreRule {
+                depth: record.parse(1)?,
+                tile:  record.variant(2, &Tile::VARIANTS)?,
+                min:   record.parse(3)?,
+                max:   record.parse(4)?,
+            };
+            if rule.depth < 0 { return Err(record.error("ore depth can't be negative")); }
+            if rule.max < 0 || rule.min > rule.max { return Err(record.error("ore needs min <= max and max >= 0")); }
+            ores.push(rule);
+        },
+        | "hard_stone" => {
+            record.expect_fields(5)?;
+            let band = HardStoneBand {
+                depth:      record.parse(1)?,
+                min_stride: record.parse(2)?,
+                max_stride: record.parse(3)?,
+                width:      record.parse(4)?,
+            };
+            if band.depth < 1 { return Err(record.error("hard_stone can't be above the room")); }
+            if band.min_stride < 1 || band.min_stride > band.max_stride {
+                return Err(record.error("hard_stone needs 1 <= min <= max"));
+            }
+            if band.width < 1 { return Err(record.error("hard_stone width has to be at least 1")); }
+            hard_stone_bands.push(band);
+        },
+        | "gem_roll" => {
+            record.expect_fields(2)?;
+            let roll = record.parse(1)?;
+            if roll < 1 { return Err(record.error("gem_roll has to be at least 1")); }
+            gem_roll = Some(roll);
+        },
+        | "gem" => {
+            record.expect_fields(3)?;
+            let gem = GemOdds {
+                tile: record.variant(1, &Tile::VARIANTS)?,
+                odds: record.parse(2)?,
+            };
+            if gem.odds < 0 { return Err(record.error("gem odds can't be negative")); }
+            gems.push(gem);
+        },
+        | other => return Err(record.error(format!("unknown key \"{}\"", other))),
+        }
+    }
+
+    let last_line = source.lines().count();
+    let missing = |key: &str| DataError { line: last_line, message: format!("{} is never set", key) };
+
+    let barrier_depth = barrier_depth.ok_or_else(|| missing("barrier_depth"))?;
+    let gem_roll = gem_roll.ok_or_else(|| missing("gem_roll"))?;
+
+    for band in &hard_stone_bands {
+        if band.depth >= barrier_depth {
+            return Err(DataError { line: last_line, message: format!("hard_stone at {} is below the barrier", band.depth) });
+        }
+    }
+    if gems.iter().map(|gem| gem.odds).sum::<i32>() > gem_roll {
+        return Err(DataError { line: last_line, message: "gem odds add up to more than gem_roll".to_string() });
+    }
+
+    ores.sort_by_key(|rule| rule.depth);
+
+    Ok(WorldGenProfile {
+        name: name.to_string(),
+        barrier_depth,
+        ores,
+        hard_stone_bands,
+        gem_roll,
+        gems,
+    })
+}
+
+// INFO: Embedded profiles can't be broken without failing tests, so they are unwrapped. Broken files on
+// disk are skipped with a warning, the game still runs with what it has.
+pub fn load_profiles() -> Vec<WorldGenProfile> {
+    let mut profiles: Vec<WorldGenProfile> = EMBEDDED_PROFILES.iter()
+        .map(|(name, source)| parse_profile(name, source).unwrap_or_else(|err| panic!("{} {}", name, err)))
+        .collect();
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let mut paths: Vec<_> = match std::fs::read_dir(PROFILE_DIR) {
+        | Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        | Err(_) => Vec::new(),
+        };
+        paths.sort();
+
+        for path in paths {
+            if path.extension().is_none_or(|extension| extension != "txt") { continue; }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else { continue };
+            let Ok(source) = std::fs::read_to_string(&path) else { continue };
+
+            match parse_profile(name, &source) {
+            | Ok(profile) => match profiles.iter_mut().find(|existing| existing.name == profile.name) {
+                | Some(existing) => *existing = profile,
+                | None => profiles.push(profile),
+                },
+            | Err(err) => warn!("Skipping world profile {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_profiles_parse() {
+        for (name, source) in EMBEDDED_PROFILES {
+            if let Err(err) = parse_profile(name, source) {
+                panic!("{} {}", name, err);
+            }
+        }
+        assert_eq!(load_profiles()[0].name, DEFAULT_PROFILE);
+    }
+
+    #[test]
+    fn default_profile_keeps_original_values() {
+        let profile = parse_profile(DEFAULT_PROFILE, EMBEDDED_PROFILES[0].1).unwrap();
+        assert_eq!(profile.barrier_depth, 13);
+        assert_eq!(profile.ores.len(), 20);
+        assert_eq!(profile.ores[0], OreRule { depth: 2, tile: Tile::StoneCopperOre, min: 10, max: 16 });
+        assert_eq!(profile.hard_stone_bands[0], HardStoneBand { depth: 4, min_stride: 12, max_stride: 24, width: 3 });
+        assert_eq!(profile.gem_roll, 300);
+        assert_eq!(profile.gems.iter().map(|gem| gem.odds).sum::<i32>(), 41);
+    }
+
+    #[test]
+    fn broken_profiles_are_rejected() {
+        let err = parse_profile("x", "barrier_depth 13\ngem_roll 10\ngem StoneRuby 11\n").err().unwrap();
+        assert!(err.message.contains("gem_roll"), "{}", err);
+
+        let err = parse_profile("x", "barrier_depth 13\ngem_roll 300\nhard_stone 13 1 2 3\n").err().unwrap();
+        assert!(err.message.contains("below the barrier"), "{}", err);
+
+        let err = parse_profile("x", "gem_roll 300\n").err().unwrap();
+        assert!(err.message.contains("barrier_depth"), "{}", err);
+
+        let err = parse_profile("x", "barrier_depth 13\ngem_roll 300\nvein 2 Stone 1 1\n").err().unwrap();
+        assert_eq!(err.line, 3);
+    }
+}